@@ -0,0 +1,24 @@
+CREATE TABLE impersonation_sessions (
+    id UUID PRIMARY KEY,
+    actor_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    ended_at TIMESTAMPTZ
+);
+
+CREATE INDEX impersonation_sessions_actor_id_idx ON impersonation_sessions(actor_id);
+CREATE INDEX impersonation_sessions_user_id_idx ON impersonation_sessions(user_id);
+
+CREATE TABLE impersonation_audit_events (
+    id UUID PRIMARY KEY,
+    session_id UUID NOT NULL REFERENCES impersonation_sessions(id) ON DELETE CASCADE,
+    event TEXT NOT NULL,
+    method TEXT,
+    path TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT impersonation_audit_events_event_check CHECK (event IN ('started', 'ended', 'write'))
+);
+
+CREATE INDEX impersonation_audit_events_session_id_idx ON impersonation_audit_events(session_id);
@@ -0,0 +1,3 @@
+ALTER TABLE refresh_tokens
+    ADD COLUMN ip_address TEXT,
+    ADD COLUMN user_agent TEXT;
@@ -0,0 +1,23 @@
+ALTER TABLE todos
+    ADD COLUMN status_rank SMALLINT GENERATED ALWAYS AS (
+        CASE status
+            WHEN 'todo' THEN 1
+            WHEN 'planned' THEN 2
+            WHEN 'in_progress' THEN 3
+            WHEN 'fixing' THEN 4
+            WHEN 'waiting_test' THEN 5
+            WHEN 'done' THEN 6
+            WHEN 'failed' THEN 7
+            ELSE 8
+        END
+    ) STORED;
+
+DROP INDEX IF EXISTS todos_reporter_id_idx;
+DROP INDEX IF EXISTS todos_assignee_id_idx;
+
+CREATE INDEX todos_reporter_board_idx ON todos(reporter_id, status_rank, position, id);
+CREATE INDEX todos_assignee_board_idx ON todos(assignee_id, status_rank, position, id);
+CREATE INDEX todos_reporter_created_idx ON todos(reporter_id, created_at, id);
+CREATE INDEX todos_assignee_created_idx ON todos(assignee_id, created_at, id);
+CREATE INDEX todos_reporter_updated_idx ON todos(reporter_id, updated_at, id);
+CREATE INDEX todos_assignee_updated_idx ON todos(assignee_id, updated_at, id);
@@ -0,0 +1,15 @@
+CREATE EXTENSION IF NOT EXISTS unaccent;
+
+-- `simple` keeps Vietnamese words intact (no stemming); `unaccent` folds
+-- diacritics so "hoan thanh" matches "hoàn thành".
+CREATE TEXT SEARCH CONFIGURATION todo_search (COPY = simple);
+ALTER TEXT SEARCH CONFIGURATION todo_search
+    ALTER MAPPING FOR asciiword, asciihword, hword_asciipart, word, hword, hword_part
+    WITH unaccent, simple;
+
+ALTER TABLE todos
+    ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+        to_tsvector('todo_search'::regconfig, COALESCE(title, ''))
+    ) STORED;
+
+CREATE INDEX todos_search_vector_idx ON todos USING GIN (search_vector);
@@ -0,0 +1,14 @@
+ALTER TABLE todos
+    ADD COLUMN description TEXT NOT NULL DEFAULT '',
+    ADD COLUMN description_html TEXT NOT NULL DEFAULT '',
+    ADD COLUMN description_excerpt TEXT NOT NULL DEFAULT '';
+
+-- Rebuild the search vector so descriptions are searchable, ranked below titles.
+ALTER TABLE todos DROP COLUMN search_vector;
+ALTER TABLE todos
+    ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('todo_search'::regconfig, COALESCE(title, '')), 'A')
+        || setweight(to_tsvector('todo_search'::regconfig, COALESCE(description, '')), 'B')
+    ) STORED;
+
+CREATE INDEX todos_search_vector_idx ON todos USING GIN (search_vector);
@@ -0,0 +1,11 @@
+ALTER TABLE users
+    ADD COLUMN time_zone TEXT NOT NULL DEFAULT 'UTC';
+
+ALTER TABLE todos
+    ADD COLUMN start_at TIMESTAMPTZ,
+    ADD COLUMN due_at TIMESTAMPTZ,
+    ADD CONSTRAINT todos_start_before_due_check CHECK (start_at IS NULL OR due_at IS NULL OR start_at <= due_at);
+
+-- Matches the `due_at` sort key, which orders todos without a due date last.
+CREATE INDEX todos_reporter_due_idx ON todos(reporter_id, COALESCE(due_at, 'infinity'::timestamptz), id);
+CREATE INDEX todos_assignee_due_idx ON todos(assignee_id, COALESCE(due_at, 'infinity'::timestamptz), id);
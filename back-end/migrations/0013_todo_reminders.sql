@@ -0,0 +1,30 @@
+ALTER TABLE users
+    ADD COLUMN reminder_lead_minutes INTEGER[] NOT NULL DEFAULT '{60}';
+
+-- One row per delivered reminder. The unique key is the claim: whichever
+-- replica inserts it first sends the reminder.
+CREATE TABLE todo_reminders (
+    id UUID PRIMARY KEY,
+    todo_id UUID NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    due_at TIMESTAMPTZ NOT NULL,
+    lead_minutes INTEGER,
+    claimed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ,
+    CONSTRAINT todo_reminders_kind_check CHECK (kind IN ('lead', 'snooze'))
+);
+
+CREATE UNIQUE INDEX todo_reminders_lead_key
+    ON todo_reminders(todo_id, user_id, due_at, lead_minutes)
+    WHERE kind = 'lead';
+
+CREATE TABLE todo_reminder_snoozes (
+    todo_id UUID NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    snoozed_until TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (todo_id, user_id)
+);
+
+CREATE INDEX todo_reminder_snoozes_until_idx ON todo_reminder_snoozes(snoozed_until);
+CREATE INDEX todos_open_due_idx ON todos(due_at) WHERE NOT completed AND due_at IS NOT NULL;
@@ -0,0 +1,20 @@
+-- A recurring todo is a series of occurrences sharing an RRULE. Each
+-- occurrence is an ordinary todo; the next one is created when the current
+-- one is done.
+CREATE TABLE todo_series (
+    id UUID PRIMARY KEY,
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    rrule TEXT NOT NULL,
+    dtstart TIMESTAMPTZ NOT NULL,
+    time_zone TEXT NOT NULL DEFAULT 'UTC',
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE todos
+    ADD COLUMN series_id UUID REFERENCES todo_series(id) ON DELETE SET NULL;
+
+-- At most one occurrence per series and due date, so generating the next
+-- occurrence is idempotent across retries and replicas.
+CREATE UNIQUE INDEX todos_series_due_key ON todos(series_id, due_at) WHERE series_id IS NOT NULL;
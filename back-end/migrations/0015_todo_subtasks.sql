@@ -0,0 +1,5 @@
+ALTER TABLE todos
+    ADD COLUMN parent_id UUID REFERENCES todos(id) ON DELETE CASCADE,
+    ADD CONSTRAINT todos_parent_not_self_check CHECK (parent_id <> id);
+
+CREATE INDEX todos_parent_idx ON todos(parent_id, completed) WHERE parent_id IS NOT NULL;
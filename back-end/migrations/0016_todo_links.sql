@@ -0,0 +1,15 @@
+-- `source_id` blocks / relates to / duplicates `target_id`. `relates_to`
+-- links are stored once with the smaller id as the source.
+CREATE TABLE todo_links (
+    id UUID PRIMARY KEY,
+    source_id UUID NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    target_id UUID NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT todo_links_kind_check CHECK (kind IN ('blocks', 'relates_to', 'duplicate_of')),
+    CONSTRAINT todo_links_not_self_check CHECK (source_id <> target_id),
+    CONSTRAINT todo_links_source_target_kind_key UNIQUE (source_id, target_id, kind)
+);
+
+CREATE INDEX todo_links_target_idx ON todo_links(target_id, kind);
@@ -0,0 +1,22 @@
+-- Each user keeps their own label catalog; a todo can carry labels from
+-- the catalogs of everyone working on it.
+CREATE TABLE labels (
+    id UUID PRIMARY KEY,
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    color TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT labels_color_check CHECK (color ~ '^#[0-9a-f]{6}$')
+);
+
+CREATE UNIQUE INDEX labels_owner_name_key ON labels(owner_id, lower(name));
+
+CREATE TABLE todo_labels (
+    todo_id UUID NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    label_id UUID NOT NULL REFERENCES labels(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (todo_id, label_id)
+);
+
+CREATE INDEX todo_labels_label_idx ON todo_labels(label_id);
@@ -0,0 +1,20 @@
+ALTER TABLE todos
+    ADD COLUMN priority TEXT NOT NULL DEFAULT 'medium',
+    ADD COLUMN estimate DOUBLE PRECISION,
+    ADD CONSTRAINT todos_priority_check CHECK (priority IN ('urgent', 'high', 'medium', 'low')),
+    ADD CONSTRAINT todos_estimate_check CHECK (estimate IS NULL OR (estimate >= 0 AND estimate <= 1000));
+
+ALTER TABLE todos
+    ADD COLUMN priority_rank SMALLINT GENERATED ALWAYS AS (
+        CASE priority
+            WHEN 'urgent' THEN 1
+            WHEN 'high' THEN 2
+            WHEN 'medium' THEN 3
+            WHEN 'low' THEN 4
+            ELSE 5
+        END
+    ) STORED;
+
+-- Matches the `priority` sort key, which falls back to board order.
+CREATE INDEX todos_reporter_priority_idx ON todos(reporter_id, priority_rank, status_rank, position, id);
+CREATE INDEX todos_assignee_priority_idx ON todos(assignee_id, priority_rank, status_rank, position, id);
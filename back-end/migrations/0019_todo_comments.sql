@@ -0,0 +1,30 @@
+CREATE TABLE todo_comments (
+    id UUID PRIMARY KEY,
+    todo_id UUID NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    author_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    body TEXT NOT NULL,
+    body_html TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX todo_comments_todo_idx ON todo_comments(todo_id, created_at, id);
+
+-- Users mentioned in the current body of a comment. Edits only notify users
+-- who were not mentioned before.
+CREATE TABLE todo_comment_mentions (
+    comment_id UUID NOT NULL REFERENCES todo_comments(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    PRIMARY KEY (comment_id, user_id)
+);
+
+-- Watchers receive comment events and may join the discussion alongside
+-- the reporter and assignee.
+CREATE TABLE todo_watchers (
+    todo_id UUID NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (todo_id, user_id)
+);
+
+CREATE INDEX todo_watchers_user_idx ON todo_watchers(user_id);
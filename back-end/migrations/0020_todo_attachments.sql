@@ -0,0 +1,34 @@
+CREATE TABLE todo_attachments (
+    id UUID PRIMARY KEY,
+    todo_id UUID NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    uploader_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    file_name TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+    sha256 TEXT NOT NULL,
+    storage_key TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX todo_attachments_todo_idx ON todo_attachments(todo_id, created_at, id);
+
+-- Stored objects whose metadata row is gone. Rows are queued by the trigger
+-- below, so todos removed through cascades (subtasks, deleted users) release
+-- their files too; the cleanup job deletes the objects and then the rows.
+CREATE TABLE attachment_deletions (
+    storage_key TEXT PRIMARY KEY,
+    queued_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE FUNCTION queue_attachment_deletion() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO attachment_deletions (storage_key)
+    VALUES (OLD.storage_key)
+    ON CONFLICT DO NOTHING;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER todo_attachments_queue_deletion
+AFTER DELETE ON todo_attachments
+FOR EACH ROW EXECUTE FUNCTION queue_attachment_deletion();
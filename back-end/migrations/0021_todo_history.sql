@@ -0,0 +1,15 @@
+-- One row per change to a todo. There is no foreign key to todos so the
+-- history of deleted todos stays queryable.
+CREATE TABLE todo_history (
+    id BIGSERIAL PRIMARY KEY,
+    todo_id UUID NOT NULL,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action TEXT NOT NULL CHECK (action IN ('created', 'updated', 'reordered', 'deleted')),
+    -- { "<field>": { "from": <old value>, "to": <new value> } }
+    changes JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX todo_history_todo_idx ON todo_history(todo_id, id DESC);
+CREATE INDEX todo_history_actor_idx ON todo_history(actor_id, id DESC);
+CREATE INDEX todo_history_created_idx ON todo_history(created_at);
@@ -0,0 +1,9 @@
+-- Deleting a todo moves it (and its subtasks, with the same timestamp) to the
+-- trash; rows are removed for good by a purge or the retention job.
+ALTER TABLE todos ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX todos_trash_idx ON todos(deleted_at) WHERE deleted_at IS NOT NULL;
+
+ALTER TABLE todo_history DROP CONSTRAINT todo_history_action_check;
+ALTER TABLE todo_history ADD CONSTRAINT todo_history_action_check
+    CHECK (action IN ('created', 'updated', 'reordered', 'deleted', 'restored', 'purged'));
@@ -0,0 +1,12 @@
+-- The inverse of each user's recent board mutations: the tracked fields of
+-- every affected todo before and after the change. Undo checks that the
+-- todos still match `after` and writes `before` back.
+CREATE TABLE todo_undo_operations (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('update', 'reorder', 'delete')),
+    steps JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX todo_undo_operations_user_idx ON todo_undo_operations(user_id, id DESC);
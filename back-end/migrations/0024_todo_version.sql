@@ -0,0 +1,14 @@
+-- Every write to a todo bumps its version, which the API returns as the
+-- ETag for If-Match / If-None-Match.
+ALTER TABLE todos ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+
+CREATE FUNCTION bump_todo_version() RETURNS trigger AS $$
+BEGIN
+    NEW.version := OLD.version + 1;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER todos_bump_version
+BEFORE UPDATE ON todos
+FOR EACH ROW EXECUTE FUNCTION bump_todo_version();
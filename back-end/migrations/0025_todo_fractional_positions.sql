@@ -0,0 +1,22 @@
+-- Board positions become fractional: a move writes a value between its new
+-- neighbours instead of renumbering the column. A column is a status for one
+-- assignee (unassigned todos share one), and live todos in it never share a
+-- position. The constraint is checked at the end of each statement, so a
+-- batch reorder may swap positions within one UPDATE.
+ALTER TABLE todos ALTER COLUMN position TYPE DOUBLE PRECISION;
+
+UPDATE todos SET position = ordered.rank - 1
+FROM (
+    SELECT id, ROW_NUMBER() OVER (
+        PARTITION BY assignee_id, status ORDER BY position, created_at, id
+    ) AS rank
+    FROM todos
+    WHERE deleted_at IS NULL
+) ordered
+WHERE todos.id = ordered.id AND todos.position <> ordered.rank - 1;
+
+ALTER TABLE todos ADD CONSTRAINT todos_column_position_excl EXCLUDE USING btree (
+    (COALESCE(assignee_id, '00000000-0000-0000-0000-000000000000'::uuid)) WITH =,
+    status WITH =,
+    position WITH =
+) WHERE (deleted_at IS NULL) DEFERRABLE INITIALLY IMMEDIATE;
@@ -0,0 +1,7 @@
+-- A reminder whose email failed keeps its claim and is retried with backoff
+-- until it is delivered or runs out of attempts.
+ALTER TABLE todo_reminders
+    ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN retry_at TIMESTAMPTZ;
+
+CREATE INDEX todo_reminders_retry_idx ON todo_reminders(retry_at) WHERE retry_at IS NOT NULL;
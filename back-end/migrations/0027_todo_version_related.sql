@@ -0,0 +1,102 @@
+-- Todo responses also show data from related rows: open blockers, subtask
+-- counts, labels and the series' recurrence. Changes there bump the versions
+-- of the todos that show them, so ETags never hide a stale body.
+CREATE FUNCTION bump_todo_versions(ids UUID[]) RETURNS void AS $$
+    UPDATE todos SET version = version WHERE id = ANY(ids);
+$$ LANGUAGE sql;
+
+-- A subtask changes its parent's roll-up; a blocker's title, status or
+-- completion changes the `blocked_by` of the todos it blocks.
+CREATE FUNCTION bump_related_todo_versions() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        IF NEW.parent_id IS NOT NULL THEN
+            PERFORM bump_todo_versions(ARRAY[NEW.parent_id]);
+        END IF;
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'DELETE' THEN
+        IF OLD.parent_id IS NOT NULL THEN
+            PERFORM bump_todo_versions(ARRAY[OLD.parent_id]);
+        END IF;
+        RETURN NULL;
+    END IF;
+
+    IF NEW.parent_id IS DISTINCT FROM OLD.parent_id
+        OR NEW.completed IS DISTINCT FROM OLD.completed
+        OR NEW.deleted_at IS DISTINCT FROM OLD.deleted_at THEN
+        PERFORM bump_todo_versions(array_remove(ARRAY[OLD.parent_id, NEW.parent_id], NULL));
+    END IF;
+    IF NEW.title IS DISTINCT FROM OLD.title
+        OR NEW.status IS DISTINCT FROM OLD.status
+        OR NEW.completed IS DISTINCT FROM OLD.completed
+        OR NEW.deleted_at IS DISTINCT FROM OLD.deleted_at THEN
+        PERFORM bump_todo_versions(ARRAY(
+            SELECT target_id FROM todo_links WHERE source_id = NEW.id AND kind = 'blocks'
+        ));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER todos_bump_related_versions
+AFTER INSERT OR DELETE OR UPDATE OF parent_id, completed, deleted_at, title, status ON todos
+FOR EACH ROW EXECUTE FUNCTION bump_related_todo_versions();
+
+CREATE FUNCTION bump_linked_todo_version() RETURNS trigger AS $$
+DECLARE
+    link todo_links;
+BEGIN
+    link := CASE WHEN TG_OP = 'DELETE' THEN OLD ELSE NEW END;
+    IF link.kind = 'blocks' THEN
+        PERFORM bump_todo_versions(ARRAY[link.target_id]);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER todo_links_bump_version
+AFTER INSERT OR DELETE ON todo_links
+FOR EACH ROW EXECUTE FUNCTION bump_linked_todo_version();
+
+CREATE FUNCTION bump_labelled_todo_version() RETURNS trigger AS $$
+BEGIN
+    PERFORM bump_todo_versions(ARRAY[CASE WHEN TG_OP = 'DELETE' THEN OLD.todo_id ELSE NEW.todo_id END]);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER todo_labels_bump_version
+AFTER INSERT OR DELETE ON todo_labels
+FOR EACH ROW EXECUTE FUNCTION bump_labelled_todo_version();
+
+CREATE FUNCTION bump_label_todo_versions() RETURNS trigger AS $$
+BEGIN
+    IF NEW.name IS DISTINCT FROM OLD.name OR NEW.color IS DISTINCT FROM OLD.color THEN
+        PERFORM bump_todo_versions(ARRAY(
+            SELECT todo_id FROM todo_labels WHERE label_id = NEW.id
+        ));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER labels_bump_todo_versions
+AFTER UPDATE OF name, color ON labels
+FOR EACH ROW EXECUTE FUNCTION bump_label_todo_versions();
+
+CREATE FUNCTION bump_series_todo_versions() RETURNS trigger AS $$
+BEGIN
+    IF NEW.active IS DISTINCT FROM OLD.active OR NEW.rrule IS DISTINCT FROM OLD.rrule THEN
+        PERFORM bump_todo_versions(ARRAY(
+            SELECT id FROM todos WHERE series_id = NEW.id
+        ));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER todo_series_bump_todo_versions
+AFTER UPDATE OF active, rrule ON todo_series
+FOR EACH ROW EXECUTE FUNCTION bump_series_todo_versions();
@@ -0,0 +1,42 @@
+-- `blocked_by` hides blockers that not everyone on the todo can see, so a
+-- blocker's people now change what the todos it blocks show.
+CREATE OR REPLACE FUNCTION bump_related_todo_versions() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        IF NEW.parent_id IS NOT NULL THEN
+            PERFORM bump_todo_versions(ARRAY[NEW.parent_id]);
+        END IF;
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'DELETE' THEN
+        IF OLD.parent_id IS NOT NULL THEN
+            PERFORM bump_todo_versions(ARRAY[OLD.parent_id]);
+        END IF;
+        RETURN NULL;
+    END IF;
+
+    IF NEW.parent_id IS DISTINCT FROM OLD.parent_id
+        OR NEW.completed IS DISTINCT FROM OLD.completed
+        OR NEW.deleted_at IS DISTINCT FROM OLD.deleted_at THEN
+        PERFORM bump_todo_versions(array_remove(ARRAY[OLD.parent_id, NEW.parent_id], NULL));
+    END IF;
+    IF NEW.title IS DISTINCT FROM OLD.title
+        OR NEW.status IS DISTINCT FROM OLD.status
+        OR NEW.completed IS DISTINCT FROM OLD.completed
+        OR NEW.deleted_at IS DISTINCT FROM OLD.deleted_at
+        OR NEW.reporter_id IS DISTINCT FROM OLD.reporter_id
+        OR NEW.assignee_id IS DISTINCT FROM OLD.assignee_id THEN
+        PERFORM bump_todo_versions(ARRAY(
+            SELECT target_id FROM todo_links WHERE source_id = NEW.id AND kind = 'blocks'
+        ));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER todos_bump_related_versions ON todos;
+
+CREATE TRIGGER todos_bump_related_versions
+AFTER INSERT OR DELETE OR UPDATE OF parent_id, completed, deleted_at, title, status, reporter_id, assignee_id ON todos
+FOR EACH ROW EXECUTE FUNCTION bump_related_todo_versions();
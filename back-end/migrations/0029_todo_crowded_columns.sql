@@ -0,0 +1,23 @@
+-- Columns whose positions got too close, queued by the writes that crowded
+-- them so the rebalance job does not have to scan every column.
+CREATE TABLE todo_crowded_columns (
+    assignee_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    status TEXT NOT NULL,
+    queued_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX todo_crowded_columns_key ON todo_crowded_columns (
+    (COALESCE(assignee_id, '00000000-0000-0000-0000-000000000000'::uuid)),
+    status
+);
+
+CREATE INDEX todo_crowded_columns_queued_idx ON todo_crowded_columns(queued_at);
+
+INSERT INTO todo_crowded_columns (assignee_id, status)
+SELECT DISTINCT assignee_id, status FROM (
+    SELECT assignee_id, status,
+        position - LAG(position) OVER (PARTITION BY assignee_id, status ORDER BY position) AS gap
+    FROM todos
+    WHERE deleted_at IS NULL
+) gaps
+WHERE gap < 1e-6;
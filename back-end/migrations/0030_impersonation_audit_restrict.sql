@@ -0,0 +1,13 @@
+-- Impersonation sessions and their events are the audit trail; deleting a
+-- user (or a session) must not silently erase it.
+ALTER TABLE impersonation_sessions DROP CONSTRAINT impersonation_sessions_actor_id_fkey;
+ALTER TABLE impersonation_sessions ADD CONSTRAINT impersonation_sessions_actor_id_fkey
+    FOREIGN KEY (actor_id) REFERENCES users(id) ON DELETE RESTRICT;
+
+ALTER TABLE impersonation_sessions DROP CONSTRAINT impersonation_sessions_user_id_fkey;
+ALTER TABLE impersonation_sessions ADD CONSTRAINT impersonation_sessions_user_id_fkey
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE RESTRICT;
+
+ALTER TABLE impersonation_audit_events DROP CONSTRAINT impersonation_audit_events_session_id_fkey;
+ALTER TABLE impersonation_audit_events ADD CONSTRAINT impersonation_audit_events_session_id_fkey
+    FOREIGN KEY (session_id) REFERENCES impersonation_sessions(id) ON DELETE RESTRICT;
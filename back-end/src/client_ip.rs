@@ -0,0 +1,271 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{HeaderMap, header, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+use ipnet::IpNet;
+use tower_governor::{GovernorError, key_extractor::KeyExtractor};
+
+use crate::state::AppState;
+
+/// Client address resolved once per request by [`resolve_client_ip`] and
+/// shared by rate limiting, request logging and session metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+pub async fn resolve_client_ip(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    if let Some(peer) = peer {
+        let client_ip = resolve(peer, request.headers(), &state.trusted_proxies);
+        request.extensions_mut().insert(ClientIp(client_ip));
+    }
+
+    next.run(request).await
+}
+
+/// Walks the forwarding chain from the nearest hop outwards and returns the
+/// first address that is not a trusted proxy. Forwarding headers are ignored
+/// entirely unless the direct peer is trusted.
+pub fn resolve(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpNet]) -> IpAddr {
+    if !is_trusted(peer, trusted_proxies) {
+        return peer;
+    }
+
+    let mut client = peer;
+    for hop in forwarding_chain(headers).into_iter().rev() {
+        let Some(hop) = hop else {
+            break;
+        };
+        client = hop;
+        if !is_trusted(hop, trusted_proxies) {
+            break;
+        }
+    }
+    client
+}
+
+fn is_trusted(ip: IpAddr, trusted_proxies: &[IpNet]) -> bool {
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        IpAddr::V4(_) => ip,
+    };
+    trusted_proxies.iter().any(|net| net.contains(&ip))
+}
+
+/// Returns the hops listed in `Forwarded` (preferred) or `X-Forwarded-For`,
+/// client-most first. Entries that are not IP addresses are kept as `None`
+/// so the walk stops there instead of skipping over them.
+fn forwarding_chain(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let forwarded: Vec<&str> = headers
+        .get_all(header::FORWARDED)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    if !forwarded.is_empty() {
+        return forwarded
+            .iter()
+            .flat_map(|value| value.split(','))
+            .filter_map(|element| {
+                element.split(';').find_map(|pair| {
+                    let (key, value) = pair.trim().split_once('=')?;
+                    key.eq_ignore_ascii_case("for").then(|| parse_node(value))
+                })
+            })
+            .collect();
+    }
+
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(parse_node)
+        .collect()
+}
+
+fn parse_node(raw: &str) -> Option<IpAddr> {
+    let node = raw.trim().trim_matches('"');
+    if let Ok(ip) = node.parse() {
+        return Some(ip);
+    }
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    node.parse::<SocketAddr>().ok().map(|addr| addr.ip())
+}
+
+pub fn parse_trusted_proxies(raw: &str) -> Result<Vec<IpNet>, String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .parse::<IpNet>()
+                .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| format!("Invalid trusted proxy: {entry}"))
+        })
+        .collect()
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let client_ip = parts
+            .extensions
+            .get::<ClientIp>()
+            .copied()
+            .unwrap_or(ClientIp(IpAddr::from([0, 0, 0, 0])));
+        Ok(client_ip)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionMetadata {
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for SessionMetadata
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(512).collect());
+        Ok(SessionMetadata {
+            ip_address: parts.extensions.get::<ClientIp>().map(|ClientIp(ip)| *ip),
+            user_agent,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIpKeyExtractor;
+
+impl KeyExtractor for ClientIpKeyExtractor {
+    type Key = IpAddr;
+
+    fn extract<T>(&self, req: &axum::http::Request<T>) -> Result<Self::Key, GovernorError> {
+        req.extensions()
+            .get::<ClientIp>()
+            .map(|ClientIp(ip)| *ip)
+            .ok_or(GovernorError::UnableToExtractKey)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn trusted() -> Vec<IpNet> {
+        parse_trusted_proxies("10.0.0.0/8, 127.0.0.1").expect("trusted proxies")
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().expect("ip")
+    }
+
+    #[test]
+    fn ignores_forwarding_headers_from_untrusted_peer() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("1.2.3.4"));
+
+        assert_eq!(
+            resolve(ip("203.0.113.9"), &headers, &trusted()),
+            ip("203.0.113.9")
+        );
+    }
+
+    #[test]
+    fn walks_x_forwarded_for_through_trusted_hops() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("6.6.6.6, 198.51.100.7, 10.1.2.3"),
+        );
+
+        assert_eq!(
+            resolve(ip("127.0.0.1"), &headers, &trusted()),
+            ip("198.51.100.7")
+        );
+    }
+
+    #[test]
+    fn prefers_forwarded_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::FORWARDED,
+            HeaderValue::from_static("for=\"[2001:db8::1]:4711\";proto=https, for=10.0.0.2"),
+        );
+        headers.insert("x-forwarded-for", HeaderValue::from_static("6.6.6.6"));
+
+        assert_eq!(
+            resolve(ip("10.0.0.1"), &headers, &trusted()),
+            ip("2001:db8::1")
+        );
+    }
+
+    #[test]
+    fn stops_at_unparseable_hop() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("6.6.6.6, unknown, 10.0.0.5"),
+        );
+
+        assert_eq!(
+            resolve(ip("10.0.0.1"), &headers, &trusted()),
+            ip("10.0.0.5")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_peer_without_headers() {
+        assert_eq!(
+            resolve(ip("10.0.0.1"), &HeaderMap::new(), &trusted()),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn treats_ipv4_mapped_peers_as_ipv4() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("198.51.100.7"));
+
+        assert_eq!(
+            resolve(ip("::ffff:127.0.0.1"), &headers, &trusted()),
+            ip("198.51.100.7")
+        );
+    }
+
+    #[test]
+    fn parse_trusted_proxies_rejects_garbage() {
+        assert!(parse_trusted_proxies("10.0.0.0/8,not-a-cidr").is_err());
+        assert!(parse_trusted_proxies("").expect("empty").is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+};
+
+use crate::{
+    controllers::{extractors::AuthUser, todo_controller::cursor_headers},
+    error::AppError,
+    models::{
+        admin::{ImpersonateRequest, ImpersonationResponse, ImpersonationSessionResponse},
+        history::{ActivityQuery, TodoHistoryEntry},
+    },
+    services::{history_service, impersonation_service},
+    state::AppState,
+};
+
+#[utoipa::path(
+    post,
+    path = "/admin/impersonations",
+    tag = "admin",
+    request_body = ImpersonateRequest,
+    responses(
+        (status = 201, body = ImpersonationResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 403, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn start_impersonation(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<ImpersonateRequest>,
+) -> Result<(StatusCode, Json<ImpersonationResponse>), AppError> {
+    user.require_admin()?;
+    user.require_not_impersonated()?;
+    let response = impersonation_service::start(&state, user.user_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/impersonations/end",
+    tag = "admin",
+    responses(
+        (status = 204),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 401, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn end_impersonation(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<StatusCode, AppError> {
+    let (Some(actor_id), Some(session_id)) = (user.actor_id, user.impersonation_session_id) else {
+        return Err(AppError::BadRequest(
+            "token is not an impersonation token".to_string(),
+        ));
+    };
+    impersonation_service::end(&state, actor_id, user.user_id, session_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/impersonations",
+    tag = "admin",
+    responses(
+        (status = 200, body = [ImpersonationSessionResponse]),
+        (status = 403, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn list_impersonations(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<ImpersonationSessionResponse>>, AppError> {
+    user.require_admin()?;
+    user.require_not_impersonated()?;
+    let sessions = impersonation_service::list_sessions(&state).await?;
+    Ok(Json(sessions))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/activity",
+    tag = "admin",
+    params(ActivityQuery),
+    responses(
+        (
+            status = 200,
+            body = [TodoHistoryEntry],
+            headers(
+                ("x-next-cursor" = String, description = "Cursor for the next page; absent on the last page")
+            )
+        ),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 403, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn list_activity(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(query): Query<ActivityQuery>,
+) -> Result<(HeaderMap, Json<Vec<TodoHistoryEntry>>), AppError> {
+    user.require_admin()?;
+    user.require_not_impersonated()?;
+    let page = history_service::list_activity(&state, query).await?;
+    Ok((cursor_headers(page.next_cursor), Json(page.items)))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/admin/impersonations",
+            get(list_impersonations).post(start_impersonation),
+        )
+        .route("/admin/impersonations/end", post(end_impersonation))
+        .route("/admin/activity", get(list_activity))
+}
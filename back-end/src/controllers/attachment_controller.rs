@@ -0,0 +1,206 @@
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{
+        DefaultBodyLimit, Multipart, Path, Query, State,
+        multipart::{Field, MultipartError},
+    },
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    routing::{delete, get},
+};
+use hyper::body::Bytes;
+use uuid::Uuid;
+
+use crate::{
+    controllers::extractors::AuthUser,
+    error::AppError,
+    models::attachment::{AttachmentContentQuery, TodoAttachmentResponse},
+    services::{
+        attachment_service::{self, UploadedFile},
+        todo_service,
+    },
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/todos/{id}/attachments",
+    tag = "attachments",
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 200, body = [TodoAttachmentResponse]),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn list_attachments(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Json<Vec<TodoAttachmentResponse>>, AppError> {
+    let attachments = attachment_service::list_attachments(&state, user.user_id, todo_id).await?;
+    Ok(Json(attachments))
+}
+
+fn multipart_error(err: MultipartError) -> AppError {
+    AppError::BadRequest(err.body_text())
+}
+
+/// Reads a whole field, failing as soon as it grows past `max_bytes`.
+async fn read_field(field: &mut Field<'_>, max_bytes: usize) -> Result<Vec<u8>, AppError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(AppError::PayloadTooLarge(format!(
+                "each field must be at most {max_bytes} bytes"
+            )));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+#[utoipa::path(
+    post,
+    path = "/todos/{id}/attachments",
+    tag = "attachments",
+    request_body(content = crate::models::attachment::AttachmentUpload, content_type = "multipart/form-data"),
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 201, body = TodoAttachmentResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse),
+        (status = 413, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn upload_attachment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<TodoAttachmentResponse>), AppError> {
+    // Check the todo before reading any of the body, so users who cannot
+    // see it get a 404 without uploading anything.
+    todo_service::get_todo(&state, user.user_id, todo_id).await?;
+
+    let max_bytes = state.attachments.max_bytes;
+    let mut upload = None;
+
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() != Some("file") {
+            read_field(&mut field, max_bytes).await?;
+            continue;
+        }
+        if upload.is_some() {
+            return Err(AppError::BadRequest(
+                "upload one file per request".to_string(),
+            ));
+        }
+
+        let file_name = field.file_name().map(str::to_string);
+        let content_type = field.content_type().map(str::to_string);
+        let bytes = read_field(&mut field, max_bytes).await?;
+        upload = Some(UploadedFile {
+            file_name,
+            content_type,
+            bytes: Bytes::from(bytes),
+        });
+    }
+
+    let upload = upload.ok_or_else(|| AppError::BadRequest("file is required".to_string()))?;
+    let attachment =
+        attachment_service::upload_attachment(&state, user.user_id, todo_id, upload).await?;
+    Ok((StatusCode::CREATED, Json(attachment)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/todos/{id}/attachments/{attachment_id}/content",
+    tag = "attachments",
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("attachment_id" = String, Path, description = "Attachment ID"),
+        AttachmentContentQuery
+    ),
+    responses(
+        (status = 200, description = "The stored file", content_type = "application/octet-stream"),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn download_attachment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((todo_id, attachment_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<AttachmentContentQuery>,
+) -> Result<(HeaderMap, Body), AppError> {
+    let (attachment, body) =
+        attachment_service::open_attachment(&state, user.user_id, todo_id, attachment_id).await?;
+
+    let inline = query.inline.unwrap_or(false) && attachment.content_type.starts_with("image/");
+    let mut headers = HeaderMap::new();
+    let content_type = HeaderValue::from_str(&attachment.content_type)
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+    headers.insert(header::CONTENT_TYPE, content_type);
+    headers.insert(
+        header::CONTENT_LENGTH,
+        HeaderValue::from(attachment.size_bytes),
+    );
+    let disposition = attachment_service::content_disposition(&attachment.file_name, inline);
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    headers.insert(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static("default-src 'none'; sandbox"),
+    );
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, no-cache"),
+    );
+    Ok((headers, body))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/todos/{id}/attachments/{attachment_id}",
+    tag = "attachments",
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("attachment_id" = String, Path, description = "Attachment ID")
+    ),
+    responses(
+        (status = 204),
+        (status = 403, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn delete_attachment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((todo_id, attachment_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    attachment_service::delete_attachment(&state, user.user_id, todo_id, attachment_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/todos/:id/attachments",
+            // Uploads are bounded by ATTACHMENT_MAX_BYTES while streaming.
+            get(list_attachments)
+                .post(upload_attachment)
+                .layer(DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/todos/:id/attachments/:attachment_id",
+            delete(delete_attachment),
+        )
+        .route(
+            "/todos/:id/attachments/:attachment_id/content",
+            get(download_attachment),
+        )
+}
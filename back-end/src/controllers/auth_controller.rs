@@ -1,14 +1,21 @@
-use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    routing::{post, put},
+};
 use axum_extra::extract::CookieJar;
-use axum_extra::extract::cookie::{Cookie, SameSite};
+use axum_extra::extract::cookie::Cookie;
 use time::Duration;
 
 use crate::{
+    client_ip::SessionMetadata,
+    controllers::extractors::{AuthUser, SameOriginRequest},
     error::AppError,
     locale::Language,
     models::auth::{
-        AuthResponse, ForgotPasswordRequest, LoginRequest, MessageResponse, RefreshRequest,
-        RegisterRequest, ResetPasswordRequest,
+        AuthResponse, ChangePasswordRequest, ForgotPasswordRequest, LoginRequest, MessageResponse,
+        RefreshRequest, RegisterRequest, ResetPasswordRequest,
     },
     services::auth_service,
     state::AppState,
@@ -27,9 +34,12 @@ use crate::{
 pub async fn register(
     State(state): State<AppState>,
     jar: CookieJar,
+    session: SessionMetadata,
+    language: Language,
     Json(payload): Json<RegisterRequest>,
 ) -> Result<(StatusCode, CookieJar, Json<AuthResponse>), AppError> {
-    let (response, refresh_token) = auth_service::register(&state, payload).await?;
+    let (response, refresh_token) =
+        auth_service::register(&state, payload, &session, language).await?;
     let jar = jar.add(build_refresh_cookie(&state, refresh_token));
     Ok((StatusCode::CREATED, jar, Json(response)))
 }
@@ -47,9 +57,10 @@ pub async fn register(
 pub async fn login(
     State(state): State<AppState>,
     jar: CookieJar,
+    session: SessionMetadata,
     Json(payload): Json<LoginRequest>,
 ) -> Result<(CookieJar, Json<AuthResponse>), AppError> {
-    let (response, refresh_token) = auth_service::login(&state, payload).await?;
+    let (response, refresh_token) = auth_service::login(&state, payload, &session).await?;
     let jar = jar.add(build_refresh_cookie(&state, refresh_token));
     Ok((jar, Json(response)))
 }
@@ -61,16 +72,20 @@ pub async fn login(
     request_body = RefreshRequest,
     responses(
         (status = 200, body = AuthResponse),
-        (status = 401, body = crate::error::ErrorResponse)
+        (status = 401, body = crate::error::ErrorResponse),
+        (status = 403, body = crate::error::ErrorResponse)
     )
 )]
 pub async fn refresh(
     State(state): State<AppState>,
+    _same_origin: SameOriginRequest,
     jar: CookieJar,
+    session: SessionMetadata,
     payload: Option<Json<RefreshRequest>>,
 ) -> Result<(CookieJar, Json<AuthResponse>), AppError> {
     let refresh_token = extract_refresh_token(&state, &jar, payload)?;
-    let (response, new_refresh_token) = auth_service::refresh(&state, &refresh_token).await?;
+    let (response, new_refresh_token) =
+        auth_service::refresh(&state, &refresh_token, &session).await?;
     let jar = jar.add(build_refresh_cookie(&state, new_refresh_token));
     Ok((jar, Json(response)))
 }
@@ -82,11 +97,13 @@ pub async fn refresh(
     request_body = RefreshRequest,
     responses(
         (status = 204),
-        (status = 401, body = crate::error::ErrorResponse)
+        (status = 401, body = crate::error::ErrorResponse),
+        (status = 403, body = crate::error::ErrorResponse)
     )
 )]
 pub async fn logout(
     State(state): State<AppState>,
+    _same_origin: SameOriginRequest,
     jar: CookieJar,
     payload: Option<Json<RefreshRequest>>,
 ) -> Result<(CookieJar, StatusCode), AppError> {
@@ -135,7 +152,32 @@ pub async fn reset(
     language: Language,
     Json(payload): Json<ResetPasswordRequest>,
 ) -> Result<Json<MessageResponse>, AppError> {
-    auth_service::reset_password(&state, payload).await?;
+    auth_service::reset_password(&state, payload, language).await?;
+    Ok(Json(MessageResponse {
+        message: language.message("Password has been updated.", "Mật khẩu đã được cập nhật."),
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/auth/password",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, body = MessageResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 401, body = crate::error::ErrorResponse),
+        (status = 403, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    user: AuthUser,
+    language: Language,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<MessageResponse>, AppError> {
+    user.require_not_impersonated()?;
+    auth_service::change_password(&state, user.user_id, payload, language).await?;
     Ok(Json(MessageResponse {
         message: language.message("Password has been updated.", "Mật khẩu đã được cập nhật."),
     }))
@@ -149,29 +191,28 @@ pub fn routes() -> Router<AppState> {
         .route("/auth/logout", post(logout))
         .route("/auth/forgot", post(forgot))
         .route("/auth/reset", post(reset))
+        .route("/auth/password", put(change_password))
 }
 
 fn build_refresh_cookie(state: &AppState, refresh_token: String) -> Cookie<'static> {
     let max_age = Duration::days(state.jwt.refresh_ttl_days);
-    let mut builder = Cookie::build((state.refresh_cookie_name.clone(), refresh_token))
-        .path("/api/auth")
-        .http_only(true)
-        .same_site(SameSite::Lax)
-        .max_age(max_age);
-    if state.refresh_cookie_secure {
-        builder = builder.secure(true);
-    }
-    builder.build()
+    refresh_cookie(state, refresh_token, max_age)
 }
 
 fn clear_refresh_cookie(state: &AppState) -> Cookie<'static> {
-    let mut builder = Cookie::build((state.refresh_cookie_name.clone(), ""))
-        .path("/api/auth")
+    refresh_cookie(state, String::new(), Duration::ZERO)
+}
+
+fn refresh_cookie(state: &AppState, value: String, max_age: Duration) -> Cookie<'static> {
+    let config = &state.refresh_cookie;
+    let mut builder = Cookie::build((config.name.clone(), value))
+        .path(config.path.clone())
         .http_only(true)
-        .same_site(SameSite::Lax)
-        .max_age(Duration::ZERO);
-    if state.refresh_cookie_secure {
-        builder = builder.secure(true);
+        .same_site(config.same_site)
+        .secure(config.secure)
+        .max_age(max_age);
+    if let Some(domain) = &config.domain {
+        builder = builder.domain(domain.clone());
     }
     builder.build()
 }
@@ -189,7 +230,7 @@ fn extract_refresh_token(
         }
     }
 
-    jar.get(&state.refresh_cookie_name)
+    jar.get(&state.refresh_cookie.name)
         .map(|cookie| cookie.value().to_string())
         .ok_or(AppError::Unauthorized)
 }
@@ -0,0 +1,204 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, put},
+};
+use uuid::Uuid;
+
+use crate::{
+    controllers::extractors::AuthUser,
+    error::AppError,
+    models::{
+        auth::Role,
+        comment::{CommentRequest, TodoCommentResponse, TodoWatcherResponse},
+    },
+    services::comment_service,
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/todos/{id}/comments",
+    tag = "comments",
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 200, body = [TodoCommentResponse]),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn list_comments(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Json<Vec<TodoCommentResponse>>, AppError> {
+    let comments = comment_service::list_comments(&state, user.user_id, todo_id).await?;
+    Ok(Json(comments))
+}
+
+#[utoipa::path(
+    post,
+    path = "/todos/{id}/comments",
+    tag = "comments",
+    request_body = CommentRequest,
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 201, body = TodoCommentResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn create_comment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    Json(payload): Json<CommentRequest>,
+) -> Result<(StatusCode, Json<TodoCommentResponse>), AppError> {
+    let comment = comment_service::create_comment(&state, user.user_id, todo_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/todos/{id}/comments/{comment_id}",
+    tag = "comments",
+    request_body = CommentRequest,
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("comment_id" = String, Path, description = "Comment ID")
+    ),
+    responses(
+        (status = 200, body = TodoCommentResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 403, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn update_comment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((todo_id, comment_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<CommentRequest>,
+) -> Result<Json<TodoCommentResponse>, AppError> {
+    let comment = comment_service::update_comment(
+        &state,
+        user.user_id,
+        user.role == Role::Admin,
+        todo_id,
+        comment_id,
+        payload,
+    )
+    .await?;
+    Ok(Json(comment))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/todos/{id}/comments/{comment_id}",
+    tag = "comments",
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("comment_id" = String, Path, description = "Comment ID")
+    ),
+    responses(
+        (status = 204),
+        (status = 403, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((todo_id, comment_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    comment_service::delete_comment(
+        &state,
+        user.user_id,
+        user.role == Role::Admin,
+        todo_id,
+        comment_id,
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/todos/{id}/watchers",
+    tag = "comments",
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 200, body = [TodoWatcherResponse]),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn list_watchers(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Json<Vec<TodoWatcherResponse>>, AppError> {
+    let watchers = comment_service::list_watchers(&state, user.user_id, todo_id).await?;
+    Ok(Json(watchers))
+}
+
+#[utoipa::path(
+    put,
+    path = "/todos/{id}/watchers/{user_id}",
+    tag = "comments",
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("user_id" = String, Path, description = "User to add as a watcher")
+    ),
+    responses(
+        (status = 204),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn add_watcher(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((todo_id, watcher_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    comment_service::add_watcher(&state, user.user_id, todo_id, watcher_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/todos/{id}/watchers/{user_id}",
+    tag = "comments",
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("user_id" = String, Path, description = "Watcher to remove")
+    ),
+    responses(
+        (status = 204),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn remove_watcher(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((todo_id, watcher_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    comment_service::remove_watcher(&state, user.user_id, todo_id, watcher_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/todos/:id/comments",
+            get(list_comments).post(create_comment),
+        )
+        .route(
+            "/todos/:id/comments/:comment_id",
+            put(update_comment).delete(delete_comment),
+        )
+        .route("/todos/:id/watchers", get(list_watchers))
+        .route(
+            "/todos/:id/watchers/:user_id",
+            put(add_watcher).delete(remove_watcher),
+        )
+}
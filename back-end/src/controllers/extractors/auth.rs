@@ -1,16 +1,23 @@
 use axum::{
     async_trait,
     extract::FromRequestParts,
-    http::{header, request::Parts},
+    http::{Method, header, request::Parts},
 };
 use uuid::Uuid;
 
-use crate::{error::AppError, models::auth::Role, services::auth_service, state::AppState};
+use crate::{
+    error::AppError,
+    models::auth::Role,
+    services::{auth_service, impersonation_service},
+    state::AppState,
+};
 
 #[derive(Debug, Clone)]
 pub struct AuthUser {
     pub user_id: Uuid,
     pub role: Role,
+    pub actor_id: Option<Uuid>,
+    pub impersonation_session_id: Option<Uuid>,
 }
 
 impl AuthUser {
@@ -20,6 +27,21 @@ impl AuthUser {
         }
         Err(AppError::Forbidden)
     }
+
+    pub fn is_impersonated(&self) -> bool {
+        self.actor_id.is_some()
+    }
+
+    pub fn require_not_impersonated(&self) -> Result<(), AppError> {
+        if self.is_impersonated() {
+            return Err(AppError::Forbidden);
+        }
+        Ok(())
+    }
+}
+
+fn is_write_method(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
 }
 
 #[async_trait]
@@ -44,6 +66,75 @@ impl FromRequestParts<AppState> for AuthUser {
         let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Unauthorized)?;
         let role = Role::try_from(claims.role.as_str()).map_err(|_| AppError::Unauthorized)?;
 
-        Ok(AuthUser { user_id, role })
+        let Some(actor) = claims.act else {
+            return Ok(AuthUser {
+                user_id,
+                role,
+                actor_id: None,
+                impersonation_session_id: None,
+            });
+        };
+
+        let actor_id = Uuid::parse_str(&actor.sub).map_err(|_| AppError::Unauthorized)?;
+        let session_id = claims
+            .sid
+            .as_deref()
+            .and_then(|sid| Uuid::parse_str(sid).ok())
+            .ok_or(AppError::Unauthorized)?;
+
+        impersonation_service::ensure_session_active(state, session_id).await?;
+        if is_write_method(&parts.method) {
+            impersonation_service::record_write(
+                state,
+                actor_id,
+                user_id,
+                session_id,
+                parts.method.as_str(),
+                parts.uri.path(),
+            )
+            .await?;
+        }
+
+        Ok(AuthUser {
+            user_id,
+            role,
+            actor_id: Some(actor_id),
+            impersonation_session_id: Some(session_id),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(actor_id: Option<Uuid>) -> AuthUser {
+        AuthUser {
+            user_id: Uuid::new_v4(),
+            role: Role::User,
+            actor_id,
+            impersonation_session_id: actor_id.map(|_| Uuid::new_v4()),
+        }
+    }
+
+    #[test]
+    fn impersonated_user_is_blocked_from_sensitive_actions() {
+        let impersonated = user(Some(Uuid::new_v4()));
+
+        assert!(impersonated.is_impersonated());
+        assert!(matches!(
+            impersonated.require_not_impersonated(),
+            Err(AppError::Forbidden)
+        ));
+        assert!(user(None).require_not_impersonated().is_ok());
+    }
+
+    #[test]
+    fn write_methods_exclude_safe_methods() {
+        assert!(!is_write_method(&Method::GET));
+        assert!(!is_write_method(&Method::HEAD));
+        assert!(is_write_method(&Method::POST));
+        assert!(is_write_method(&Method::PUT));
+        assert!(is_write_method(&Method::DELETE));
     }
 }
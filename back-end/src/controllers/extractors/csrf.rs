@@ -0,0 +1,157 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{HeaderMap, HeaderValue, header, request::Parts},
+};
+
+use crate::{error::AppError, state::AppState};
+
+/// Rejects cross-site requests to routes that authenticate with the refresh
+/// cookie, using Fetch Metadata first and falling back to `Origin`/`Referer`.
+/// Requests without any of these headers come from non-browser clients, which
+/// cannot ride on a victim's cookies, and are let through.
+#[derive(Debug, Clone, Copy)]
+pub struct SameOriginRequest;
+
+#[async_trait]
+impl FromRequestParts<AppState> for SameOriginRequest {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        verify_same_origin(&parts.headers, &state.cors_allowed_origins).inspect_err(|_| {
+            tracing::warn!(path = %parts.uri.path(), "rejected cross-site cookie request");
+        })?;
+        Ok(SameOriginRequest)
+    }
+}
+
+fn verify_same_origin(
+    headers: &HeaderMap,
+    allowed_origins: &[HeaderValue],
+) -> Result<(), AppError> {
+    let fetch_site = header_str(headers, "sec-fetch-site");
+    if matches!(fetch_site, Some("same-origin" | "none")) {
+        return Ok(());
+    }
+
+    let origin = header_str(headers, header::ORIGIN.as_str())
+        .map(str::to_string)
+        .or_else(|| header_str(headers, header::REFERER.as_str()).and_then(referer_origin));
+
+    match origin {
+        Some(origin) if is_trusted_origin(&origin, headers, allowed_origins) => Ok(()),
+        Some(_) => Err(AppError::Forbidden),
+        // A cross-site fetch always carries an Origin header.
+        None if fetch_site.is_some() => Err(AppError::Forbidden),
+        None => Ok(()),
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn referer_origin(referer: &str) -> Option<String> {
+    let (scheme, rest) = referer.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    if authority.is_empty() {
+        return None;
+    }
+    Some(format!("{scheme}://{authority}"))
+}
+
+fn is_trusted_origin(origin: &str, headers: &HeaderMap, allowed_origins: &[HeaderValue]) -> bool {
+    if origin == "null" {
+        return false;
+    }
+
+    if allowed_origins
+        .iter()
+        .filter_map(|allowed| allowed.to_str().ok())
+        .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+    {
+        return true;
+    }
+
+    let origin_host = origin.split_once("://").map(|(_, host)| host);
+    let request_host = header_str(headers, header::HOST.as_str());
+    matches!((origin_host, request_host), (Some(origin_host), Some(host)) if origin_host.eq_ignore_ascii_case(host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed() -> Vec<HeaderValue> {
+        vec![HeaderValue::from_static("http://localhost:5173")]
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn allows_same_origin_fetch_metadata() {
+        let headers = headers(&[("sec-fetch-site", "same-origin")]);
+
+        assert!(verify_same_origin(&headers, &allowed()).is_ok());
+    }
+
+    #[test]
+    fn allows_cross_site_request_from_allowed_origin() {
+        let headers = headers(&[
+            ("sec-fetch-site", "same-site"),
+            ("origin", "http://localhost:5173"),
+        ]);
+
+        assert!(verify_same_origin(&headers, &allowed()).is_ok());
+    }
+
+    #[test]
+    fn rejects_cross_site_request_from_unknown_origin() {
+        let headers = headers(&[
+            ("sec-fetch-site", "cross-site"),
+            ("origin", "https://evil.example"),
+        ]);
+
+        assert!(matches!(
+            verify_same_origin(&headers, &allowed()),
+            Err(AppError::Forbidden)
+        ));
+    }
+
+    #[test]
+    fn allows_origin_matching_host() {
+        let headers = headers(&[("origin", "https://todo.example"), ("host", "todo.example")]);
+
+        assert!(verify_same_origin(&headers, &allowed()).is_ok());
+    }
+
+    #[test]
+    fn falls_back_to_referer_when_origin_is_missing() {
+        let trusted = headers(&[("referer", "http://localhost:5173/board?x=1")]);
+        let untrusted = headers(&[("referer", "https://evil.example/attack")]);
+
+        assert!(verify_same_origin(&trusted, &allowed()).is_ok());
+        assert!(verify_same_origin(&untrusted, &allowed()).is_err());
+    }
+
+    #[test]
+    fn rejects_null_origin() {
+        let headers = headers(&[("origin", "null")]);
+
+        assert!(verify_same_origin(&headers, &allowed()).is_err());
+    }
+
+    #[test]
+    fn allows_non_browser_clients() {
+        assert!(verify_same_origin(&HeaderMap::new(), &allowed()).is_ok());
+    }
+}
@@ -1,3 +1,7 @@
 pub mod auth;
+pub mod csrf;
+pub mod preconditions;
 
 pub use auth::AuthUser;
+pub use csrf::SameOriginRequest;
+pub use preconditions::{IfMatch, IfNoneMatch};
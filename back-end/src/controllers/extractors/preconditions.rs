@@ -0,0 +1,138 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{HeaderMap, HeaderName, HeaderValue, header, request::Parts},
+};
+
+use crate::error::AppError;
+
+/// The entity tags listed in an `If-Match` or `If-None-Match` header. Todo
+/// ETags are their version as a quoted number; weak tags (`W/"3"`) compare by
+/// that number too, since proxies may weaken them. Tags that are not versions
+/// are kept as never-matching so `If-Match` still fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityTags {
+    Any,
+    Versions(Vec<i64>),
+}
+
+impl EntityTags {
+    fn from_headers(headers: &HeaderMap, name: HeaderName) -> Option<Self> {
+        let mut versions = Vec::new();
+        let mut present = false;
+        for value in headers.get_all(name) {
+            present = true;
+            let raw = value.to_str().unwrap_or_default();
+            for tag in raw.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+                if tag == "*" {
+                    return Some(Self::Any);
+                }
+                let tag = tag.strip_prefix("W/").unwrap_or(tag);
+                if let Some(version) = tag
+                    .strip_prefix('"')
+                    .and_then(|tag| tag.strip_suffix('"'))
+                    .and_then(|tag| tag.parse::<i64>().ok())
+                {
+                    versions.push(version);
+                }
+            }
+        }
+        present.then_some(Self::Versions(versions))
+    }
+
+    pub fn matches(&self, version: i64) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Versions(versions) => versions.contains(&version),
+        }
+    }
+}
+
+pub fn etag(version: i64) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{version}\"")).expect("numeric etag is a valid header")
+}
+
+/// `If-Match`; `None` when absent.
+#[derive(Debug, Clone)]
+pub struct IfMatch(pub Option<EntityTags>);
+
+impl IfMatch {
+    /// The versions a write may apply to, or `None` when any version will do.
+    pub fn versions(&self) -> Option<&[i64]> {
+        match &self.0 {
+            Some(EntityTags::Versions(versions)) => Some(versions),
+            Some(EntityTags::Any) | None => None,
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(IfMatch(EntityTags::from_headers(
+            &parts.headers,
+            header::IF_MATCH,
+        )))
+    }
+}
+
+/// `If-None-Match`; `None` when absent.
+#[derive(Debug, Clone)]
+pub struct IfNoneMatch(pub Option<EntityTags>);
+
+impl IfNoneMatch {
+    pub fn matches(&self, version: i64) -> bool {
+        self.0.as_ref().is_some_and(|tags| tags.matches(version))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfNoneMatch {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(IfNoneMatch(EntityTags::from_headers(
+            &parts.headers,
+            header::IF_NONE_MATCH,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(raw: &str) -> Option<EntityTags> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_MATCH,
+            HeaderValue::from_str(raw).expect("header"),
+        );
+        EntityTags::from_headers(&headers, header::IF_MATCH)
+    }
+
+    #[test]
+    fn parses_versions_wildcards_and_weak_tags() {
+        assert_eq!(tags("\"3\""), Some(EntityTags::Versions(vec![3])));
+        assert_eq!(
+            tags("\"3\", W/\"4\""),
+            Some(EntityTags::Versions(vec![3, 4]))
+        );
+        assert_eq!(tags("*"), Some(EntityTags::Any));
+        assert_eq!(
+            EntityTags::from_headers(&HeaderMap::new(), header::IF_MATCH),
+            None
+        );
+    }
+
+    #[test]
+    fn foreign_tags_never_match() {
+        let foreign = tags("\"abc\"").expect("present");
+        assert_eq!(foreign, EntityTags::Versions(Vec::new()));
+        assert!(!foreign.matches(1));
+        assert!(EntityTags::Any.matches(1));
+        assert_eq!(etag(7), HeaderValue::from_static("\"7\""));
+    }
+}
@@ -0,0 +1,138 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, put},
+};
+use uuid::Uuid;
+
+use crate::{
+    controllers::{extractors::AuthUser, todo_controller::page_response},
+    error::AppError,
+    models::{
+        filter::{SavedFilterRequest, SavedFilterResponse},
+        todo::{ListTodosQuery, TodoResponse},
+    },
+    services::saved_filter_service,
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/filters",
+    tag = "filters",
+    responses(
+        (status = 200, body = [SavedFilterResponse]),
+        (status = 401, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn list_filters(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<SavedFilterResponse>>, AppError> {
+    let filters = saved_filter_service::list_filters(&state, user.user_id).await?;
+    Ok(Json(filters))
+}
+
+#[utoipa::path(
+    post,
+    path = "/filters",
+    tag = "filters",
+    request_body = SavedFilterRequest,
+    responses(
+        (status = 201, body = SavedFilterResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 401, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn create_filter(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<SavedFilterRequest>,
+) -> Result<(StatusCode, Json<SavedFilterResponse>), AppError> {
+    let filter = saved_filter_service::create_filter(&state, user.user_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(filter)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/filters/{id}",
+    tag = "filters",
+    request_body = SavedFilterRequest,
+    params(
+        ("id" = Uuid, Path, description = "Saved filter id")
+    ),
+    responses(
+        (status = 200, body = SavedFilterResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn update_filter(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(filter_id): Path<Uuid>,
+    Json(payload): Json<SavedFilterRequest>,
+) -> Result<Json<SavedFilterResponse>, AppError> {
+    let filter =
+        saved_filter_service::update_filter(&state, user.user_id, filter_id, payload).await?;
+    Ok(Json(filter))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/filters/{id}",
+    tag = "filters",
+    params(
+        ("id" = Uuid, Path, description = "Saved filter id")
+    ),
+    responses(
+        (status = 204),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn delete_filter(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(filter_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    saved_filter_service::delete_filter(&state, user.user_id, filter_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/filters/{id}/todos",
+    tag = "filters",
+    params(
+        ("id" = Uuid, Path, description = "Saved filter id"),
+        ListTodosQuery
+    ),
+    responses(
+        (
+            status = 200,
+            body = [TodoResponse],
+            headers(
+                ("x-next-cursor" = String, description = "Cursor for the next page; absent on the last page")
+            )
+        ),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn run_filter(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(filter_id): Path<Uuid>,
+    Query(query): Query<ListTodosQuery>,
+) -> Result<(HeaderMap, Json<Vec<TodoResponse>>), AppError> {
+    let page = saved_filter_service::run_filter(&state, user.user_id, filter_id, query).await?;
+    Ok(page_response(page))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/filters", get(list_filters).post(create_filter))
+        .route("/filters/:id", put(update_filter).delete(delete_filter))
+        .route("/filters/:id/todos", get(run_filter))
+}
@@ -0,0 +1,121 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, put},
+};
+use uuid::Uuid;
+
+use crate::{
+    controllers::extractors::AuthUser,
+    error::AppError,
+    models::{
+        label::{LabelRequest, LabelResponse, SetTodoLabelsRequest},
+        todo::TodoResponse,
+    },
+    services::label_service,
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/labels",
+    tag = "labels",
+    responses((status = 200, body = [LabelResponse]))
+)]
+pub async fn list_labels(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<LabelResponse>>, AppError> {
+    let labels = label_service::list_labels(&state, user.user_id).await?;
+    Ok(Json(labels))
+}
+
+#[utoipa::path(
+    post,
+    path = "/labels",
+    tag = "labels",
+    request_body = LabelRequest,
+    responses(
+        (status = 201, body = LabelResponse),
+        (status = 400, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn create_label(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<LabelRequest>,
+) -> Result<(StatusCode, Json<LabelResponse>), AppError> {
+    let label = label_service::create_label(&state, user.user_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(label)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/labels/{id}",
+    tag = "labels",
+    request_body = LabelRequest,
+    params(("id" = String, Path, description = "Label ID")),
+    responses(
+        (status = 200, body = LabelResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn update_label(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(label_id): Path<Uuid>,
+    Json(payload): Json<LabelRequest>,
+) -> Result<Json<LabelResponse>, AppError> {
+    let label = label_service::update_label(&state, user.user_id, label_id, payload).await?;
+    Ok(Json(label))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/labels/{id}",
+    tag = "labels",
+    params(("id" = String, Path, description = "Label ID")),
+    responses(
+        (status = 204),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn delete_label(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(label_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    label_service::delete_label(&state, user.user_id, label_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/todos/{id}/labels",
+    tag = "labels",
+    request_body = SetTodoLabelsRequest,
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 200, body = TodoResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn set_todo_labels(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    Json(payload): Json<SetTodoLabelsRequest>,
+) -> Result<Json<TodoResponse>, AppError> {
+    let todo = label_service::set_todo_labels(&state, user.user_id, todo_id, payload).await?;
+    Ok(Json(todo))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/labels", get(list_labels).post(create_label))
+        .route("/labels/:id", put(update_label).delete(delete_label))
+        .route("/todos/:id/labels", put(set_todo_labels))
+}
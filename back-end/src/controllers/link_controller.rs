@@ -0,0 +1,84 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get},
+};
+use uuid::Uuid;
+
+use crate::{
+    controllers::extractors::AuthUser,
+    error::AppError,
+    models::link::{CreateTodoLinkRequest, TodoLinkResponse},
+    services::link_service,
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/todos/{id}/links",
+    tag = "todos",
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 200, body = [TodoLinkResponse]),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn list_links(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Json<Vec<TodoLinkResponse>>, AppError> {
+    let links = link_service::list_links(&state, user.user_id, todo_id).await?;
+    Ok(Json(links))
+}
+
+#[utoipa::path(
+    post,
+    path = "/todos/{id}/links",
+    tag = "todos",
+    request_body = CreateTodoLinkRequest,
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 201, body = TodoLinkResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn create_link(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    Json(payload): Json<CreateTodoLinkRequest>,
+) -> Result<(StatusCode, Json<TodoLinkResponse>), AppError> {
+    let link = link_service::create_link(&state, user.user_id, todo_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(link)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/todos/{id}/links/{link_id}",
+    tag = "todos",
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("link_id" = String, Path, description = "Link ID")
+    ),
+    responses(
+        (status = 204),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn delete_link(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((todo_id, link_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    link_service::delete_link(&state, user.user_id, todo_id, link_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/todos/:id/links", get(list_links).post(create_link))
+        .route("/todos/:id/links/:link_id", delete(delete_link))
+}
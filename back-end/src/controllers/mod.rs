@@ -1,9 +1,16 @@
+pub mod admin_controller;
 pub mod ai_controller;
+pub mod attachment_controller;
 pub mod auth_controller;
+pub mod comment_controller;
 pub mod docs_controller;
 pub mod extractors;
+pub mod filter_controller;
 pub mod health_controller;
+pub mod label_controller;
+pub mod link_controller;
 pub mod system_controller;
 pub mod todo_controller;
 pub mod todo_realtime_controller;
+pub mod trash_controller;
 pub mod user_controller;
@@ -1,33 +1,116 @@
 use axum::{
     Json, Router,
-    extract::{Path, State},
-    routing::{get, put},
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
 };
 use uuid::Uuid;
 
 use crate::{
-    controllers::extractors::AuthUser,
+    controllers::extractors::{AuthUser, IfMatch, IfNoneMatch, preconditions},
     error::AppError,
-    models::todo::{CreateTodoRequest, ReorderTodosRequest, TodoResponse, UpdateTodoRequest},
-    services::todo_service,
+    models::{
+        bulk::{BulkTodosRequest, BulkTodosResponse},
+        history::{TodoHistoryEntry, TodoHistoryQuery},
+        todo::{
+            CreateTodoRequest, ListTodosQuery, MoveTodoRequest, PatchTodoRequest,
+            RecurrenceRequest, ReorderTodosRequest, SearchTodosQuery, SnoozeReminderRequest,
+            SnoozeReminderResponse, TodoColumnSummary, TodoPage, TodoResponse, TodoSearchResult,
+            UndoResponse, UpdateTodoRequest,
+        },
+    },
+    services::{
+        bulk_service, history_service, recurrence_service, reminder_service, subtask_service,
+        todo_service, undo_service,
+    },
     state::AppState,
 };
 
+const NEXT_CURSOR_HEADER: HeaderName = HeaderName::from_static("x-next-cursor");
+
+pub(crate) fn cursor_headers(next_cursor: Option<String>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(cursor) = next_cursor.and_then(|cursor| HeaderValue::from_str(&cursor).ok()) {
+        headers.insert(NEXT_CURSOR_HEADER, cursor);
+    }
+    headers
+}
+
+fn etag_headers(todo: &TodoResponse) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ETAG, preconditions::etag(todo.version));
+    headers
+}
+
+pub(crate) fn page_response(page: TodoPage) -> (HeaderMap, Json<Vec<TodoResponse>>) {
+    (cursor_headers(page.next_cursor), Json(page.items))
+}
+
 #[utoipa::path(
     get,
     path = "/todos",
     tag = "todos",
+    params(ListTodosQuery),
     responses(
-        (status = 200, body = [TodoResponse]),
+        (
+            status = 200,
+            body = [TodoResponse],
+            headers(
+                ("x-next-cursor" = String, description = "Cursor for the next page; absent on the last page")
+            )
+        ),
+        (status = 400, body = crate::error::ErrorResponse),
         (status = 401, body = crate::error::ErrorResponse)
     )
 )]
 pub async fn list_todos(
     State(state): State<AppState>,
     user: AuthUser,
-) -> Result<Json<Vec<TodoResponse>>, AppError> {
-    let todos = todo_service::list_todos(&state, user.user_id).await?;
-    Ok(Json(todos))
+    Query(query): Query<ListTodosQuery>,
+) -> Result<(HeaderMap, Json<Vec<TodoResponse>>), AppError> {
+    let page = todo_service::list_todos(&state, user.user_id, query, None).await?;
+    Ok(page_response(page))
+}
+
+#[utoipa::path(
+    get,
+    path = "/todos/summary",
+    tag = "todos",
+    params(ListTodosQuery),
+    responses(
+        (status = 200, body = [TodoColumnSummary], description = "One entry per status in board order; paging and sort parameters are ignored"),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 401, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn summarize_todos(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(query): Query<ListTodosQuery>,
+) -> Result<Json<Vec<TodoColumnSummary>>, AppError> {
+    let summary = todo_service::summarize_todos(&state, user.user_id, query).await?;
+    Ok(Json(summary))
+}
+
+#[utoipa::path(
+    get,
+    path = "/todos/search",
+    tag = "todos",
+    params(SearchTodosQuery),
+    responses(
+        (status = 200, body = [TodoSearchResult]),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 401, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn search_todos(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(query): Query<SearchTodosQuery>,
+) -> Result<Json<Vec<TodoSearchResult>>, AppError> {
+    let results = todo_service::search_todos(&state, user.user_id, query).await?;
+    Ok(Json(results))
 }
 
 #[utoipa::path(
@@ -36,7 +119,7 @@ pub async fn list_todos(
     tag = "todos",
     request_body = CreateTodoRequest,
     responses(
-        (status = 201, body = TodoResponse),
+        (status = 201, body = TodoResponse, headers(("etag" = String, description = "Quoted version"))),
         (status = 401, body = crate::error::ErrorResponse)
     )
 )]
@@ -44,18 +127,22 @@ pub async fn create_todo(
     State(state): State<AppState>,
     user: AuthUser,
     Json(payload): Json<CreateTodoRequest>,
-) -> Result<(axum::http::StatusCode, Json<TodoResponse>), AppError> {
+) -> Result<(StatusCode, HeaderMap, Json<TodoResponse>), AppError> {
     let todo = todo_service::create_todo(&state, user.user_id, payload).await?;
-    Ok((axum::http::StatusCode::CREATED, Json(todo)))
+    Ok((StatusCode::CREATED, etag_headers(&todo), Json(todo)))
 }
 
 #[utoipa::path(
     get,
     path = "/todos/{id}",
     tag = "todos",
-    params(("id" = String, Path, description = "Todo ID")),
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("if-none-match" = Option<String>, Header, description = "ETag from an earlier response")
+    ),
     responses(
-        (status = 200, body = TodoResponse),
+        (status = 200, body = TodoResponse, headers(("etag" = String, description = "Quoted version"))),
+        (status = 304, description = "The todo still has the given version"),
         (status = 404, body = crate::error::ErrorResponse)
     )
 )]
@@ -63,9 +150,13 @@ pub async fn get_todo(
     State(state): State<AppState>,
     user: AuthUser,
     Path(todo_id): Path<Uuid>,
-) -> Result<Json<TodoResponse>, AppError> {
+    if_none_match: IfNoneMatch,
+) -> Result<Response, AppError> {
     let todo = todo_service::get_todo(&state, user.user_id, todo_id).await?;
-    Ok(Json(todo))
+    if if_none_match.matches(todo.version) {
+        return Ok((StatusCode::NOT_MODIFIED, etag_headers(&todo)).into_response());
+    }
+    Ok((etag_headers(&todo), Json(todo)).into_response())
 }
 
 #[utoipa::path(
@@ -73,42 +164,153 @@ pub async fn get_todo(
     path = "/todos/{id}",
     tag = "todos",
     request_body = UpdateTodoRequest,
-    params(("id" = String, Path, description = "Todo ID")),
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("if-match" = Option<String>, Header, description = "Only update while the todo has this ETag")
+    ),
     responses(
-        (status = 200, body = TodoResponse),
+        (status = 200, body = TodoResponse, headers(("etag" = String, description = "Quoted version"))),
         (status = 400, body = crate::error::ErrorResponse),
-        (status = 404, body = crate::error::ErrorResponse)
+        (status = 404, body = crate::error::ErrorResponse),
+        (status = 412, body = crate::error::ErrorResponse)
     )
 )]
 pub async fn update_todo(
     State(state): State<AppState>,
     user: AuthUser,
     Path(todo_id): Path<Uuid>,
+    if_match: IfMatch,
     Json(payload): Json<UpdateTodoRequest>,
-) -> Result<Json<TodoResponse>, AppError> {
-    let todo = todo_service::update_todo(&state, user.user_id, todo_id, payload).await?;
-    Ok(Json(todo))
+) -> Result<(HeaderMap, Json<TodoResponse>), AppError> {
+    let todo = todo_service::update_todo(
+        &state,
+        user.user_id,
+        todo_id,
+        payload.into(),
+        if_match.versions(),
+    )
+    .await?;
+    Ok((etag_headers(&todo), Json(todo)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/todos/{id}",
+    tag = "todos",
+    request_body(content = PatchTodoRequest, content_type = "application/merge-patch+json"),
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("if-match" = Option<String>, Header, description = "Only update while the todo has this ETag")
+    ),
+    responses(
+        (status = 200, body = TodoResponse, headers(("etag" = String, description = "Quoted version"))),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse),
+        (status = 412, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn patch_todo(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    if_match: IfMatch,
+    Json(payload): Json<PatchTodoRequest>,
+) -> Result<(HeaderMap, Json<TodoResponse>), AppError> {
+    let todo =
+        todo_service::update_todo(&state, user.user_id, todo_id, payload, if_match.versions())
+            .await?;
+    Ok((etag_headers(&todo), Json(todo)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/todos/{id}/move",
+    tag = "todos",
+    request_body = MoveTodoRequest,
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("if-match" = Option<String>, Header, description = "Only move while the todo has this ETag")
+    ),
+    responses(
+        (status = 200, body = TodoResponse, headers(("etag" = String, description = "Quoted version"))),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse),
+        (status = 412, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn move_todo(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    if_match: IfMatch,
+    Json(payload): Json<MoveTodoRequest>,
+) -> Result<(HeaderMap, Json<TodoResponse>), AppError> {
+    let todo = todo_service::move_todo(&state, user.user_id, todo_id, payload, if_match.versions())
+        .await?;
+    Ok((etag_headers(&todo), Json(todo)))
 }
 
 #[utoipa::path(
     delete,
     path = "/todos/{id}",
     tag = "todos",
-    params(("id" = String, Path, description = "Todo ID")),
+    params(
+        ("id" = String, Path, description = "Todo ID"),
+        ("if-match" = Option<String>, Header, description = "Only delete while the todo has this ETag")
+    ),
     responses(
         (status = 204),
-        (status = 404, body = crate::error::ErrorResponse)
+        (status = 404, body = crate::error::ErrorResponse),
+        (status = 412, body = crate::error::ErrorResponse)
     )
 )]
 pub async fn delete_todo(
     State(state): State<AppState>,
     user: AuthUser,
     Path(todo_id): Path<Uuid>,
+    if_match: IfMatch,
 ) -> Result<axum::http::StatusCode, AppError> {
-    todo_service::delete_todo(&state, user.user_id, todo_id).await?;
+    todo_service::delete_todo(&state, user.user_id, todo_id, if_match.versions()).await?;
     Ok(axum::http::StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/todos/undo",
+    tag = "todos",
+    responses(
+        (status = 200, body = UndoResponse),
+        (status = 404, description = "Nothing to undo", body = crate::error::ErrorResponse),
+        (status = 409, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn undo(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<UndoResponse>, AppError> {
+    let result = undo_service::undo_last(&state, user.user_id).await?;
+    Ok(Json(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/todos/bulk",
+    tag = "todos",
+    request_body = BulkTodosRequest,
+    responses(
+        (status = 200, description = "Per-item results; failed items were left unchanged", body = BulkTodosResponse),
+        (status = 400, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn bulk_todos(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<BulkTodosRequest>,
+) -> Result<Json<BulkTodosResponse>, AppError> {
+    let result = bulk_service::apply_bulk(&state, user.user_id, payload).await?;
+    Ok(Json(result))
+}
+
 #[utoipa::path(
     put,
     path = "/todos/reorder-items",
@@ -117,7 +319,9 @@ pub async fn delete_todo(
     responses(
         (status = 204),
         (status = 400, body = crate::error::ErrorResponse),
-        (status = 404, body = crate::error::ErrorResponse)
+        (status = 404, body = crate::error::ErrorResponse),
+        (status = 409, description = "Two todos would share a position in a column", body = crate::error::ErrorResponse),
+        (status = 412, description = "An item's `version` is stale", body = crate::error::ErrorResponse)
     )
 )]
 pub async fn reorder_todos(
@@ -144,12 +348,159 @@ pub async fn reorder_todos(
     Ok(axum::http::StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    path = "/todos/{id}/history",
+    tag = "todos",
+    params(("id" = String, Path, description = "Todo ID"), TodoHistoryQuery),
+    responses(
+        (
+            status = 200,
+            body = [TodoHistoryEntry],
+            headers(
+                ("x-next-cursor" = String, description = "Cursor for the next page; absent on the last page")
+            )
+        ),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn list_history(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    Query(query): Query<TodoHistoryQuery>,
+) -> Result<(HeaderMap, Json<Vec<TodoHistoryEntry>>), AppError> {
+    let page = history_service::list_todo_history(&state, user.user_id, todo_id, query).await?;
+    Ok((cursor_headers(page.next_cursor), Json(page.items)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/todos/{id}/subtasks",
+    tag = "todos",
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 200, body = [TodoResponse]),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn list_subtasks(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Json<Vec<TodoResponse>>, AppError> {
+    let subtasks = subtask_service::list_subtasks(&state, user.user_id, todo_id).await?;
+    Ok(Json(subtasks))
+}
+
+#[utoipa::path(
+    put,
+    path = "/todos/{id}/recurrence",
+    tag = "todos",
+    request_body = RecurrenceRequest,
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 200, body = TodoResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn set_recurrence(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    Json(payload): Json<RecurrenceRequest>,
+) -> Result<Json<TodoResponse>, AppError> {
+    let todo = recurrence_service::set_recurrence(&state, user.user_id, todo_id, payload).await?;
+    Ok(Json(todo))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/todos/{id}/recurrence",
+    tag = "todos",
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 200, body = TodoResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn stop_recurrence(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Json<TodoResponse>, AppError> {
+    let todo = recurrence_service::stop_recurrence(&state, user.user_id, todo_id).await?;
+    Ok(Json(todo))
+}
+
+#[utoipa::path(
+    post,
+    path = "/todos/{id}/reminders/snooze",
+    tag = "todos",
+    request_body = SnoozeReminderRequest,
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 200, body = SnoozeReminderResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 403, description = "You are not the reminder recipient", body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn snooze_reminder(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    Json(payload): Json<SnoozeReminderRequest>,
+) -> Result<Json<SnoozeReminderResponse>, AppError> {
+    let snooze = reminder_service::snooze(&state, user.user_id, todo_id, payload).await?;
+    Ok(Json(snooze))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/todos/{id}/reminders/snooze",
+    tag = "todos",
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 204),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn cancel_snooze(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<axum::http::StatusCode, AppError> {
+    reminder_service::cancel_snooze(&state, user.user_id, todo_id).await?;
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/todos", get(list_todos).post(create_todo))
+        .route("/todos/search", get(search_todos))
+        .route("/todos/summary", get(summarize_todos))
         .route("/todos/reorder-items", put(reorder_todos))
+        .route("/todos/undo", post(undo))
+        .route("/todos/bulk", post(bulk_todos))
         .route(
             "/todos/:id",
-            get(get_todo).put(update_todo).delete(delete_todo),
+            get(get_todo)
+                .put(update_todo)
+                .patch(patch_todo)
+                .delete(delete_todo),
+        )
+        .route("/todos/:id/move", post(move_todo))
+        .route("/todos/:id/history", get(list_history))
+        .route("/todos/:id/subtasks", get(list_subtasks))
+        .route(
+            "/todos/:id/recurrence",
+            put(set_recurrence).delete(stop_recurrence),
+        )
+        .route(
+            "/todos/:id/reminders/snooze",
+            post(snooze_reminder).delete(cancel_snooze),
         )
 }
@@ -0,0 +1,72 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+};
+use uuid::Uuid;
+
+use crate::{
+    controllers::extractors::AuthUser, error::AppError, models::todo::TodoResponse,
+    services::trash_service, state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/todos/trash",
+    tag = "todos",
+    responses((status = 200, body = [TodoResponse]))
+)]
+pub async fn list_trash(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<TodoResponse>>, AppError> {
+    let todos = trash_service::list_trash(&state, user.user_id).await?;
+    Ok(Json(todos))
+}
+
+#[utoipa::path(
+    post,
+    path = "/todos/{id}/restore",
+    tag = "todos",
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 200, body = TodoResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn restore_todo(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Json<TodoResponse>, AppError> {
+    let todo = trash_service::restore_todo(&state, user.user_id, todo_id).await?;
+    Ok(Json(todo))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/todos/trash/{id}",
+    tag = "todos",
+    params(("id" = String, Path, description = "Todo ID")),
+    responses(
+        (status = 204),
+        (status = 404, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn purge_todo(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    trash_service::purge_todo(&state, user.user_id, todo_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/todos/trash", get(list_trash))
+        .route("/todos/trash/:id", delete(purge_todo))
+        .route("/todos/:id/restore", post(restore_todo))
+}
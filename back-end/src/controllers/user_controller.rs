@@ -1,8 +1,14 @@
 use axum::{Json, Router, extract::State, routing::get};
 
 use crate::{
-    controllers::extractors::AuthUser, error::AppError, models::auth::UserResponse,
-    services::user_service, state::AppState,
+    controllers::extractors::AuthUser,
+    error::AppError,
+    models::{
+        auth::UserResponse,
+        user::{UpdateUserPreferencesRequest, UserPreferencesResponse},
+    },
+    services::user_service,
+    state::AppState,
 };
 
 #[utoipa::path(
@@ -22,6 +28,46 @@ pub async fn list_users(
     Ok(Json(users))
 }
 
+#[utoipa::path(
+    get,
+    path = "/users/me/preferences",
+    tag = "users",
+    responses(
+        (status = 200, body = UserPreferencesResponse),
+        (status = 401, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<UserPreferencesResponse>, AppError> {
+    let preferences = user_service::get_preferences(&state, user.user_id).await?;
+    Ok(Json(preferences))
+}
+
+#[utoipa::path(
+    put,
+    path = "/users/me/preferences",
+    tag = "users",
+    request_body = UpdateUserPreferencesRequest,
+    responses(
+        (status = 200, body = UserPreferencesResponse),
+        (status = 400, body = crate::error::ErrorResponse),
+        (status = 401, body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn update_preferences(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<UpdateUserPreferencesRequest>,
+) -> Result<Json<UserPreferencesResponse>, AppError> {
+    let preferences = user_service::update_preferences(&state, user.user_id, payload).await?;
+    Ok(Json(preferences))
+}
+
 pub fn routes() -> Router<AppState> {
-    Router::new().route("/users", get(list_users))
+    Router::new().route("/users", get(list_users)).route(
+        "/users/me/preferences",
+        get(get_preferences).put(update_preferences),
+    )
 }
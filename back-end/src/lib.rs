@@ -1,3 +1,4 @@
+pub mod client_ip;
 pub mod controllers;
 pub mod error;
 pub mod locale;
@@ -4,28 +4,30 @@ use std::{
     sync::Arc,
 };
 
-use axum::http::{HeaderValue, Method, header};
-use axum::{Router, routing::get};
+use axum::http::{HeaderName, HeaderValue, Method, Request, header};
+use axum::{Router, middleware, routing::get};
 use axum_prometheus::PrometheusMetricLayer;
+use client_ip::{ClientIp, ClientIpKeyExtractor};
 use controllers::{
-    ai_controller, auth_controller, docs_controller, health_controller, system_controller,
-    todo_controller, todo_realtime_controller, user_controller,
+    admin_controller, ai_controller, attachment_controller, auth_controller, comment_controller,
+    docs_controller, filter_controller, health_controller, label_controller, link_controller,
+    system_controller, todo_controller, todo_realtime_controller, trash_controller,
+    user_controller,
 };
 use dotenvy::dotenv;
 use error::AppError;
 use sqlx::postgres::PgPoolOptions;
 use state::AppState;
-use tower_governor::{
-    GovernorLayer, governor::GovernorConfigBuilder, key_extractor::SmartIpKeyExtractor,
-};
+use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder};
 use tower_http::{
-    cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer},
+    cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer, ExposeHeaders},
     request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
     services::{ServeDir, ServeFile},
-    trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
+    trace::{DefaultOnResponse, TraceLayer},
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utoipa::OpenApi;
+mod client_ip;
 mod controllers;
 mod error;
 mod locale;
@@ -37,48 +39,133 @@ mod state;
 #[derive(OpenApi)]
 #[openapi(
     paths(
+        admin_controller::start_impersonation,
+        admin_controller::end_impersonation,
+        admin_controller::list_impersonations,
+        admin_controller::list_activity,
         ai_controller::generate,
+        attachment_controller::list_attachments,
+        attachment_controller::upload_attachment,
+        attachment_controller::download_attachment,
+        attachment_controller::delete_attachment,
         auth_controller::register,
         auth_controller::login,
         auth_controller::refresh,
         auth_controller::logout,
         auth_controller::forgot,
         auth_controller::reset,
+        auth_controller::change_password,
+        filter_controller::list_filters,
+        filter_controller::create_filter,
+        filter_controller::update_filter,
+        filter_controller::delete_filter,
+        filter_controller::run_filter,
+        comment_controller::list_comments,
+        comment_controller::create_comment,
+        comment_controller::update_comment,
+        comment_controller::delete_comment,
+        comment_controller::list_watchers,
+        comment_controller::add_watcher,
+        comment_controller::remove_watcher,
+        label_controller::list_labels,
+        label_controller::create_label,
+        label_controller::update_label,
+        label_controller::delete_label,
+        label_controller::set_todo_labels,
+        link_controller::list_links,
+        link_controller::create_link,
+        link_controller::delete_link,
         todo_controller::list_todos,
+        todo_controller::search_todos,
+        todo_controller::summarize_todos,
         todo_controller::create_todo,
         todo_controller::get_todo,
         todo_controller::update_todo,
+        todo_controller::patch_todo,
+        todo_controller::move_todo,
         todo_controller::delete_todo,
         todo_controller::reorder_todos,
+        todo_controller::undo,
+        todo_controller::bulk_todos,
+        todo_controller::list_history,
+        todo_controller::list_subtasks,
+        todo_controller::set_recurrence,
+        todo_controller::stop_recurrence,
+        todo_controller::snooze_reminder,
+        todo_controller::cancel_snooze,
+        trash_controller::list_trash,
+        trash_controller::restore_todo,
+        trash_controller::purge_todo,
         user_controller::list_users,
+        user_controller::get_preferences,
+        user_controller::update_preferences,
         health_controller::health_check,
         system_controller::unit_test_coverage,
         docs_controller::scalar_ui
     ),
     components(schemas(
+        models::admin::ImpersonateRequest,
+        models::admin::ImpersonationResponse,
+        models::admin::ImpersonationSessionResponse,
         models::ai::AiGenerateRequest,
         models::ai::AiGenerateResponse,
+        models::attachment::TodoAttachmentResponse,
+        models::attachment::AttachmentUpload,
         models::auth::RegisterRequest,
         models::auth::LoginRequest,
         models::auth::RefreshRequest,
         models::auth::ForgotPasswordRequest,
         models::auth::ResetPasswordRequest,
+        models::auth::ChangePasswordRequest,
         models::auth::AuthResponse,
         models::auth::UserResponse,
         models::auth::MessageResponse,
+        models::comment::CommentRequest,
+        models::comment::TodoCommentResponse,
+        models::comment::TodoWatcherResponse,
+        models::filter::SavedFilterRequest,
+        models::filter::SavedFilterResponse,
+        models::history::TodoHistoryEntry,
+        models::label::LabelRequest,
+        models::label::LabelResponse,
+        models::label::SetTodoLabelsRequest,
+        models::label::TodoLabel,
+        models::link::CreateTodoLinkRequest,
+        models::link::TodoLinkResponse,
         models::todo::CreateTodoRequest,
         models::todo::UpdateTodoRequest,
+        models::todo::PatchTodoRequest,
         models::todo::ReorderTodosRequest,
+        models::todo::MoveTodoRequest,
         models::todo::ReorderTodoItem,
         models::todo::TodoResponse,
+        models::todo::UndoResponse,
+        models::bulk::BulkTodosRequest,
+        models::bulk::BulkOperation,
+        models::bulk::BulkItemResult,
+        models::bulk::BulkTodosResponse,
+        models::todo::TodoSearchResult,
+        models::todo::TodoSummary,
+        models::todo::TodoColumnSummary,
+        models::todo::RecurrenceRequest,
+        models::todo::TodoReminder,
+        models::todo::SnoozeReminderRequest,
+        models::todo::SnoozeReminderResponse,
+        models::user::UserPreferencesResponse,
+        models::user::UpdateUserPreferencesRequest,
         error::ErrorResponse,
         controllers::health_controller::HealthResponse,
         controllers::system_controller::CoverageResponse
     )),
     servers((url = "/api", description = "API base")),
     tags(
+        (name = "admin", description = "Administration and support tooling"),
         (name = "ai", description = "Local AI integration"),
+        (name = "attachments", description = "Todo file attachments"),
         (name = "auth", description = "Authentication"),
+        (name = "comments", description = "Todo comments and watchers"),
+        (name = "filters", description = "Saved todo filters"),
+        (name = "labels", description = "Todo labels"),
         (name = "todos", description = "Todo management"),
         (name = "users", description = "User directory"),
         (name = "health", description = "Health check"),
@@ -115,13 +202,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     sqlx::migrate!("./migrations").run(&pool).await?;
 
     let state = AppState::from_env(pool)?;
+    services::reminder_service::spawn_scheduler(state.clone());
+    services::attachment_service::spawn_cleanup(state.clone());
+    services::trash_service::spawn_purge_job(state.clone());
+    services::position_service::spawn_rebalance_job(state.clone());
 
     let cors_layer = build_cors_layer(&state.cors_allowed_origins);
 
     let governor_conf = GovernorConfigBuilder::default()
         .per_second(state.rate_limit_per_second.get() as u64)
         .burst_size(state.rate_limit_burst.get())
-        .key_extractor(SmartIpKeyExtractor)
+        .key_extractor(ClientIpKeyExtractor)
         .finish()
         .expect("valid governor config");
     let governor_conf = Arc::new(governor_conf);
@@ -138,10 +229,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             "/metrics",
             get(move || async move { metrics_route_handle.render() }),
         )
+        .merge(admin_controller::routes())
         .merge(ai_controller::routes())
+        .merge(attachment_controller::routes())
         .merge(auth_controller::routes())
+        .merge(comment_controller::routes())
+        .merge(filter_controller::routes())
+        .merge(label_controller::routes())
+        .merge(link_controller::routes())
         .merge(todo_controller::routes())
         .merge(todo_realtime_controller::routes())
+        .merge(trash_controller::routes())
         .merge(user_controller::routes())
         .merge(system_controller::routes())
         .fallback(api_not_found);
@@ -156,9 +254,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .layer(prometheus_layer)
         .layer(
             TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::new().include_headers(true))
+                .make_span_with(make_request_span)
                 .on_response(DefaultOnResponse::new().include_headers(true)),
         )
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            client_ip::resolve_client_ip,
+        ))
         .layer(PropagateRequestIdLayer::x_request_id())
         .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         .layer(cors_layer)
@@ -182,6 +284,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+fn make_request_span<B>(request: &Request<B>) -> tracing::Span {
+    let client_ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ClientIp(ip)| ip.to_string())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+        client_ip = %client_ip,
+        headers = ?request.headers(),
+    )
+}
+
 async fn api_not_found() -> AppError {
     AppError::NotFound
 }
@@ -193,9 +311,15 @@ fn build_cors_layer(origins: &[HeaderValue]) -> CorsLayer {
             Method::GET,
             Method::POST,
             Method::PUT,
+            Method::PATCH,
             Method::DELETE,
             Method::OPTIONS,
         ]))
+        .expose_headers(ExposeHeaders::list([
+            HeaderName::from_static("x-next-cursor"),
+            header::CONTENT_DISPOSITION,
+            header::ETAG,
+        ]))
         .allow_credentials(true);
 
     if env_mode == "production" {
@@ -205,6 +329,8 @@ fn build_cors_layer(origins: &[HeaderValue]) -> CorsLayer {
                 header::ACCEPT_LANGUAGE,
                 header::CONTENT_TYPE,
                 header::AUTHORIZATION,
+                header::IF_MATCH,
+                header::IF_NONE_MATCH,
             ]))
     } else {
         cors.allow_origin(AllowOrigin::mirror_request())
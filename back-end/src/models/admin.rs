@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::models::auth::UserResponse;
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct ImpersonateRequest {
+    pub user_id: Uuid,
+    pub reason: String,
+    #[serde(default)]
+    pub ttl_minutes: Option<i64>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ImpersonationResponse {
+    pub session_id: Uuid,
+    pub user: UserResponse,
+    pub access_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow, utoipa::ToSchema)]
+pub struct ImpersonationSessionResponse {
+    pub id: Uuid,
+    pub actor_id: Uuid,
+    pub actor_email: String,
+    pub user_id: Uuid,
+    pub user_email: String,
+    pub reason: String,
+    pub started_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub write_count: i64,
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, FromRow, utoipa::ToSchema)]
+pub struct TodoAttachmentResponse {
+    pub id: Uuid,
+    pub todo_id: Uuid,
+    pub uploader_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    /// Hex SHA-256 of the stored bytes.
+    pub sha256: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Multipart body of `POST /todos/{id}/attachments`. Only describes the form
+/// in the OpenAPI document; the handler reads the `file` part directly.
+#[derive(utoipa::ToSchema)]
+#[allow(dead_code)]
+pub struct AttachmentUpload {
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+pub struct AttachmentContentQuery {
+    /// Render images inline instead of downloading them.
+    pub inline: Option<bool>,
+}
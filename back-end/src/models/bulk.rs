@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The change applied to every selected todo.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkOperation {
+    SetStatus {
+        status: String,
+    },
+    /// `null` unassigns.
+    SetAssignee {
+        assignee_id: Option<Uuid>,
+    },
+    /// Replaces the labels from your catalog, like `PUT /todos/{id}/labels`.
+    SetLabels {
+        label_ids: Vec<Uuid>,
+    },
+    Delete,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct BulkTodosRequest {
+    /// Todos to change, at most 500. Give either `ids` or `filter`.
+    pub ids: Option<Vec<Uuid>>,
+    /// Filter expression as in `GET /todos?filter=`; it may match at most 500 todos.
+    pub filter: Option<String>,
+    pub operation: BulkOperation,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BulkItemResult {
+    pub id: Uuid,
+    /// HTTP status the change would have had on its own.
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BulkTodosResponse {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+}
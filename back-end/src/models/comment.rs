@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct CommentRequest {
+    /// Markdown body; `@user@example.com` mentions a user.
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow, utoipa::ToSchema)]
+pub struct TodoCommentResponse {
+    pub id: Uuid,
+    pub todo_id: Uuid,
+    pub author_id: Uuid,
+    pub author_email: String,
+    /// Markdown source.
+    pub body: String,
+    /// Sanitized HTML rendered from `body`.
+    pub body_html: String,
+    /// Users mentioned in the body.
+    pub mentioned_user_ids: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow, utoipa::ToSchema)]
+pub struct TodoWatcherResponse {
+    pub user_id: Uuid,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+}
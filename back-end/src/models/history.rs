@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, FromRow, utoipa::ToSchema)]
+pub struct TodoHistoryEntry {
+    pub id: i64,
+    pub todo_id: Uuid,
+    /// Absent once the acting user has been deleted.
+    pub actor_id: Option<Uuid>,
+    pub actor_email: Option<String>,
+    /// `created`, `updated`, `reordered` or `deleted`.
+    pub action: String,
+    /// Changed fields as `{ "status": { "from": "todo", "to": "done" } }`.
+    #[schema(value_type = Object)]
+    pub changes: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TodoHistoryQuery {
+    /// Page size, between 1 and 200 (default 50).
+    pub limit: Option<u32>,
+    /// Opaque cursor taken from the `X-Next-Cursor` header of the previous page.
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ActivityQuery {
+    /// Page size, between 1 and 200 (default 50).
+    pub limit: Option<u32>,
+    /// Opaque cursor taken from the `X-Next-Cursor` header of the previous page.
+    pub cursor: Option<String>,
+    pub actor_id: Option<Uuid>,
+    pub todo_id: Option<Uuid>,
+    /// `created`, `updated`, `reordered` or `deleted`.
+    pub action: Option<String>,
+    /// Only entries that changed this field, e.g. `status`.
+    pub field: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct HistoryPage {
+    pub items: Vec<TodoHistoryEntry>,
+    pub next_cursor: Option<String>,
+}
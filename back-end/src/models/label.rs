@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct LabelRequest {
+    pub name: String,
+    /// Hex color such as `#e11d48`; defaults to gray.
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow, utoipa::ToSchema)]
+pub struct LabelResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub color: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct SetTodoLabelsRequest {
+    /// Labels from your catalog; replaces the labels you had put on the todo.
+    pub label_ids: Vec<Uuid>,
+}
+
+/// A label as shown on a todo.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TodoLabel {
+    pub id: Uuid,
+    pub name: String,
+    pub color: String,
+}
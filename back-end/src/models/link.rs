@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct CreateTodoLinkRequest {
+    /// `blocks`, `blocked_by`, `relates_to` or `duplicate_of`,
+    /// read as "this todo <kind> `todo_id`".
+    pub kind: String,
+    pub todo_id: Uuid,
+}
+
+#[derive(Debug, Serialize, FromRow, utoipa::ToSchema)]
+pub struct TodoLinkResponse {
+    pub id: Uuid,
+    /// Stored kind: `blocks`, `relates_to` or `duplicate_of`.
+    pub kind: String,
+    pub source_id: Uuid,
+    pub target_id: Uuid,
+    /// The todo on the other end of the link. Its title and status are
+    /// `None` when you cannot see it.
+    pub todo_id: Uuid,
+    pub todo_title: Option<String>,
+    pub todo_status: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,3 +1,13 @@
+pub mod admin;
 pub mod ai;
+pub mod attachment;
 pub mod auth;
+pub mod bulk;
+pub mod comment;
+pub mod filter;
+pub mod history;
+pub mod label;
+pub mod link;
+pub mod nullable;
 pub mod todo;
+pub mod user;
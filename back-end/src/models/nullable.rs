@@ -0,0 +1,35 @@
+//! Serde helper for update fields that distinguish "absent" from `null`:
+//! a missing field stays `None`, an explicit `null` becomes `Some(None)`.
+//! Use with `#[serde(default, deserialize_with = "nullable::deserialize")]`.
+
+use serde::{Deserialize, Deserializer};
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Payload {
+        #[serde(default, deserialize_with = "super::deserialize")]
+        value: Option<Option<i32>>,
+    }
+
+    #[test]
+    fn distinguishes_missing_null_and_value() {
+        let missing: Payload = serde_json::from_str("{}").expect("payload");
+        let null: Payload = serde_json::from_str(r#"{"value":null}"#).expect("payload");
+        let value: Payload = serde_json::from_str(r#"{"value":3}"#).expect("payload");
+
+        assert_eq!(missing.value, None);
+        assert_eq!(null.value, Some(None));
+        assert_eq!(value.value, Some(Some(3)));
+    }
+}
@@ -1,29 +1,132 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, types::Json};
 use uuid::Uuid;
 
+use crate::models::{
+    attachment::TodoAttachmentResponse,
+    comment::TodoCommentResponse,
+    label::{LabelResponse, TodoLabel},
+    nullable,
+};
+
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct CreateTodoRequest {
     pub title: String,
+    /// Markdown description.
+    pub description: Option<String>,
     pub status: Option<String>,
     pub assignee_id: Option<Uuid>,
+    /// `urgent`, `high`, `medium` (default) or `low`.
+    pub priority: Option<String>,
+    /// Story points or hours, between 0 and 1000.
+    pub estimate: Option<f64>,
+    pub start_at: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
+    /// RFC 5545 RRULE such as `FREQ=WEEKLY;BYDAY=MO`; requires `due_at`.
+    pub recurrence: Option<String>,
+    /// Creates the todo as a subtask of this todo.
+    pub parent_id: Option<Uuid>,
 }
 
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct UpdateTodoRequest {
     pub title: Option<String>,
+    /// Markdown description; an empty string clears it.
+    pub description: Option<String>,
     pub completed: Option<bool>,
     pub status: Option<String>,
-    pub position: Option<i32>,
+    pub position: Option<f64>,
+    /// `null` is ignored; `PATCH` with `null` unassigns.
     pub assignee_id: Option<Uuid>,
+    pub priority: Option<String>,
+    /// `null` clears the estimate.
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<f64>)]
+    pub estimate: Option<Option<f64>>,
+    /// `null` clears the start date.
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub start_at: Option<Option<DateTime<Utc>>>,
+    /// `null` clears the due date.
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    /// Moves the todo under another todo; `null` makes it top-level.
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<Uuid>)]
+    pub parent_id: Option<Option<Uuid>>,
+}
+
+/// `PATCH /todos/{id}` body, an RFC 7396 merge patch: absent members are left
+/// alone and `null` clears a field. `title`, `completed`, `status`, `position`
+/// and `priority` cannot be cleared; a `null` description empties it.
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PatchTodoRequest {
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<String>)]
+    pub title: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<String>)]
+    pub description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<bool>)]
+    pub completed: Option<Option<bool>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<String>)]
+    pub status: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<f64>)]
+    pub position: Option<Option<f64>>,
+    /// `null` unassigns the todo.
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<Uuid>)]
+    pub assignee_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<String>)]
+    pub priority: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<f64>)]
+    pub estimate: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub start_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    #[schema(value_type = Option<Uuid>)]
+    pub parent_id: Option<Option<Uuid>>,
+}
+
+/// `PUT` ignores a `null` assignee; every other field keeps its meaning.
+impl From<UpdateTodoRequest> for PatchTodoRequest {
+    fn from(payload: UpdateTodoRequest) -> Self {
+        Self {
+            title: payload.title.map(Some),
+            description: payload.description.map(Some),
+            completed: payload.completed.map(Some),
+            status: payload.status.map(Some),
+            position: payload.position.map(Some),
+            assignee_id: payload.assignee_id.map(Some),
+            priority: payload.priority.map(Some),
+            estimate: payload.estimate,
+            start_at: payload.start_at,
+            due_at: payload.due_at,
+            parent_id: payload.parent_id,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct ReorderTodoItem {
     pub id: Uuid,
     pub status: String,
-    pub position: i32,
+    /// Must not be taken by another todo of the column after the reorder.
+    pub position: f64,
+    /// Like `If-Match`: the item is only moved while the todo has this version.
+    pub version: Option<i64>,
 }
 
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
@@ -31,11 +134,160 @@ pub struct ReorderTodosRequest {
     pub items: Vec<ReorderTodoItem>,
 }
 
+/// Places a todo in a column by its neighbours. Give at most one of
+/// `after_id` and `before_id`; with neither, the todo goes to the end.
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+pub struct MoveTodoRequest {
+    /// Target status; defaults to the current one.
+    pub status: Option<String>,
+    /// Place the todo right after this todo of the target column.
+    pub after_id: Option<Uuid>,
+    /// Place the todo right before this todo of the target column.
+    pub before_id: Option<Uuid>,
+}
+
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListTodosQuery {
+    /// Page size, between 1 and 500 (default 100).
+    pub limit: Option<u32>,
+    /// Opaque cursor taken from the `X-Next-Cursor` header of the previous page.
+    pub cursor: Option<String>,
+    /// Comma-separated list of statuses.
+    pub status: Option<String>,
+    /// Comma-separated list of priorities.
+    pub priority: Option<String>,
+    pub assignee_id: Option<Uuid>,
+    pub reporter_id: Option<Uuid>,
+    pub completed: Option<bool>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    /// One of `overdue`, `today`, `this_week`, evaluated in the caller's time zone.
+    pub due: Option<String>,
+    /// One of `board` (default), `created_at`, `-created_at`, `updated_at`, `-updated_at`,
+    /// `due_at`, `-due_at` (todos without a due date come last), `priority` (most urgent first,
+    /// then board order), `-priority`.
+    pub sort: Option<String>,
+    /// Filter expression, e.g. `status:in_progress assignee:me -completed:true updated:>7d`.
+    pub filter: Option<String>,
+    /// Comma-separated label names (case-insensitive).
+    pub label: Option<String>,
+    /// `any` (default) or `all` of the labels in `label`.
+    pub label_match: Option<String>,
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchTodosQuery {
+    /// Search text; accents are ignored and `"quoted phrases"`, `or` and `-word` are supported.
+    pub q: String,
+    /// Maximum number of results, between 1 and 100 (default 20).
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, FromRow, utoipa::ToSchema)]
+pub struct TodoSearchResult {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub todo: TodoResponse,
+    pub rank: f32,
+    /// HTML-escaped snippet of the title and description excerpt with matches wrapped in `<mark>`.
+    pub highlight: String,
+}
+
+#[derive(Debug)]
+pub struct TodoPage {
+    pub items: Vec<TodoResponse>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct UndoResponse {
+    /// The operation that was undone: `update`, `reorder` or `delete`.
+    pub operation: String,
+    /// The affected todos as they are now.
+    pub todos: Vec<TodoResponse>,
+}
+
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct TodoRealtimeEvent {
     pub event: String,
     pub todo: Option<TodoResponse>,
     pub todo_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reminder: Option<TodoReminder>,
+    /// Set on `label_created`, `label_updated` and `label_deleted` events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<LabelResponse>,
+    /// Set on `comment_created`, `comment_updated`, `comment_deleted` and
+    /// `todo_mentioned` events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<TodoCommentResponse>,
+    /// Set on `attachment_added` and `attachment_removed` events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<TodoAttachmentResponse>,
+    /// Set on `todos_bulk_updated` and `todos_bulk_deleted` events: the
+    /// affected todos the recipient can see.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todos: Option<Vec<TodoResponse>>,
+}
+
+/// Details of a `todo_reminder` realtime event.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct TodoReminder {
+    pub due_at: DateTime<Utc>,
+    /// Lead time that triggered the reminder; absent when a snooze ended.
+    pub lead_minutes: Option<i32>,
+    pub snoozed: bool,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct RecurrenceRequest {
+    /// RFC 5545 RRULE, e.g. `FREQ=MONTHLY;BYMONTHDAY=1`. Daily or less frequent.
+    pub rrule: String,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct SnoozeReminderRequest {
+    /// Minutes until the reminder fires again, between 5 and 10080 (one week).
+    pub minutes: i64,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct SnoozeReminderResponse {
+    pub todo_id: Uuid,
+    pub snoozed_until: DateTime<Utc>,
+}
+
+/// Totals for one status column of the board.
+#[derive(Debug, Clone, Serialize, FromRow, utoipa::ToSchema)]
+pub struct TodoColumnSummary {
+    pub status: String,
+    pub todo_count: i64,
+    /// Todos in the column that have an estimate.
+    pub estimated_count: i64,
+    pub estimate_total: f64,
+}
+
+impl TodoColumnSummary {
+    pub fn empty(status: &str) -> Self {
+        Self {
+            status: status.to_string(),
+            todo_count: 0,
+            estimated_count: 0,
+            estimate_total: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TodoSummary {
+    pub id: Uuid,
+    /// `None`, like `status`, when someone on this todo cannot see it.
+    pub title: Option<String>,
+    pub status: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, FromRow, utoipa::ToSchema)]
@@ -47,9 +299,40 @@ pub struct TodoResponse {
     pub assignee_id: Option<Uuid>,
     pub assignee_email: Option<String>,
     pub title: String,
+    /// Markdown source.
+    pub description: String,
+    /// Sanitized HTML rendered from `description`.
+    pub description_html: String,
+    /// Plain-text summary of `description` for list views.
+    pub description_excerpt: String,
     pub completed: bool,
     pub status: String,
-    pub position: i32,
+    /// Order within the status column. Positions are fractional and only
+    /// their order is meaningful; a rebalance may renumber them.
+    pub position: f64,
+    /// One of `urgent`, `high`, `medium`, `low`.
+    pub priority: String,
+    /// Story points or hours, as the team prefers.
+    pub estimate: Option<f64>,
+    pub start_at: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub series_id: Option<Uuid>,
+    /// RRULE of the series while it is still generating occurrences.
+    pub recurrence: Option<String>,
+    pub parent_id: Option<Uuid>,
+    /// Number of direct subtasks.
+    pub subtask_count: i64,
+    /// Number of direct subtasks that are completed.
+    pub subtasks_done: i64,
+    /// Open todos that block this one.
+    #[schema(value_type = Vec<TodoSummary>)]
+    pub blocked_by: Json<Vec<TodoSummary>>,
+    #[schema(value_type = Vec<TodoLabel>)]
+    pub labels: Json<Vec<TodoLabel>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set while the todo is in the trash.
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Incremented on every change and returned as the `ETag`.
+    pub version: i64,
 }
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, FromRow, utoipa::ToSchema)]
+pub struct UserPreferencesResponse {
+    /// IANA time zone used for due-date windows, e.g. `Asia/Ho_Chi_Minh`.
+    pub time_zone: String,
+    /// Minutes before `due_at` at which reminders are sent; empty disables them.
+    pub reminder_lead_minutes: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct UpdateUserPreferencesRequest {
+    pub time_zone: Option<String>,
+    /// Up to 5 lead times, each between 1 and 10080 minutes (one week).
+    pub reminder_lead_minutes: Option<Vec<i32>>,
+}
@@ -0,0 +1,452 @@
+use std::time::Duration as StdDuration;
+
+use axum::body::Body;
+use hyper::body::Bytes;
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        attachment::TodoAttachmentResponse,
+        todo::{TodoRealtimeEvent, TodoResponse},
+    },
+    services::todo_service,
+    state::AppState,
+};
+
+const MAX_ATTACHMENTS_PER_TODO: i64 = 50;
+const MAX_FILE_NAME_CHARS: usize = 255;
+const CLEANUP_BATCH_SIZE: i64 = 100;
+const ATTACHMENT_COLUMNS: &str =
+    "id, todo_id, uploader_id, file_name, content_type, size_bytes, sha256, created_at";
+
+/// A file read from a multipart upload.
+pub struct UploadedFile {
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub bytes: Bytes,
+}
+
+/// Keeps the last path segment of a client-supplied name and drops
+/// characters that would break a `Content-Disposition` header.
+fn normalize_file_name(file_name: Option<&str>) -> String {
+    let name = file_name
+        .unwrap_or_default()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default();
+    let name: String = name
+        .chars()
+        .filter(|ch| !ch.is_control() && *ch != '"')
+        .take(MAX_FILE_NAME_CHARS)
+        .collect();
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." {
+        "attachment".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Builds a `Content-Disposition` value with an ASCII fallback name and the
+/// exact UTF-8 name (RFC 6266).
+pub fn content_disposition(file_name: &str, inline: bool) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii() && !ch.is_ascii_control() && ch != '\\' && ch != '"' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let mut encoded = String::with_capacity(file_name.len());
+    for byte in file_name.bytes() {
+        if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    let kind = if inline { "inline" } else { "attachment" };
+    format!("{kind}; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
+
+/// Recognizes the binary formats we accept from their leading bytes.
+fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if bytes.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+        Some("application/zip")
+    } else if bytes.starts_with(b"\x1f\x8b") {
+        Some("application/gzip")
+    } else {
+        None
+    }
+}
+
+fn is_allowed(content_type: &str, allowed_types: &[String]) -> bool {
+    allowed_types
+        .iter()
+        .any(|allowed| match allowed.strip_suffix("/*") {
+            Some(prefix) => content_type
+                .split_once('/')
+                .is_some_and(|(kind, _)| kind == prefix),
+            None => allowed == content_type,
+        })
+}
+
+/// Resolves the stored content type from the declared one and the file's
+/// leading bytes. A declared type must agree with any recognized signature,
+/// so binary files cannot be passed off as text or as another format.
+fn resolve_content_type(
+    declared: Option<&str>,
+    bytes: &[u8],
+    allowed_types: &[String],
+) -> Result<String, AppError> {
+    let declared = declared
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty() && value != "application/octet-stream");
+    let sniffed = sniff_content_type(bytes);
+
+    let content_type = match (declared, sniffed) {
+        (Some(declared), Some(sniffed)) if declared != sniffed => {
+            return Err(AppError::BadRequest(format!(
+                "file content does not match {declared}"
+            )));
+        }
+        (Some(declared), _) => declared,
+        (None, Some(sniffed)) => sniffed.to_string(),
+        (None, None) => {
+            return Err(AppError::BadRequest(
+                "could not determine the file type".to_string(),
+            ));
+        }
+    };
+
+    if !is_allowed(&content_type, allowed_types) {
+        return Err(AppError::BadRequest(format!(
+            "file type {content_type} is not allowed"
+        )));
+    }
+    Ok(content_type)
+}
+
+pub async fn list_attachments(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<Vec<TodoAttachmentResponse>, AppError> {
+    todo_service::get_todo(state, user_id, todo_id).await?;
+
+    let attachments = sqlx::query_as::<_, TodoAttachmentResponse>(&format!(
+        "SELECT {ATTACHMENT_COLUMNS} FROM todo_attachments WHERE todo_id = $1 ORDER BY created_at, id"
+    ))
+    .bind(todo_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(attachments)
+}
+
+pub async fn upload_attachment(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    file: UploadedFile,
+) -> Result<TodoAttachmentResponse, AppError> {
+    if file.bytes.is_empty() {
+        return Err(AppError::BadRequest("file is empty".to_string()));
+    }
+    let content_type = resolve_content_type(
+        file.content_type.as_deref(),
+        &file.bytes,
+        &state.attachments.allowed_types,
+    )?;
+    let file_name = normalize_file_name(file.file_name.as_deref());
+    let todo = todo_service::get_todo(state, user_id, todo_id).await?;
+
+    let existing: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM todo_attachments WHERE todo_id = $1")
+            .bind(todo_id)
+            .fetch_one(&state.db)
+            .await?;
+    if existing >= MAX_ATTACHMENTS_PER_TODO {
+        return Err(AppError::BadRequest(format!(
+            "a todo can have at most {MAX_ATTACHMENTS_PER_TODO} attachments"
+        )));
+    }
+
+    let attachment_id = Uuid::new_v4();
+    let storage_key = format!("todos/{todo_id}/{attachment_id}");
+    let sha256 = hex::encode(Sha256::digest(&file.bytes));
+    let size_bytes = file.bytes.len() as i64;
+    state
+        .attachment_storage
+        .put(&storage_key, &content_type, file.bytes)
+        .await?;
+
+    let inserted = sqlx::query_as::<_, TodoAttachmentResponse>(&format!(
+        "INSERT INTO todo_attachments (id, todo_id, uploader_id, file_name, content_type, size_bytes, sha256, storage_key)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING {ATTACHMENT_COLUMNS}"
+    ))
+    .bind(attachment_id)
+    .bind(todo_id)
+    .bind(user_id)
+    .bind(file_name)
+    .bind(content_type)
+    .bind(size_bytes)
+    .bind(sha256)
+    .bind(&storage_key)
+    .fetch_one(&state.db)
+    .await;
+    let attachment = match inserted {
+        Ok(attachment) => attachment,
+        Err(err) => {
+            // The todo may have been deleted while the file was uploading.
+            if let Err(cleanup_err) = state.attachment_storage.delete(&storage_key).await {
+                tracing::warn!(storage_key, error = %cleanup_err, "failed to remove orphaned attachment");
+            }
+            if let sqlx::Error::Database(db_err) = &err {
+                if db_err.constraint() == Some("todo_attachments_todo_id_fkey") {
+                    return Err(AppError::NotFound);
+                }
+            }
+            return Err(AppError::from(err));
+        }
+    };
+
+    broadcast_attachment_event(state, user_id, &todo, "attachment_added", &attachment).await;
+    Ok(attachment)
+}
+
+/// Loads an attachment of a todo the user can see, with its stored bytes.
+pub async fn open_attachment(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    attachment_id: Uuid,
+) -> Result<(TodoAttachmentResponse, Body), AppError> {
+    todo_service::get_todo(state, user_id, todo_id).await?;
+    let (attachment, storage_key) = find_attachment(state, todo_id, attachment_id).await?;
+
+    let body = state
+        .attachment_storage
+        .get(&storage_key)
+        .await
+        .map_err(|err| {
+            if matches!(err, AppError::NotFound) {
+                tracing::error!(%attachment_id, storage_key, "attachment object is missing");
+                AppError::Internal
+            } else {
+                err
+            }
+        })?;
+    Ok((attachment, body))
+}
+
+/// Removes an attachment. Allowed for the uploader and the todo's reporter.
+pub async fn delete_attachment(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    attachment_id: Uuid,
+) -> Result<(), AppError> {
+    let todo = todo_service::get_todo(state, user_id, todo_id).await?;
+    let (attachment, _) = find_attachment(state, todo_id, attachment_id).await?;
+    if attachment.uploader_id != user_id && todo.reporter_id != user_id {
+        return Err(AppError::Forbidden);
+    }
+
+    sqlx::query("DELETE FROM todo_attachments WHERE id = $1")
+        .bind(attachment_id)
+        .execute(&state.db)
+        .await?;
+
+    schedule_cleanup(state);
+    broadcast_attachment_event(state, user_id, &todo, "attachment_removed", &attachment).await;
+    Ok(())
+}
+
+#[derive(FromRow)]
+struct StoredAttachment {
+    #[sqlx(flatten)]
+    attachment: TodoAttachmentResponse,
+    storage_key: String,
+}
+
+async fn find_attachment(
+    state: &AppState,
+    todo_id: Uuid,
+    attachment_id: Uuid,
+) -> Result<(TodoAttachmentResponse, String), AppError> {
+    let stored = sqlx::query_as::<_, StoredAttachment>(&format!(
+        "SELECT {ATTACHMENT_COLUMNS}, storage_key FROM todo_attachments WHERE id = $1 AND todo_id = $2"
+    ))
+    .bind(attachment_id)
+    .bind(todo_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok((stored.attachment, stored.storage_key))
+}
+
+async fn broadcast_attachment_event(
+    state: &AppState,
+    actor_id: Uuid,
+    todo: &TodoResponse,
+    event: &str,
+    attachment: &TodoAttachmentResponse,
+) {
+    let mut targets = vec![todo.reporter_id];
+    targets.extend(todo.assignee_id);
+    let payload = TodoRealtimeEvent {
+        event: event.to_string(),
+        todo: None,
+        todo_id: Some(todo.id),
+        reminder: None,
+        label: None,
+        comment: None,
+        attachment: Some(attachment.clone()),
+        todos: None,
+    };
+
+    if let Ok(message) = serde_json::to_string(&payload) {
+        state
+            .todo_realtime_hub
+            .broadcast_todo_change(actor_id, &targets, message)
+            .await;
+    }
+}
+
+/// Deletes stored objects whose metadata rows are gone, in the background.
+/// Called after deletions so files disappear promptly; the periodic job
+/// retries whatever fails here.
+pub fn schedule_cleanup(state: &AppState) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        if let Err(err) = run_cleanup_once(&state).await {
+            tracing::warn!(error = %err, "attachment cleanup failed");
+        }
+    });
+}
+
+/// Starts the background loop that removes objects of deleted attachments.
+pub fn spawn_cleanup(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(StdDuration::from_secs(
+            state.attachments.cleanup_interval_seconds,
+        ));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match run_cleanup_once(&state).await {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!(deleted, "attachment objects deleted"),
+                Err(err) => tracing::error!(error = %err, "attachment cleanup failed"),
+            }
+        }
+    });
+}
+
+/// Deletes one batch of queued objects. Rows stay queued when the storage
+/// backend fails, so the next run retries them.
+pub async fn run_cleanup_once(state: &AppState) -> Result<usize, AppError> {
+    let mut tx = state.db.begin().await?;
+    let keys = sqlx::query_scalar::<_, String>(
+        "SELECT storage_key FROM attachment_deletions ORDER BY queued_at LIMIT $1 FOR UPDATE SKIP LOCKED",
+    )
+    .bind(CLEANUP_BATCH_SIZE)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut deleted = Vec::with_capacity(keys.len());
+    for key in keys {
+        match state.attachment_storage.delete(&key).await {
+            Ok(()) => deleted.push(key),
+            Err(err) => {
+                tracing::warn!(storage_key = key, error = %err, "failed to delete attachment object")
+            }
+        }
+    }
+
+    sqlx::query("DELETE FROM attachment_deletions WHERE storage_key = ANY($1)")
+        .bind(&deleted)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(deleted.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed() -> Vec<String> {
+        vec![
+            "image/*".to_string(),
+            "text/plain".to_string(),
+            "application/pdf".to_string(),
+        ]
+    }
+
+    #[test]
+    fn file_names_keep_the_last_segment() {
+        assert_eq!(normalize_file_name(Some("../../etc/passwd")), "passwd");
+        assert_eq!(normalize_file_name(Some("C:\\logs\\app.log")), "app.log");
+        assert_eq!(normalize_file_name(Some("a\"b\n.txt")), "ab.txt");
+        assert_eq!(normalize_file_name(Some("  ")), "attachment");
+        assert_eq!(normalize_file_name(None), "attachment");
+    }
+
+    #[test]
+    fn content_disposition_escapes_names() {
+        assert_eq!(
+            content_disposition("report 1.pdf", false),
+            "attachment; filename=\"report 1.pdf\"; filename*=UTF-8''report%201.pdf"
+        );
+        assert_eq!(
+            content_disposition("résumé.png", true),
+            "inline; filename=\"r_sum_.png\"; filename*=UTF-8''r%C3%A9sum%C3%A9.png"
+        );
+    }
+
+    #[test]
+    fn content_types_are_checked_against_signatures_and_allowlist() {
+        let png = b"\x89PNG\r\n\x1a\nrest";
+        assert_eq!(
+            resolve_content_type(Some("image/png"), png, &allowed()).expect("png"),
+            "image/png"
+        );
+        assert_eq!(
+            resolve_content_type(Some("application/octet-stream"), png, &allowed()).expect("png"),
+            "image/png"
+        );
+        assert_eq!(
+            resolve_content_type(Some("Text/Plain; charset=utf-8"), b"log line", &allowed())
+                .expect("text"),
+            "text/plain"
+        );
+        assert!(resolve_content_type(Some("text/plain"), png, &allowed()).is_err());
+        assert!(resolve_content_type(Some("image/jpeg"), png, &allowed()).is_err());
+        assert!(resolve_content_type(Some("text/html"), b"<html>", &allowed()).is_err());
+        assert!(resolve_content_type(None, b"plain", &allowed()).is_err());
+        assert!(resolve_content_type(None, b"PK\x03\x04", &allowed()).is_err());
+    }
+}
@@ -1,28 +1,36 @@
-use argon2::{PasswordHasher, PasswordVerifier};
 use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
-use rand::rngs::OsRng;
 use sha2::{Digest, Sha256};
 use sqlx::FromRow;
 use uuid::Uuid;
 
 use crate::{
+    client_ip::SessionMetadata,
     error::AppError,
+    locale::Language,
     models::auth::{
-        AuthResponse, Claims, ForgotPasswordRequest, LoginRequest, RegisterRequest,
-        ResetPasswordRequest, Role, UserResponse,
+        ActorClaim, AuthResponse, ChangePasswordRequest, Claims, ForgotPasswordRequest,
+        LoginRequest, RegisterRequest, ResetPasswordRequest, Role, UserResponse,
     },
-    services::email_service,
-    state::AppState,
+    services::{
+        email_service,
+        password_service::{self, PasswordVerification},
+    },
+    state::{AppState, PasswordPolicyConfig},
 };
 
 pub async fn register(
     state: &AppState,
     payload: RegisterRequest,
+    session: &SessionMetadata,
+    language: Language,
 ) -> Result<(AuthResponse, String), AppError> {
-    validate_register_payload(&payload)?;
+    validate_register_payload(&state.password_policy, &payload, language)?;
+    password_service::check_breached_password(&state.password_policy, &payload.password)
+        .await
+        .map_err(|violation| AppError::BadRequest(violation.message(language)))?;
 
-    let password_hash = hash_password(&payload.password)?;
+    let password_hash = password_service::hash_password(&state.password, &payload.password).await?;
     let user_id = Uuid::new_v4();
 
     let user = sqlx::query_as::<_, UserResponseRow>(
@@ -42,7 +50,7 @@ pub async fn register(
         AppError::from(err)
     })?;
 
-    let tokens = create_tokens(state, user.id).await?;
+    let tokens = create_tokens(state, user.id, session).await?;
     Ok((
         AuthResponse {
             user: user.into_response()?,
@@ -55,6 +63,7 @@ pub async fn register(
 pub async fn login(
     state: &AppState,
     payload: LoginRequest,
+    session: &SessionMetadata,
 ) -> Result<(AuthResponse, String), AppError> {
     let user = sqlx::query_as::<_, UserRow>(
         "SELECT id, email, password_hash, role FROM users WHERE email = $1",
@@ -64,10 +73,15 @@ pub async fn login(
     .await?
     .ok_or(AppError::Unauthorized)?;
 
-    verify_password(&payload.password, &user.password_hash)?;
+    let verification =
+        password_service::verify_password(&state.password, &payload.password, &user.password_hash)
+            .await?;
+    if verification == PasswordVerification::NeedsRehash {
+        rehash_password(state, user.id, &payload.password, &user.password_hash).await;
+    }
 
     let role = user.role_from_db()?;
-    let tokens = create_tokens(state, user.id).await?;
+    let tokens = create_tokens(state, user.id, session).await?;
     Ok((
         AuthResponse {
             user: UserResponse {
@@ -84,6 +98,7 @@ pub async fn login(
 pub async fn refresh(
     state: &AppState,
     refresh_token: &str,
+    session: &SessionMetadata,
 ) -> Result<(AuthResponse, String), AppError> {
     let token_hash = hash_token(refresh_token);
 
@@ -105,7 +120,7 @@ pub async fn refresh(
         .await?;
 
     let role = row.role_from_db()?;
-    let tokens = create_tokens(state, row.user_id).await?;
+    let tokens = create_tokens(state, row.user_id, session).await?;
     Ok((
         AuthResponse {
             user: UserResponse {
@@ -179,14 +194,13 @@ pub async fn forgot_password(
 pub async fn reset_password(
     state: &AppState,
     payload: ResetPasswordRequest,
+    language: Language,
 ) -> Result<(), AppError> {
-    validate_password_basic(&payload.password)?;
-
     let token_hash = hash_token(&payload.token);
     let mut tx = state.db.begin().await?;
 
     let row = sqlx::query_as::<_, PasswordResetRow>(
-        "SELECT user_id, expires_at FROM password_resets WHERE token_hash = $1 AND used_at IS NULL",
+        "SELECT password_resets.user_id, password_resets.expires_at, users.email FROM password_resets JOIN users ON users.id = password_resets.user_id WHERE password_resets.token_hash = $1 AND password_resets.used_at IS NULL",
     )
     .bind(&token_hash)
     .fetch_optional(&mut *tx)
@@ -199,7 +213,9 @@ pub async fn reset_password(
         ));
     }
 
-    let password_hash = hash_password(&payload.password)?;
+    validate_new_password(state, &payload.password, &row.email, language).await?;
+
+    let password_hash = password_service::hash_password(&state.password, &payload.password).await?;
     sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
         .bind(password_hash)
         .bind(row.user_id)
@@ -220,6 +236,75 @@ pub async fn reset_password(
     Ok(())
 }
 
+pub async fn change_password(
+    state: &AppState,
+    user_id: Uuid,
+    payload: ChangePasswordRequest,
+    language: Language,
+) -> Result<(), AppError> {
+    let user = sqlx::query_as::<_, UserRow>(
+        "SELECT id, email, password_hash, role FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::Unauthorized)?;
+
+    // Verify the caller first, so the policy (and its breach lookup) is never
+    // run for someone who does not know the current password.
+    password_service::verify_password(
+        &state.password,
+        &payload.current_password,
+        &user.password_hash,
+    )
+    .await
+    .map_err(|err| match err {
+        AppError::Unauthorized => AppError::BadRequest("current password is incorrect".to_string()),
+        err => err,
+    })?;
+
+    validate_new_password(state, &payload.new_password, &user.email, language).await?;
+
+    let password_hash =
+        password_service::hash_password(&state.password, &payload.new_password).await?;
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(password_hash)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM refresh_tokens WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn rehash_password(state: &AppState, user_id: Uuid, password: &str, old_hash: &str) {
+    let result = match password_service::hash_password(&state.password, password).await {
+        Ok(new_hash) => {
+            sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2 AND password_hash = $3")
+                .bind(new_hash)
+                .bind(user_id)
+                .bind(old_hash)
+                .execute(&state.db)
+                .await
+                .map(|_| ())
+                .map_err(AppError::from)
+        }
+        Err(error) => Err(error),
+    };
+
+    match result {
+        Ok(()) => tracing::info!(user_id = %user_id, "password hash upgraded"),
+        Err(error) => tracing::warn!(user_id = %user_id, "password rehash failed: {error}"),
+    }
+}
+
 #[derive(Debug, FromRow)]
 struct UserResponseRow {
     id: Uuid,
@@ -269,6 +354,7 @@ impl RefreshRow {
 #[derive(Debug, FromRow)]
 struct PasswordResetRow {
     user_id: Uuid,
+    email: String,
     expires_at: DateTime<Utc>,
 }
 
@@ -277,10 +363,14 @@ struct TokenPair {
     refresh_token: String,
 }
 
-fn validate_register_payload(payload: &RegisterRequest) -> Result<(), AppError> {
+fn validate_register_payload(
+    policy: &PasswordPolicyConfig,
+    payload: &RegisterRequest,
+    language: Language,
+) -> Result<(), AppError> {
     validate_email(&payload.email)?;
-    validate_password_basic(&payload.password)?;
-    Ok(())
+    password_service::check_password_rules(policy, &payload.password, &payload.email)
+        .map_err(|violation| AppError::BadRequest(violation.message(language)))
 }
 
 fn validate_email(email: &str) -> Result<(), AppError> {
@@ -290,39 +380,15 @@ fn validate_email(email: &str) -> Result<(), AppError> {
     Ok(())
 }
 
-fn validate_password_basic(password: &str) -> Result<(), AppError> {
-    if password.len() < 8 {
-        return Err(AppError::BadRequest(
-            "password must be at least 8 characters".to_string(),
-        ));
-    }
-    let has_letter = password.chars().any(|c| c.is_ascii_alphabetic());
-    let has_number = password.chars().any(|c| c.is_ascii_digit());
-    if !has_letter || !has_number {
-        return Err(AppError::BadRequest(
-            "password must include letters and numbers".to_string(),
-        ));
-    }
-    Ok(())
-}
-
-fn hash_password(password: &str) -> Result<String, AppError> {
-    let salt = argon2::password_hash::SaltString::generate(&mut OsRng);
-    let argon = argon2::Argon2::default();
-    let hash = argon
-        .hash_password(password.as_bytes(), &salt)
-        .map_err(|_| AppError::Internal)?
-        .to_string();
-    Ok(hash)
-}
-
-fn verify_password(password: &str, hash: &str) -> Result<(), AppError> {
-    let parsed_hash =
-        argon2::password_hash::PasswordHash::new(hash).map_err(|_| AppError::Unauthorized)?;
-    let argon = argon2::Argon2::default();
-    argon
-        .verify_password(password.as_bytes(), &parsed_hash)
-        .map_err(|_| AppError::Unauthorized)
+async fn validate_new_password(
+    state: &AppState,
+    password: &str,
+    email: &str,
+    language: Language,
+) -> Result<(), AppError> {
+    password_service::check_password_policy(&state.password_policy, password, email)
+        .await
+        .map_err(|violation| AppError::BadRequest(violation.message(language)))
 }
 
 fn hash_token(token: &str) -> String {
@@ -341,10 +407,36 @@ fn create_access_token(
         sub: user_id.to_string(),
         role: role.as_str().to_string(),
         exp,
+        act: None,
+        sid: None,
     };
+    encode_claims(secret, &claims)
+}
+
+pub fn create_impersonation_token(
+    secret: &str,
+    user_id: Uuid,
+    role: Role,
+    actor_id: Uuid,
+    session_id: Uuid,
+    expires_at: DateTime<Utc>,
+) -> Result<String, AppError> {
+    let claims = Claims {
+        sub: user_id.to_string(),
+        role: role.as_str().to_string(),
+        exp: expires_at.timestamp() as usize,
+        act: Some(ActorClaim {
+            sub: actor_id.to_string(),
+        }),
+        sid: Some(session_id.to_string()),
+    };
+    encode_claims(secret, &claims)
+}
+
+fn encode_claims(secret: &str, claims: &Claims) -> Result<String, AppError> {
     encode(
         &Header::default(),
-        &claims,
+        claims,
         &EncodingKey::from_secret(secret.as_bytes()),
     )
     .map_err(|_| AppError::Internal)
@@ -360,7 +452,11 @@ pub fn decode_token(secret: &str, token: &str) -> Result<Claims, AppError> {
     .map_err(|_| AppError::Unauthorized)
 }
 
-async fn create_tokens(state: &AppState, user_id: Uuid) -> Result<TokenPair, AppError> {
+async fn create_tokens(
+    state: &AppState,
+    user_id: Uuid,
+    session: &SessionMetadata,
+) -> Result<TokenPair, AppError> {
     let user_role = sqlx::query_scalar::<_, String>("SELECT role FROM users WHERE id = $1")
         .bind(user_id)
         .fetch_optional(&state.db)
@@ -380,12 +476,14 @@ async fn create_tokens(state: &AppState, user_id: Uuid) -> Result<TokenPair, App
     let expires_at = Utc::now() + Duration::days(state.jwt.refresh_ttl_days);
 
     sqlx::query(
-        "INSERT INTO refresh_tokens (id, user_id, token_hash, expires_at) VALUES ($1, $2, $3, $4)",
+        "INSERT INTO refresh_tokens (id, user_id, token_hash, expires_at, ip_address, user_agent) VALUES ($1, $2, $3, $4, $5, $6)",
     )
     .bind(Uuid::new_v4())
     .bind(user_id)
     .bind(token_hash)
     .bind(expires_at)
+    .bind(session.ip_address.map(|ip| ip.to_string()))
+    .bind(session.user_agent.as_deref())
     .execute(&state.db)
     .await?;
 
@@ -400,16 +498,6 @@ mod tests {
     use super::*;
     use crate::models::auth::RegisterRequest;
 
-    #[test]
-    fn password_hash_and_verify_round_trip() {
-        let password = "strong-password";
-        let hash = hash_password(password).expect("hash");
-        verify_password(password, &hash).expect("verify ok");
-
-        let wrong = verify_password("bad-password", &hash);
-        assert!(matches!(wrong, Err(AppError::Unauthorized)));
-    }
-
     #[test]
     fn hash_token_is_sha256_hex() {
         let hashed = hash_token("refresh-token");
@@ -436,7 +524,8 @@ mod tests {
             password: "password123".to_string(),
         };
 
-        let result = validate_register_payload(&payload);
+        let result =
+            validate_register_payload(&PasswordPolicyConfig::default(), &payload, Language::En);
         assert!(matches!(result, Err(AppError::BadRequest(msg)) if msg.contains("invalid email")));
     }
 
@@ -447,7 +536,8 @@ mod tests {
             password: "short".to_string(),
         };
 
-        let result = validate_register_payload(&payload);
+        let result =
+            validate_register_payload(&PasswordPolicyConfig::default(), &payload, Language::En);
         assert!(matches!(result, Err(AppError::BadRequest(msg)) if msg.contains("at least 8")));
     }
 
@@ -458,12 +548,25 @@ mod tests {
             password: "passwordonly".to_string(),
         };
 
-        let result = validate_register_payload(&payload);
+        let result =
+            validate_register_payload(&PasswordPolicyConfig::default(), &payload, Language::En);
         assert!(
             matches!(result, Err(AppError::BadRequest(msg)) if msg.contains("letters and numbers"))
         );
     }
 
+    #[test]
+    fn validate_register_localizes_policy_violations() {
+        let payload = RegisterRequest {
+            email: "user@example.com".to_string(),
+            password: "short".to_string(),
+        };
+
+        let result =
+            validate_register_payload(&PasswordPolicyConfig::default(), &payload, Language::Vi);
+        assert!(matches!(result, Err(AppError::BadRequest(msg)) if msg.contains("ít nhất 8")));
+    }
+
     #[test]
     fn decode_token_rejects_wrong_secret() {
         let user_id = Uuid::new_v4();
@@ -484,6 +587,41 @@ mod tests {
         assert!(claims.exp >= Utc::now().timestamp() as usize);
     }
 
+    #[test]
+    fn impersonation_token_carries_actor_and_session() {
+        let secret = "impersonation-secret";
+        let user_id = Uuid::new_v4();
+        let actor_id = Uuid::new_v4();
+        let session_id = Uuid::new_v4();
+        let expires_at = Utc::now() + Duration::minutes(10);
+
+        let token = create_impersonation_token(
+            secret,
+            user_id,
+            Role::User,
+            actor_id,
+            session_id,
+            expires_at,
+        )
+        .expect("token");
+        let claims = decode_token(secret, &token).expect("claims");
+
+        assert_eq!(claims.sub, user_id.to_string());
+        assert_eq!(claims.act.map(|act| act.sub), Some(actor_id.to_string()));
+        assert_eq!(claims.sid, Some(session_id.to_string()));
+        assert_eq!(claims.exp, expires_at.timestamp() as usize);
+    }
+
+    #[test]
+    fn regular_access_token_has_no_actor() {
+        let secret = "plain-secret";
+        let token = create_access_token(secret, Uuid::new_v4(), Role::Admin, 5).expect("token");
+        let claims = decode_token(secret, &token).expect("claims");
+
+        assert!(claims.act.is_none());
+        assert!(claims.sid.is_none());
+    }
+
     #[test]
     fn role_try_from_maps_known_values() {
         assert_eq!(Role::try_from("user"), Ok(Role::User));
@@ -0,0 +1,336 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use sqlx::{Acquire, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        bulk::{BulkItemResult, BulkOperation, BulkTodosRequest, BulkTodosResponse},
+        todo::{ListTodosQuery, PatchTodoRequest, TodoRealtimeEvent, TodoResponse},
+    },
+    services::{
+        history_service, label_service, position_service, recurrence_service,
+        todo_service::{self, AppliedUpdate},
+        undo_service::{self, UndoStep},
+    },
+    state::AppState,
+};
+
+const MAX_BULK_TODOS: usize = 500;
+
+/// What one item changed, kept until the transaction commits.
+enum Applied {
+    Updated(Box<AppliedUpdate>),
+    Labeled,
+    Deleted(Box<TodoResponse>, Vec<UndoStep>),
+}
+
+/// Drops repeated ids, keeping the first occurrence.
+fn unique_ids(ids: Vec<Uuid>) -> Vec<Uuid> {
+    let mut seen = HashSet::with_capacity(ids.len());
+    ids.into_iter().filter(|id| seen.insert(*id)).collect()
+}
+
+async fn resolve_ids(
+    state: &AppState,
+    user_id: Uuid,
+    ids: Option<Vec<Uuid>>,
+    filter: Option<String>,
+) -> Result<Vec<Uuid>, AppError> {
+    let ids = match (ids, filter) {
+        (Some(ids), None) => unique_ids(ids),
+        (None, Some(filter)) => {
+            let query = ListTodosQuery {
+                filter: Some(filter),
+                limit: Some(MAX_BULK_TODOS as u32),
+                ..ListTodosQuery::default()
+            };
+            let page = todo_service::list_todos(state, user_id, query, None).await?;
+            if page.next_cursor.is_some() {
+                return Err(AppError::BadRequest(format!(
+                    "filter matches more than {MAX_BULK_TODOS} todos"
+                )));
+            }
+            page.items.into_iter().map(|todo| todo.id).collect()
+        }
+        _ => {
+            return Err(AppError::BadRequest(
+                "give either ids or filter".to_string(),
+            ));
+        }
+    };
+
+    if ids.is_empty() {
+        return Err(AppError::BadRequest("no todos selected".to_string()));
+    }
+    if ids.len() > MAX_BULK_TODOS {
+        return Err(AppError::BadRequest(format!(
+            "at most {MAX_BULK_TODOS} todos per request"
+        )));
+    }
+    Ok(ids)
+}
+
+async fn apply_one(
+    state: &AppState,
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    todo_id: Uuid,
+    operation: &BulkOperation,
+) -> Result<Applied, AppError> {
+    match operation {
+        BulkOperation::SetStatus { status } => {
+            let payload = PatchTodoRequest {
+                status: Some(Some(status.clone())),
+                ..PatchTodoRequest::default()
+            };
+            todo_service::apply_update(state, conn, user_id, todo_id, payload, None)
+                .await
+                .map(|update| Applied::Updated(Box::new(update)))
+        }
+        BulkOperation::SetAssignee { assignee_id } => {
+            let payload = PatchTodoRequest {
+                assignee_id: Some(*assignee_id),
+                ..PatchTodoRequest::default()
+            };
+            todo_service::apply_update(state, conn, user_id, todo_id, payload, None)
+                .await
+                .map(|update| Applied::Updated(Box::new(update)))
+        }
+        BulkOperation::SetLabels { label_ids } => {
+            history_service::lock_snapshot(&mut *conn, user_id, todo_id).await?;
+            label_service::replace_todo_labels(conn, user_id, todo_id, label_ids).await?;
+            Ok(Applied::Labeled)
+        }
+        BulkOperation::Delete => {
+            let (todo, steps) = todo_service::apply_delete(conn, user_id, todo_id, None).await?;
+            Ok(Applied::Deleted(Box::new(todo), steps))
+        }
+    }
+}
+
+/// Locks the selected todos in id order, then the columns the operation may
+/// place them in, before any item runs. Moves and reorders also take rows
+/// before columns, so concurrent requests wait instead of deadlocking.
+async fn lock_targets(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    ids: &[Uuid],
+    operation: &BulkOperation,
+) -> Result<(), AppError> {
+    let snapshots = history_service::lock_snapshots(&mut *conn, user_id, ids).await?;
+    let mut columns = BTreeSet::new();
+    for (_, snapshot) in &snapshots {
+        let assignee_id = todo_service::snapshot_uuid(snapshot, "assignee_id");
+        let status = snapshot["status"].as_str().unwrap_or_default();
+        match operation {
+            BulkOperation::SetStatus { status } => {
+                columns.insert((assignee_id, status.as_str()));
+                // Completed occurrences spawn their next one at the end of
+                // `todo`.
+                if status == "done" {
+                    columns.insert((assignee_id, "todo"));
+                }
+            }
+            BulkOperation::SetAssignee {
+                assignee_id: new_assignee_id,
+            } => {
+                columns.insert((*new_assignee_id, status));
+            }
+            BulkOperation::SetLabels { .. } | BulkOperation::Delete => {}
+        }
+    }
+    for (assignee_id, status) in columns {
+        position_service::lock_column(&mut *conn, assignee_id, status).await?;
+    }
+    Ok(())
+}
+
+/// Applies one operation to many todos in a single transaction. Each todo
+/// runs in its own savepoint, so a todo the user cannot change (or that
+/// fails validation) is reported and skipped without undoing the others.
+/// Subtasks trashed along with a parent earlier in the same request are
+/// reported as deleted.
+pub async fn apply_bulk(
+    state: &AppState,
+    user_id: Uuid,
+    payload: BulkTodosRequest,
+) -> Result<BulkTodosResponse, AppError> {
+    let mut operation = payload.operation;
+    match &mut operation {
+        BulkOperation::SetStatus { status } => {
+            *status = todo_service::normalize_status(status)?;
+        }
+        BulkOperation::SetAssignee {
+            assignee_id: Some(assignee_id),
+        } => todo_service::ensure_user_exists(state, *assignee_id).await?,
+        BulkOperation::SetLabels { label_ids } => {
+            *label_ids = label_service::normalize_label_ids(std::mem::take(label_ids))?;
+        }
+        BulkOperation::SetAssignee { assignee_id: None } | BulkOperation::Delete => {}
+    }
+    let ids = resolve_ids(state, user_id, payload.ids, payload.filter).await?;
+
+    let mut tx = state.db.begin().await?;
+    lock_targets(&mut tx, user_id, &ids, &operation).await?;
+    let mut results = Vec::with_capacity(ids.len());
+    let mut applied = Vec::new();
+    let mut trashed = HashSet::new();
+    for todo_id in ids {
+        if trashed.contains(&todo_id) {
+            results.push(BulkItemResult {
+                id: todo_id,
+                status: 200,
+                error: None,
+            });
+            continue;
+        }
+        let mut savepoint = tx.begin().await?;
+        match apply_one(state, &mut savepoint, user_id, todo_id, &operation).await {
+            Ok(outcome) => {
+                savepoint.commit().await?;
+                if let Applied::Deleted(_, steps) = &outcome {
+                    trashed.extend(steps.iter().map(|step| step.todo_id));
+                }
+                results.push(BulkItemResult {
+                    id: todo_id,
+                    status: 200,
+                    error: None,
+                });
+                applied.push((todo_id, outcome));
+            }
+            Err(err) => {
+                savepoint.rollback().await?;
+                let (status, message) = err.status_and_message();
+                results.push(BulkItemResult {
+                    id: todo_id,
+                    status: status.as_u16(),
+                    error: Some(message),
+                });
+            }
+        }
+    }
+
+    let mut steps = Vec::new();
+    let mut todos = Vec::with_capacity(applied.len());
+    let mut spawned = Vec::new();
+    let mut labeled = Vec::new();
+    for (todo_id, outcome) in applied {
+        match outcome {
+            Applied::Updated(update) => {
+                spawned.extend(update.next_occurrence);
+                steps.extend(update.steps);
+                todos.push(update.todo);
+            }
+            Applied::Labeled => labeled.push(todo_id),
+            Applied::Deleted(todo, todo_steps) => {
+                steps.extend(todo_steps);
+                todos.push(*todo);
+            }
+        }
+    }
+    let deleting = matches!(operation, BulkOperation::Delete);
+    let undo_kind = if deleting { "delete" } else { "update" };
+    undo_service::push(&mut tx, user_id, undo_kind, steps).await?;
+    tx.commit().await?;
+
+    for todo_id in labeled {
+        todos.push(todo_service::get_todo(state, user_id, todo_id).await?);
+    }
+    let event = if deleting {
+        "todos_bulk_deleted"
+    } else {
+        "todos_bulk_updated"
+    };
+    broadcast_bulk_event(state, user_id, event, &todos).await;
+    recurrence_service::broadcast_spawned(state, user_id, &spawned).await;
+
+    let succeeded = results
+        .iter()
+        .filter(|result| result.error.is_none())
+        .count();
+    Ok(BulkTodosResponse {
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+    })
+}
+
+/// Sends one event per recipient, listing only the todos they are on; the
+/// actor gets all of them.
+async fn broadcast_bulk_event(
+    state: &AppState,
+    actor_id: Uuid,
+    event: &str,
+    todos: &[TodoResponse],
+) {
+    if todos.is_empty() {
+        return;
+    }
+
+    let mut audiences: HashMap<Uuid, Vec<TodoResponse>> = HashMap::new();
+    audiences.insert(actor_id, todos.to_vec());
+    for todo in todos {
+        for target in [Some(todo.reporter_id), todo.assignee_id]
+            .into_iter()
+            .flatten()
+        {
+            if target == actor_id {
+                continue;
+            }
+            let audience = audiences.entry(target).or_default();
+            if !audience.iter().any(|seen| seen.id == todo.id) {
+                audience.push(todo.clone());
+            }
+        }
+    }
+
+    for (user_id, todos) in audiences {
+        let payload = TodoRealtimeEvent {
+            event: event.to_string(),
+            todo: None,
+            todo_id: None,
+            reminder: None,
+            label: None,
+            comment: None,
+            attachment: None,
+            todos: Some(todos),
+        };
+        if let Ok(message) = serde_json::to_string(&payload) {
+            // The recipient is passed as the actor so only they receive it.
+            state
+                .todo_realtime_hub
+                .broadcast_todo_change(user_id, &[], message)
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_ids_are_applied_once() {
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        assert_eq!(
+            unique_ids(vec![first, second, first, second]),
+            vec![first, second]
+        );
+    }
+
+    #[test]
+    fn operations_are_tagged_by_op() {
+        let operation: BulkOperation =
+            serde_json::from_str(r#"{"op":"set_assignee","assignee_id":null}"#).expect("parse");
+        assert!(matches!(
+            operation,
+            BulkOperation::SetAssignee { assignee_id: None }
+        ));
+        let operation: BulkOperation = serde_json::from_str(r#"{"op":"delete"}"#).expect("parse");
+        assert!(matches!(operation, BulkOperation::Delete));
+        assert!(serde_json::from_str::<BulkOperation>(r#"{"op":"archive"}"#).is_err());
+    }
+}
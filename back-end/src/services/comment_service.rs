@@ -0,0 +1,457 @@
+use sqlx::{FromRow, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        comment::{CommentRequest, TodoCommentResponse, TodoWatcherResponse},
+        todo::TodoRealtimeEvent,
+    },
+    services::{email_service, markdown_service},
+    state::AppState,
+};
+
+const MAX_MENTIONS: usize = 20;
+
+const COMMENT_COLUMNS: &str = "comments.id, comments.todo_id, comments.author_id, author.email AS author_email, comments.body, comments.body_html, ARRAY(SELECT mentions.user_id FROM todo_comment_mentions mentions WHERE mentions.comment_id = comments.id ORDER BY mentions.user_id) AS mentioned_user_ids, comments.created_at, comments.updated_at";
+
+const COMMENT_FROM: &str =
+    "FROM todo_comments comments JOIN users author ON author.id = comments.author_id";
+
+/// The part of a todo that comment notifications need.
+#[derive(Debug, FromRow)]
+struct DiscussedTodo {
+    id: Uuid,
+    title: String,
+    reporter_id: Uuid,
+    assignee_id: Option<Uuid>,
+}
+
+#[derive(Debug, FromRow)]
+struct MentionedUser {
+    id: Uuid,
+    email: String,
+}
+
+/// Loads a todo the user can see: as reporter or assignee. Watching alone
+/// grants no access.
+async fn discussed_todo(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<DiscussedTodo, AppError> {
+    let todo = sqlx::query_as::<_, DiscussedTodo>(
+        "SELECT id, title, reporter_id, assignee_id FROM todos
+        WHERE id = $1 AND deleted_at IS NULL AND (reporter_id = $2 OR assignee_id = $2)",
+    )
+    .bind(todo_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(todo)
+}
+
+async fn load_comment(state: &AppState, comment_id: Uuid) -> Result<TodoCommentResponse, AppError> {
+    let comment = sqlx::query_as::<_, TodoCommentResponse>(&format!(
+        "SELECT {COMMENT_COLUMNS} {COMMENT_FROM} WHERE comments.id = $1"
+    ))
+    .bind(comment_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(comment)
+}
+
+fn parse_mentions(markdown: &str) -> Result<Vec<String>, AppError> {
+    let mentions = markdown_service::extract_mentions(markdown);
+    if mentions.len() > MAX_MENTIONS {
+        return Err(AppError::BadRequest(format!(
+            "a comment can mention at most {MAX_MENTIONS} users"
+        )));
+    }
+    Ok(mentions)
+}
+
+/// Makes the comment's mentions match `emails` and returns the users who
+/// were not mentioned before. Only users who can see the todo are mentioned;
+/// other emails, registered or not, are ignored alike. Mentioned users start
+/// watching the todo.
+async fn sync_mentions(
+    tx: &mut Transaction<'_, Postgres>,
+    todo_id: Uuid,
+    comment_id: Uuid,
+    emails: &[String],
+) -> Result<Vec<MentionedUser>, AppError> {
+    let users = sqlx::query_as::<_, MentionedUser>(
+        "SELECT users.id, users.email FROM users JOIN todos ON todos.id = $2
+        WHERE users.email = ANY($1) AND (users.id = todos.reporter_id OR users.id = todos.assignee_id)",
+    )
+    .bind(emails)
+    .bind(todo_id)
+    .fetch_all(&mut **tx)
+    .await?;
+    let user_ids: Vec<Uuid> = users.iter().map(|user| user.id).collect();
+
+    sqlx::query(
+        "DELETE FROM todo_comment_mentions WHERE comment_id = $1 AND NOT (user_id = ANY($2))",
+    )
+    .bind(comment_id)
+    .bind(&user_ids)
+    .execute(&mut **tx)
+    .await?;
+    let added = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO todo_comment_mentions (comment_id, user_id) SELECT $1, user_id FROM UNNEST($2::uuid[]) AS user_id ON CONFLICT DO NOTHING RETURNING user_id",
+    )
+    .bind(comment_id)
+    .bind(&user_ids)
+    .fetch_all(&mut **tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO todo_watchers (todo_id, user_id) SELECT $1, user_id FROM UNNEST($2::uuid[]) AS user_id ON CONFLICT DO NOTHING",
+    )
+    .bind(todo_id)
+    .bind(&added)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(users
+        .into_iter()
+        .filter(|user| added.contains(&user.id))
+        .collect())
+}
+
+pub async fn list_comments(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<Vec<TodoCommentResponse>, AppError> {
+    discussed_todo(state, user_id, todo_id).await?;
+
+    let comments = sqlx::query_as::<_, TodoCommentResponse>(&format!(
+        "SELECT {COMMENT_COLUMNS} {COMMENT_FROM} WHERE comments.todo_id = $1 ORDER BY comments.created_at, comments.id"
+    ))
+    .bind(todo_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(comments)
+}
+
+pub async fn create_comment(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    payload: CommentRequest,
+) -> Result<TodoCommentResponse, AppError> {
+    let rendered = markdown_service::render_comment(&payload.body)?;
+    let mentions = parse_mentions(&rendered.markdown)?;
+    let todo = discussed_todo(state, user_id, todo_id).await?;
+
+    let comment_id = Uuid::new_v4();
+    let mut tx = state.db.begin().await?;
+    sqlx::query(
+        "INSERT INTO todo_comments (id, todo_id, author_id, body, body_html) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(comment_id)
+    .bind(todo_id)
+    .bind(user_id)
+    .bind(&rendered.markdown)
+    .bind(&rendered.html)
+    .execute(&mut *tx)
+    .await?;
+    let mentioned = sync_mentions(&mut tx, todo_id, comment_id, &mentions).await?;
+    tx.commit().await?;
+
+    let comment = load_comment(state, comment_id).await?;
+    broadcast_comment_event(state, user_id, &todo, "comment_created", &comment).await;
+    notify_mentioned(
+        state,
+        user_id,
+        &todo,
+        &comment,
+        &rendered.excerpt,
+        mentioned,
+    )
+    .await;
+    Ok(comment)
+}
+
+/// Authors can change their own comments; admins can change any comment.
+async fn editable_comment(
+    state: &AppState,
+    user_id: Uuid,
+    is_admin: bool,
+    todo_id: Uuid,
+    comment_id: Uuid,
+) -> Result<DiscussedTodo, AppError> {
+    let author_id = sqlx::query_scalar::<_, Uuid>(
+        "SELECT author_id FROM todo_comments WHERE id = $1 AND todo_id = $2",
+    )
+    .bind(comment_id)
+    .bind(todo_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if is_admin {
+        let todo = sqlx::query_as::<_, DiscussedTodo>(
+            "SELECT id, title, reporter_id, assignee_id FROM todos WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(todo_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or(AppError::NotFound)?;
+        return Ok(todo);
+    }
+
+    let todo = discussed_todo(state, user_id, todo_id).await?;
+    if author_id != user_id {
+        return Err(AppError::Forbidden);
+    }
+    Ok(todo)
+}
+
+pub async fn update_comment(
+    state: &AppState,
+    user_id: Uuid,
+    is_admin: bool,
+    todo_id: Uuid,
+    comment_id: Uuid,
+    payload: CommentRequest,
+) -> Result<TodoCommentResponse, AppError> {
+    let rendered = markdown_service::render_comment(&payload.body)?;
+    let mentions = parse_mentions(&rendered.markdown)?;
+    let todo = editable_comment(state, user_id, is_admin, todo_id, comment_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let result = sqlx::query(
+        "UPDATE todo_comments SET body = $1, body_html = $2, updated_at = NOW() WHERE id = $3",
+    )
+    .bind(&rendered.markdown)
+    .bind(&rendered.html)
+    .bind(comment_id)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    let mentioned = sync_mentions(&mut tx, todo_id, comment_id, &mentions).await?;
+    tx.commit().await?;
+
+    let comment = load_comment(state, comment_id).await?;
+    broadcast_comment_event(state, user_id, &todo, "comment_updated", &comment).await;
+    notify_mentioned(
+        state,
+        user_id,
+        &todo,
+        &comment,
+        &rendered.excerpt,
+        mentioned,
+    )
+    .await;
+    Ok(comment)
+}
+
+pub async fn delete_comment(
+    state: &AppState,
+    user_id: Uuid,
+    is_admin: bool,
+    todo_id: Uuid,
+    comment_id: Uuid,
+) -> Result<(), AppError> {
+    let todo = editable_comment(state, user_id, is_admin, todo_id, comment_id).await?;
+    let comment = load_comment(state, comment_id).await?;
+
+    let result = sqlx::query("DELETE FROM todo_comments WHERE id = $1")
+        .bind(comment_id)
+        .execute(&state.db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    broadcast_comment_event(state, user_id, &todo, "comment_deleted", &comment).await;
+    Ok(())
+}
+
+pub async fn list_watchers(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<Vec<TodoWatcherResponse>, AppError> {
+    discussed_todo(state, user_id, todo_id).await?;
+
+    let watchers = sqlx::query_as::<_, TodoWatcherResponse>(
+        "SELECT users.id AS user_id, users.email, todo_watchers.created_at FROM todo_watchers
+        JOIN users ON users.id = todo_watchers.user_id
+        WHERE todo_watchers.todo_id = $1 ORDER BY users.email",
+    )
+    .bind(todo_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(watchers)
+}
+
+/// Anyone taking part in the todo can add a watcher, including themselves.
+/// Watchers must already be able to see the todo; unknown users and
+/// outsiders get the same error.
+pub async fn add_watcher(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    watcher_id: Uuid,
+) -> Result<(), AppError> {
+    let todo = discussed_todo(state, user_id, todo_id).await?;
+    if watcher_id != todo.reporter_id && Some(watcher_id) != todo.assignee_id {
+        return Err(AppError::BadRequest(
+            "watchers must be the todo's reporter or assignee".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        "INSERT INTO todo_watchers (todo_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+    )
+    .bind(todo_id)
+    .bind(watcher_id)
+    .execute(&state.db)
+    .await?;
+    Ok(())
+}
+
+/// Users who lost access to a todo can still stop watching it.
+pub async fn remove_watcher(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    watcher_id: Uuid,
+) -> Result<(), AppError> {
+    if watcher_id != user_id {
+        discussed_todo(state, user_id, todo_id).await?;
+    }
+
+    let result = sqlx::query("DELETE FROM todo_watchers WHERE todo_id = $1 AND user_id = $2")
+        .bind(todo_id)
+        .bind(watcher_id)
+        .execute(&state.db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+fn comment_event(event: &str, comment: &TodoCommentResponse) -> Option<String> {
+    let payload = TodoRealtimeEvent {
+        event: event.to_string(),
+        todo: None,
+        todo_id: Some(comment.todo_id),
+        reminder: None,
+        label: None,
+        comment: Some(comment.clone()),
+        attachment: None,
+        todos: None,
+    };
+    serde_json::to_string(&payload).ok()
+}
+
+/// Sends a comment event to the todo's reporter, assignee and the watchers
+/// who can still see it.
+async fn broadcast_comment_event(
+    state: &AppState,
+    actor_id: Uuid,
+    todo: &DiscussedTodo,
+    event: &str,
+    comment: &TodoCommentResponse,
+) {
+    let watchers =
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT todo_watchers.user_id FROM todo_watchers JOIN todos ON todos.id = todo_watchers.todo_id
+            WHERE todo_watchers.todo_id = $1
+                AND (todo_watchers.user_id = todos.reporter_id OR todo_watchers.user_id = todos.assignee_id)",
+        )
+        .bind(todo.id)
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_else(|err| {
+            tracing::warn!(todo_id = %todo.id, error = %err, "failed to load todo watchers");
+            Vec::new()
+        });
+    let mut targets = watchers;
+    targets.push(todo.reporter_id);
+    targets.extend(todo.assignee_id);
+
+    if let Some(message) = comment_event(event, comment) {
+        state
+            .todo_realtime_hub
+            .broadcast_todo_change(actor_id, &targets, message)
+            .await;
+    }
+}
+
+/// Tells newly mentioned users through a `todo_mentioned` event and an email.
+/// Emails are sent in the background so a slow SMTP server does not hold up
+/// the request.
+async fn notify_mentioned(
+    state: &AppState,
+    actor_id: Uuid,
+    todo: &DiscussedTodo,
+    comment: &TodoCommentResponse,
+    excerpt: &str,
+    mentioned: Vec<MentionedUser>,
+) {
+    let Some(message) = comment_event("todo_mentioned", comment) else {
+        return;
+    };
+
+    for user in mentioned.into_iter().filter(|user| user.id != actor_id) {
+        state
+            .todo_realtime_hub
+            .broadcast_todo_change(user.id, &[], message.clone())
+            .await;
+
+        let email_config = state.email.clone();
+        let author_email = comment.author_email.clone();
+        let title = todo.title.clone();
+        let excerpt = excerpt.to_string();
+        let comment_id = comment.id;
+        tokio::spawn(async move {
+            if let Err(err) = email_service::send_mention_email(
+                &email_config,
+                &user.email,
+                &author_email,
+                &title,
+                &excerpt,
+            )
+            .await
+            {
+                tracing::warn!(%comment_id, user_id = %user.id, error = %err, "mention email failed");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mentions_are_capped() {
+        let body = (0..=MAX_MENTIONS)
+            .map(|index| format!("@user{index}@example.com"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        assert!(matches!(
+            parse_mentions(&body),
+            Err(AppError::BadRequest(msg)) if msg.contains("at most 20")
+        ));
+        assert_eq!(
+            parse_mentions("ping @a@example.com @A@example.com").expect("mentions"),
+            vec!["a@example.com"]
+        );
+    }
+}
@@ -10,6 +10,49 @@ pub async fn send_reset_email(
     email_config: &EmailConfig,
     to_email: &str,
     reset_link: &str,
+) -> Result<(), AppError> {
+    let body = format!(
+        "Bạn đã yêu cầu đặt lại mật khẩu.\n\nNhấn link sau để đặt lại: {reset_link}\n\nLink hết hạn sau {} phút.",
+        email_config.reset_ttl_minutes
+    );
+
+    send_plain_text(email_config, to_email, "Reset mật khẩu Todo App", body).await
+}
+
+pub async fn send_reminder_email(
+    email_config: &EmailConfig,
+    to_email: &str,
+    title: &str,
+    due_at: &str,
+) -> Result<(), AppError> {
+    let body = format!("Công việc \"{title}\" đến hạn lúc {due_at}.");
+
+    send_plain_text(email_config, to_email, &format!("Nhắc việc: {title}"), body).await
+}
+
+pub async fn send_mention_email(
+    email_config: &EmailConfig,
+    to_email: &str,
+    author_email: &str,
+    title: &str,
+    excerpt: &str,
+) -> Result<(), AppError> {
+    let body = format!("{author_email} đã nhắc đến bạn trong công việc \"{title}\":\n\n{excerpt}");
+
+    send_plain_text(
+        email_config,
+        to_email,
+        &format!("Bạn được nhắc đến: {title}"),
+        body,
+    )
+    .await
+}
+
+async fn send_plain_text(
+    email_config: &EmailConfig,
+    to_email: &str,
+    subject: &str,
+    body: String,
 ) -> Result<(), AppError> {
     let from = Mailbox::new(
         Some(email_config.from_name.clone()),
@@ -20,15 +63,10 @@ pub async fn send_reset_email(
     );
     let to = Mailbox::new(None, to_email.parse().map_err(|_| AppError::Internal)?);
 
-    let body = format!(
-        "Bạn đã yêu cầu đặt lại mật khẩu.\n\nNhấn link sau để đặt lại: {reset_link}\n\nLink hết hạn sau {} phút.",
-        email_config.reset_ttl_minutes
-    );
-
     let email = Message::builder()
         .from(from)
         .to(to)
-        .subject("Reset mật khẩu Todo App")
+        .subject(subject)
         .header(ContentType::TEXT_PLAIN)
         .body(body)
         .map_err(|_| AppError::Internal)?;
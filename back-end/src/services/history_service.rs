@@ -0,0 +1,370 @@
+use serde_json::{Map, Value, json};
+use sqlx::{PgExecutor, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::history::{ActivityQuery, HistoryPage, TodoHistoryEntry, TodoHistoryQuery},
+    services::todo_service,
+    state::AppState,
+};
+
+const HISTORY_ACTIONS: [&str; 6] = [
+    "created",
+    "updated",
+    "reordered",
+    "deleted",
+    "restored",
+    "purged",
+];
+
+/// Fields whose changes are recorded.
+const TRACKED_FIELDS: [&str; 12] = [
+    "title",
+    "description",
+    "status",
+    "position",
+    "completed",
+    "assignee_id",
+    "priority",
+    "estimate",
+    "start_at",
+    "due_at",
+    "parent_id",
+    "deleted_at",
+];
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 200;
+const HISTORY_COLUMNS: &str = "todo_history.id, todo_history.todo_id, todo_history.actor_id, actor.email AS actor_email, todo_history.action, todo_history.changes, todo_history.created_at";
+
+/// A JSON object with the tracked columns of `todos`.
+fn snapshot_sql() -> String {
+    let fields = TRACKED_FIELDS
+        .iter()
+        .map(|field| format!("'{field}', todos.{field}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("jsonb_build_object({fields})")
+}
+
+/// Reads the tracked fields of a todo the user can see, locking the row
+/// until the surrounding transaction ends.
+pub(crate) async fn lock_snapshot<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<Value, AppError> {
+    let snapshot = sqlx::query_scalar::<_, Value>(&format!(
+        "SELECT {} FROM todos WHERE todos.id = $1 AND todos.deleted_at IS NULL AND (todos.reporter_id = $2 OR todos.assignee_id = $2) FOR UPDATE",
+        snapshot_sql()
+    ))
+    .bind(todo_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(snapshot)
+}
+
+/// [`lock_snapshot`] for several todos at once, in id order so concurrent
+/// callers lock rows the same way. Todos the user cannot see are left out.
+pub(crate) async fn lock_snapshots<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    todo_ids: &[Uuid],
+) -> Result<Vec<(Uuid, Value)>, AppError> {
+    let snapshots = sqlx::query_as::<_, (Uuid, Value)>(&format!(
+        "SELECT todos.id, {} FROM todos WHERE todos.id = ANY($1) AND todos.deleted_at IS NULL AND (todos.reporter_id = $2 OR todos.assignee_id = $2) ORDER BY todos.id FOR UPDATE",
+        snapshot_sql()
+    ))
+    .bind(todo_ids)
+    .bind(user_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(snapshots)
+}
+
+/// Like [`lock_snapshot`], but also finds todos in the trash; `None` when the
+/// todo is gone or no longer visible.
+pub(crate) async fn lock_any_snapshot<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<Option<Value>, AppError> {
+    let snapshot = sqlx::query_scalar::<_, Value>(&format!(
+        "SELECT {} FROM todos WHERE todos.id = $1 AND (todos.reporter_id = $2 OR todos.assignee_id = $2) FOR UPDATE",
+        snapshot_sql()
+    ))
+    .bind(todo_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(snapshot)
+}
+
+pub(crate) async fn snapshot<'e>(
+    executor: impl PgExecutor<'e>,
+    todo_id: Uuid,
+) -> Result<Value, AppError> {
+    let snapshot = sqlx::query_scalar::<_, Value>(&format!(
+        "SELECT {} FROM todos WHERE todos.id = $1",
+        snapshot_sql()
+    ))
+    .bind(todo_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(snapshot)
+}
+
+pub(crate) async fn snapshots<'e>(
+    executor: impl PgExecutor<'e>,
+    todo_ids: &[Uuid],
+) -> Result<Vec<(Uuid, Value)>, AppError> {
+    let snapshots = sqlx::query_as::<_, (Uuid, Value)>(&format!(
+        "SELECT todos.id, {} FROM todos WHERE todos.id = ANY($1)",
+        snapshot_sql()
+    ))
+    .bind(todo_ids)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(snapshots)
+}
+
+/// Snapshots a todo and all of its subtasks, trashed or not, which a delete
+/// or purge affects too.
+pub(crate) async fn lock_subtree_snapshots<'e>(
+    executor: impl PgExecutor<'e>,
+    todo_id: Uuid,
+) -> Result<Vec<(Uuid, Value)>, AppError> {
+    let snapshots = sqlx::query_as::<_, (Uuid, Value)>(&format!(
+        "WITH RECURSIVE subtree AS (
+            SELECT id FROM todos WHERE id = $1
+            UNION
+            SELECT children.id FROM todos children JOIN subtree ON children.parent_id = subtree.id
+        )
+        SELECT todos.id, {} FROM todos JOIN subtree ON subtree.id = todos.id FOR UPDATE OF todos",
+        snapshot_sql()
+    ))
+    .bind(todo_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(snapshots)
+}
+
+/// Field-by-field differences between two snapshots. A missing snapshot
+/// stands for a todo that does not exist (before a create, after a delete).
+pub(crate) fn diff(before: Option<&Value>, after: Option<&Value>) -> Map<String, Value> {
+    let mut changes = Map::new();
+    for field in TRACKED_FIELDS {
+        let from = before
+            .and_then(|snapshot| snapshot.get(field))
+            .unwrap_or(&Value::Null);
+        let to = after
+            .and_then(|snapshot| snapshot.get(field))
+            .unwrap_or(&Value::Null);
+        if from != to {
+            changes.insert(field.to_string(), json!({ "from": from, "to": to }));
+        }
+    }
+    changes
+}
+
+/// Appends a history row; changes without any field difference are skipped.
+/// Background jobs record without an actor.
+pub(crate) async fn record<'e>(
+    executor: impl PgExecutor<'e>,
+    todo_id: Uuid,
+    actor_id: Option<Uuid>,
+    action: &str,
+    changes: Map<String, Value>,
+) -> Result<(), AppError> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "INSERT INTO todo_history (todo_id, actor_id, action, changes) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(todo_id)
+    .bind(actor_id)
+    .bind(action)
+    .bind(Value::Object(changes))
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+fn page_size(limit: Option<u32>) -> Result<u32, AppError> {
+    match limit {
+        None => Ok(DEFAULT_PAGE_SIZE),
+        Some(limit) if (1..=MAX_PAGE_SIZE).contains(&limit) => Ok(limit),
+        Some(_) => Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {MAX_PAGE_SIZE}"
+        ))),
+    }
+}
+
+fn parse_cursor(cursor: Option<&str>) -> Result<Option<i64>, AppError> {
+    cursor
+        .map(|raw| {
+            raw.parse::<i64>()
+                .map_err(|_| AppError::BadRequest("invalid cursor".to_string()))
+        })
+        .transpose()
+}
+
+/// Newest entries first; the cursor is the id of the last entry returned.
+async fn fetch_page(
+    state: &AppState,
+    mut builder: QueryBuilder<'_, Postgres>,
+    limit: Option<u32>,
+    cursor: Option<&str>,
+) -> Result<HistoryPage, AppError> {
+    let limit = page_size(limit)?;
+    if let Some(before) = parse_cursor(cursor)? {
+        builder.push(" AND todo_history.id < ").push_bind(before);
+    }
+    builder
+        .push(" ORDER BY todo_history.id DESC LIMIT ")
+        .push_bind(i64::from(limit) + 1);
+
+    let mut items = builder
+        .build_query_as::<TodoHistoryEntry>()
+        .fetch_all(&state.db)
+        .await?;
+    let next_cursor = if items.len() > limit as usize {
+        items.truncate(limit as usize);
+        items.last().map(|entry| entry.id.to_string())
+    } else {
+        None
+    };
+
+    Ok(HistoryPage { items, next_cursor })
+}
+
+fn select_history() -> QueryBuilder<'static, Postgres> {
+    QueryBuilder::new(format!(
+        "SELECT {HISTORY_COLUMNS} FROM todo_history LEFT JOIN users actor ON actor.id = todo_history.actor_id WHERE TRUE"
+    ))
+}
+
+pub async fn list_todo_history(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    query: TodoHistoryQuery,
+) -> Result<HistoryPage, AppError> {
+    todo_service::get_todo(state, user_id, todo_id).await?;
+
+    let mut builder = select_history();
+    builder
+        .push(" AND todo_history.todo_id = ")
+        .push_bind(todo_id);
+    fetch_page(state, builder, query.limit, query.cursor.as_deref()).await
+}
+
+/// Activity across all todos, including deleted ones. Admin only.
+pub async fn list_activity(
+    state: &AppState,
+    query: ActivityQuery,
+) -> Result<HistoryPage, AppError> {
+    let mut builder = select_history();
+    if let Some(actor_id) = query.actor_id {
+        builder
+            .push(" AND todo_history.actor_id = ")
+            .push_bind(actor_id);
+    }
+    if let Some(todo_id) = query.todo_id {
+        builder
+            .push(" AND todo_history.todo_id = ")
+            .push_bind(todo_id);
+    }
+    if let Some(action) = query.action.as_deref() {
+        let action = action.trim().to_lowercase();
+        if !HISTORY_ACTIONS.contains(&action.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "action must be one of: {}",
+                HISTORY_ACTIONS.join(", ")
+            )));
+        }
+        builder
+            .push(" AND todo_history.action = ")
+            .push_bind(action);
+    }
+    if let Some(field) = query.field.as_deref() {
+        let field = field.trim().to_lowercase();
+        if !TRACKED_FIELDS.contains(&field.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "field must be one of: {}",
+                TRACKED_FIELDS.join(", ")
+            )));
+        }
+        builder
+            .push(" AND todo_history.changes ? ")
+            .push_bind(field);
+    }
+    if let Some(since) = query.since {
+        builder
+            .push(" AND todo_history.created_at >= ")
+            .push_bind(since);
+    }
+    if let Some(until) = query.until {
+        builder
+            .push(" AND todo_history.created_at < ")
+            .push_bind(until);
+    }
+    fetch_page(state, builder, query.limit, query.cursor.as_deref()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lists_changed_fields_only() {
+        let before = json!({ "title": "Write", "status": "todo", "position": 0, "due_at": null });
+        let after = json!({ "title": "Write", "status": "done", "position": 3, "due_at": null });
+
+        let changes = diff(Some(&before), Some(&after));
+
+        assert_eq!(
+            Value::Object(changes),
+            json!({
+                "status": { "from": "todo", "to": "done" },
+                "position": { "from": 0, "to": 3 }
+            })
+        );
+        assert!(diff(Some(&before), Some(&before)).is_empty());
+    }
+
+    #[test]
+    fn diff_of_create_and_delete_covers_set_fields() {
+        let todo = json!({ "title": "Write", "status": "todo", "assignee_id": null });
+
+        let created = diff(None, Some(&todo));
+        assert_eq!(created.len(), 2);
+        assert_eq!(created["title"], json!({ "from": null, "to": "Write" }));
+
+        let deleted = diff(Some(&todo), None);
+        assert_eq!(deleted["status"], json!({ "from": "todo", "to": null }));
+        assert!(!deleted.contains_key("assignee_id"));
+    }
+
+    #[test]
+    fn cursors_and_limits_are_validated() {
+        assert_eq!(parse_cursor(Some("42")).expect("cursor"), Some(42));
+        assert!(parse_cursor(Some("abc")).is_err());
+        assert_eq!(page_size(None).expect("limit"), DEFAULT_PAGE_SIZE);
+        assert!(page_size(Some(0)).is_err());
+        assert!(page_size(Some(MAX_PAGE_SIZE + 1)).is_err());
+    }
+}
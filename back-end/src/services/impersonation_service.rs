@@ -0,0 +1,226 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        admin::{ImpersonateRequest, ImpersonationResponse, ImpersonationSessionResponse},
+        auth::{Role, UserResponse},
+    },
+    services::auth_service,
+    state::AppState,
+};
+
+#[derive(Debug, FromRow)]
+struct ActiveSessionRow {
+    expires_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+}
+
+fn resolve_ttl_minutes(requested: Option<i64>, max_minutes: i64) -> Result<i64, AppError> {
+    match requested {
+        Some(minutes) if minutes <= 0 => Err(AppError::BadRequest(
+            "ttl_minutes must be greater than zero".to_string(),
+        )),
+        Some(minutes) => Ok(minutes.min(max_minutes)),
+        None => Ok(max_minutes),
+    }
+}
+
+async fn record_event(
+    state: &AppState,
+    session_id: Uuid,
+    event: &str,
+    method: Option<&str>,
+    path: Option<&str>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO impersonation_audit_events (id, session_id, event, method, path) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(session_id)
+    .bind(event)
+    .bind(method)
+    .bind(path)
+    .execute(&state.db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn start(
+    state: &AppState,
+    actor_id: Uuid,
+    payload: ImpersonateRequest,
+) -> Result<ImpersonationResponse, AppError> {
+    if payload.user_id == actor_id {
+        return Err(AppError::BadRequest(
+            "cannot impersonate yourself".to_string(),
+        ));
+    }
+
+    let reason = payload.reason.trim().to_string();
+    if reason.is_empty() {
+        return Err(AppError::BadRequest("reason is required".to_string()));
+    }
+
+    let ttl_minutes =
+        resolve_ttl_minutes(payload.ttl_minutes, state.jwt.impersonation_ttl_minutes)?;
+
+    let user = sqlx::query_as::<_, UserResponse>("SELECT id, email, role FROM users WHERE id = $1")
+        .bind(payload.user_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if user.role == Role::Admin {
+        return Err(AppError::Forbidden);
+    }
+
+    let session_id = Uuid::new_v4();
+    let expires_at = Utc::now() + Duration::minutes(ttl_minutes);
+
+    let mut tx = state.db.begin().await?;
+    sqlx::query(
+        "INSERT INTO impersonation_sessions (id, actor_id, user_id, reason, expires_at) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(session_id)
+    .bind(actor_id)
+    .bind(user.id)
+    .bind(&reason)
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO impersonation_audit_events (id, session_id, event) VALUES ($1, $2, 'started')",
+    )
+    .bind(Uuid::new_v4())
+    .bind(session_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    let access_token = auth_service::create_impersonation_token(
+        &state.jwt.secret,
+        user.id,
+        user.role,
+        actor_id,
+        session_id,
+        expires_at,
+    )?;
+
+    tracing::warn!(
+        actor_id = %actor_id,
+        user_id = %user.id,
+        session_id = %session_id,
+        %expires_at,
+        reason = %reason,
+        "impersonation started"
+    );
+
+    Ok(ImpersonationResponse {
+        session_id,
+        user,
+        access_token,
+        expires_at,
+    })
+}
+
+pub async fn end(
+    state: &AppState,
+    actor_id: Uuid,
+    user_id: Uuid,
+    session_id: Uuid,
+) -> Result<(), AppError> {
+    let result = sqlx::query(
+        "UPDATE impersonation_sessions SET ended_at = NOW() WHERE id = $1 AND ended_at IS NULL",
+    )
+    .bind(session_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::Unauthorized);
+    }
+
+    record_event(state, session_id, "ended", None, None).await?;
+
+    tracing::warn!(
+        actor_id = %actor_id,
+        user_id = %user_id,
+        session_id = %session_id,
+        "impersonation ended"
+    );
+    Ok(())
+}
+
+pub async fn list_sessions(
+    state: &AppState,
+) -> Result<Vec<ImpersonationSessionResponse>, AppError> {
+    let sessions = sqlx::query_as::<_, ImpersonationSessionResponse>(
+        "SELECT sessions.id, sessions.actor_id, actor.email AS actor_email, sessions.user_id, target.email AS user_email, sessions.reason, sessions.started_at, sessions.expires_at, sessions.ended_at, (SELECT COUNT(*) FROM impersonation_audit_events events WHERE events.session_id = sessions.id AND events.event = 'write') AS write_count FROM impersonation_sessions sessions JOIN users actor ON actor.id = sessions.actor_id JOIN users target ON target.id = sessions.user_id ORDER BY sessions.started_at DESC LIMIT 200",
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(sessions)
+}
+
+pub async fn ensure_session_active(state: &AppState, session_id: Uuid) -> Result<(), AppError> {
+    let session = sqlx::query_as::<_, ActiveSessionRow>(
+        "SELECT expires_at, ended_at FROM impersonation_sessions WHERE id = $1",
+    )
+    .bind(session_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::Unauthorized)?;
+
+    if session.ended_at.is_some() || session.expires_at < Utc::now() {
+        return Err(AppError::Unauthorized);
+    }
+
+    Ok(())
+}
+
+pub async fn record_write(
+    state: &AppState,
+    actor_id: Uuid,
+    user_id: Uuid,
+    session_id: Uuid,
+    method: &str,
+    path: &str,
+) -> Result<(), AppError> {
+    tracing::warn!(
+        actor_id = %actor_id,
+        user_id = %user_id,
+        session_id = %session_id,
+        method,
+        path,
+        "impersonated write"
+    );
+    record_event(state, session_id, "write", Some(method), Some(path)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_ttl_defaults_to_configured_maximum() {
+        assert_eq!(resolve_ttl_minutes(None, 15).expect("ttl"), 15);
+    }
+
+    #[test]
+    fn resolve_ttl_caps_requested_value() {
+        assert_eq!(resolve_ttl_minutes(Some(5), 15).expect("ttl"), 5);
+        assert_eq!(resolve_ttl_minutes(Some(120), 15).expect("ttl"), 15);
+    }
+
+    #[test]
+    fn resolve_ttl_rejects_non_positive_values() {
+        let result = resolve_ttl_minutes(Some(0), 15);
+
+        assert!(matches!(result, Err(AppError::BadRequest(msg)) if msg.contains("ttl_minutes")));
+    }
+}
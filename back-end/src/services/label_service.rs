@@ -0,0 +1,296 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        label::{LabelRequest, LabelResponse, SetTodoLabelsRequest},
+        todo::{TodoRealtimeEvent, TodoResponse},
+    },
+    services::todo_service,
+    state::AppState,
+};
+
+const MAX_LABEL_NAME_CHARS: usize = 50;
+const MAX_LABELS_PER_TODO: usize = 20;
+const DEFAULT_LABEL_COLOR: &str = "#6b7280";
+
+fn normalize_name(name: &str) -> Result<String, AppError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(AppError::BadRequest("name is required".to_string()));
+    }
+    if trimmed.chars().count() > MAX_LABEL_NAME_CHARS {
+        return Err(AppError::BadRequest(format!(
+            "name must be at most {MAX_LABEL_NAME_CHARS} characters"
+        )));
+    }
+    // Label lists in `?label=` and `label:` are comma-separated.
+    if trimmed.contains(',') {
+        return Err(AppError::BadRequest(
+            "name must not contain commas".to_string(),
+        ));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn normalize_color(color: Option<&str>) -> Result<String, AppError> {
+    let Some(color) = color else {
+        return Ok(DEFAULT_LABEL_COLOR.to_string());
+    };
+    let color = color.trim().to_lowercase();
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|ch| ch.is_ascii_hexdigit());
+    if !valid {
+        return Err(AppError::BadRequest(
+            "color must look like #rrggbb".to_string(),
+        ));
+    }
+    Ok(color)
+}
+
+fn map_label_violation(err: sqlx::Error) -> AppError {
+    if let sqlx::Error::Database(db_err) = &err {
+        if db_err.constraint() == Some("labels_owner_name_key") {
+            return AppError::BadRequest("a label with this name already exists".to_string());
+        }
+    }
+    AppError::from(err)
+}
+
+pub async fn list_labels(state: &AppState, user_id: Uuid) -> Result<Vec<LabelResponse>, AppError> {
+    let labels = sqlx::query_as::<_, LabelResponse>(
+        "SELECT id, name, color, created_at, updated_at FROM labels WHERE owner_id = $1 ORDER BY lower(name), id",
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(labels)
+}
+
+pub async fn create_label(
+    state: &AppState,
+    user_id: Uuid,
+    payload: LabelRequest,
+) -> Result<LabelResponse, AppError> {
+    let name = normalize_name(&payload.name)?;
+    let color = normalize_color(payload.color.as_deref())?;
+
+    let label = sqlx::query_as::<_, LabelResponse>(
+        "INSERT INTO labels (id, owner_id, name, color) VALUES ($1, $2, $3, $4) RETURNING id, name, color, created_at, updated_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(name)
+    .bind(color)
+    .fetch_one(&state.db)
+    .await
+    .map_err(map_label_violation)?;
+
+    broadcast_label_event(state, user_id, "label_created", &label).await;
+    Ok(label)
+}
+
+pub async fn update_label(
+    state: &AppState,
+    user_id: Uuid,
+    label_id: Uuid,
+    payload: LabelRequest,
+) -> Result<LabelResponse, AppError> {
+    let name = normalize_name(&payload.name)?;
+    let color = payload
+        .color
+        .as_deref()
+        .map(|color| normalize_color(Some(color)))
+        .transpose()?;
+
+    let label = sqlx::query_as::<_, LabelResponse>(
+        "UPDATE labels SET name = $1, color = COALESCE($2, color), updated_at = NOW() WHERE id = $3 AND owner_id = $4 RETURNING id, name, color, created_at, updated_at",
+    )
+    .bind(name)
+    .bind(color)
+    .bind(label_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(map_label_violation)?
+    .ok_or(AppError::NotFound)?;
+
+    broadcast_label_event(state, user_id, "label_updated", &label).await;
+    Ok(label)
+}
+
+pub async fn delete_label(state: &AppState, user_id: Uuid, label_id: Uuid) -> Result<(), AppError> {
+    let label = sqlx::query_as::<_, LabelResponse>(
+        "SELECT id, name, color, created_at, updated_at FROM labels WHERE id = $1 AND owner_id = $2",
+    )
+    .bind(label_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    // Collect the audience before the todo links disappear with the label.
+    let targets = label_audience(state, label_id).await?;
+    sqlx::query("DELETE FROM labels WHERE id = $1 AND owner_id = $2")
+        .bind(label_id)
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    send_label_event(state, user_id, &targets, "label_deleted", &label).await;
+    Ok(())
+}
+
+pub(crate) fn normalize_label_ids(mut label_ids: Vec<Uuid>) -> Result<Vec<Uuid>, AppError> {
+    label_ids.sort();
+    label_ids.dedup();
+    if label_ids.len() > MAX_LABELS_PER_TODO {
+        return Err(AppError::BadRequest(format!(
+            "a todo can have at most {MAX_LABELS_PER_TODO} of your labels"
+        )));
+    }
+    Ok(label_ids)
+}
+
+/// Replaces the caller's labels on a todo in the caller's transaction. Labels
+/// other people put on the todo from their own catalogs are kept.
+pub(crate) async fn replace_todo_labels(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    todo_id: Uuid,
+    label_ids: &[Uuid],
+) -> Result<(), AppError> {
+    let owned: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM labels WHERE id = ANY($1) AND owner_id = $2")
+            .bind(label_ids)
+            .bind(user_id)
+            .fetch_one(&mut *conn)
+            .await?;
+    if owned != label_ids.len() as i64 {
+        return Err(AppError::BadRequest("label not found".to_string()));
+    }
+
+    sqlx::query(
+        "DELETE FROM todo_labels WHERE todo_id = $1 AND NOT (label_id = ANY($2)) AND label_id IN (SELECT id FROM labels WHERE owner_id = $3)",
+    )
+    .bind(todo_id)
+    .bind(label_ids)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        "INSERT INTO todo_labels (todo_id, label_id) SELECT $1, label_id FROM UNNEST($2::uuid[]) AS label_id ON CONFLICT DO NOTHING",
+    )
+    .bind(todo_id)
+    .bind(label_ids)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query("UPDATE todos SET updated_at = NOW() WHERE id = $1")
+        .bind(todo_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Replaces the caller's labels on a todo. Labels other people put on the
+/// todo from their own catalogs are kept.
+pub async fn set_todo_labels(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    payload: SetTodoLabelsRequest,
+) -> Result<TodoResponse, AppError> {
+    let label_ids = normalize_label_ids(payload.label_ids)?;
+    todo_service::get_todo(state, user_id, todo_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    replace_todo_labels(&mut tx, user_id, todo_id, &label_ids).await?;
+    tx.commit().await?;
+
+    let todo = todo_service::get_todo(state, user_id, todo_id).await?;
+    todo_service::broadcast_todo_event(state, user_id, "todo_labels_updated", Some(&todo), None)
+        .await;
+    Ok(todo)
+}
+
+/// Everyone on a todo that carries the label.
+async fn label_audience(state: &AppState, label_id: Uuid) -> Result<Vec<Uuid>, AppError> {
+    let targets = sqlx::query_scalar::<_, Uuid>(
+        "SELECT todos.reporter_id FROM todo_labels JOIN todos ON todos.id = todo_labels.todo_id WHERE todo_labels.label_id = $1
+        UNION
+        SELECT todos.assignee_id FROM todo_labels JOIN todos ON todos.id = todo_labels.todo_id WHERE todo_labels.label_id = $1 AND todos.assignee_id IS NOT NULL",
+    )
+    .bind(label_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(targets)
+}
+
+async fn broadcast_label_event(
+    state: &AppState,
+    user_id: Uuid,
+    event: &str,
+    label: &LabelResponse,
+) {
+    match label_audience(state, label.id).await {
+        Ok(targets) => send_label_event(state, user_id, &targets, event, label).await,
+        Err(err) => {
+            tracing::warn!(label_id = %label.id, error = %err, "failed to load label audience")
+        }
+    }
+}
+
+async fn send_label_event(
+    state: &AppState,
+    user_id: Uuid,
+    targets: &[Uuid],
+    event: &str,
+    label: &LabelResponse,
+) {
+    let payload = TodoRealtimeEvent {
+        event: event.to_string(),
+        todo: None,
+        todo_id: None,
+        reminder: None,
+        label: Some(label.clone()),
+        comment: None,
+        attachment: None,
+        todos: None,
+    };
+
+    if let Ok(message) = serde_json::to_string(&payload) {
+        state
+            .todo_realtime_hub
+            .broadcast_todo_change(user_id, targets, message)
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_names_are_trimmed_and_bounded() {
+        assert_eq!(normalize_name("  Bug ").expect("name"), "Bug");
+        assert!(normalize_name(" ").is_err());
+        assert!(normalize_name("bug, urgent").is_err());
+        assert!(normalize_name(&"x".repeat(MAX_LABEL_NAME_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn colors_must_be_hex_triplets() {
+        assert_eq!(normalize_color(None).expect("color"), DEFAULT_LABEL_COLOR);
+        assert_eq!(
+            normalize_color(Some(" #E11D48 ")).expect("color"),
+            "#e11d48"
+        );
+        assert!(normalize_color(Some("red")).is_err());
+        assert!(normalize_color(Some("#e11d4")).is_err());
+    }
+}
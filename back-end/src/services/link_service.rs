@@ -0,0 +1,263 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::link::{CreateTodoLinkRequest, TodoLinkResponse},
+    services::todo_service,
+    state::AppState,
+};
+
+// Serializes link writes so two concurrent inserts cannot close a cycle
+// that neither of them sees on its own.
+const LINK_LOCK_KEY: i64 = 0x746f_646f_6c69_6e6b;
+
+// `$2` is the viewer; the other todo's details are hidden from anyone who
+// cannot see it.
+const LINK_COLUMNS: &str = "links.id, links.kind, links.source_id, links.target_id, other.id AS todo_id, CASE WHEN other.reporter_id = $2 OR other.assignee_id = $2 THEN other.title END AS todo_title, CASE WHEN other.reporter_id = $2 OR other.assignee_id = $2 THEN other.status END AS todo_status, links.created_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkKind {
+    Blocks,
+    RelatesTo,
+    DuplicateOf,
+}
+
+impl LinkKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Blocks => "blocks",
+            Self::RelatesTo => "relates_to",
+            Self::DuplicateOf => "duplicate_of",
+        }
+    }
+
+    /// Whether links of this kind must not form a cycle.
+    fn is_directed(self) -> bool {
+        !matches!(self, Self::RelatesTo)
+    }
+}
+
+/// Turns "`todo_id` <kind> `other_id`" into the stored `(kind, source, target)`.
+fn resolve_link(
+    raw_kind: &str,
+    todo_id: Uuid,
+    other_id: Uuid,
+) -> Result<(LinkKind, Uuid, Uuid), AppError> {
+    if todo_id == other_id {
+        return Err(AppError::BadRequest(
+            "a todo cannot be linked to itself".to_string(),
+        ));
+    }
+
+    let link = match raw_kind.trim().to_lowercase().as_str() {
+        "blocks" => (LinkKind::Blocks, todo_id, other_id),
+        "blocked_by" => (LinkKind::Blocks, other_id, todo_id),
+        "relates_to" => (
+            LinkKind::RelatesTo,
+            todo_id.min(other_id),
+            todo_id.max(other_id),
+        ),
+        "duplicate_of" => (LinkKind::DuplicateOf, todo_id, other_id),
+        _ => return Err(AppError::BadRequest("invalid link kind".to_string())),
+    };
+    Ok(link)
+}
+
+fn map_link_violation(err: sqlx::Error) -> AppError {
+    if let sqlx::Error::Database(db_err) = &err {
+        if db_err.constraint() == Some("todo_links_source_target_kind_key") {
+            return AppError::BadRequest("link already exists".to_string());
+        }
+    }
+    AppError::from(err)
+}
+
+pub async fn list_links(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<Vec<TodoLinkResponse>, AppError> {
+    todo_service::get_todo(state, user_id, todo_id).await?;
+
+    let links = sqlx::query_as::<_, TodoLinkResponse>(&format!(
+        "SELECT {LINK_COLUMNS} FROM todo_links links
+        JOIN todos other ON other.id = CASE WHEN links.source_id = $1 THEN links.target_id ELSE links.source_id END
+        WHERE (links.source_id = $1 OR links.target_id = $1) AND other.deleted_at IS NULL
+        ORDER BY links.created_at, links.id"
+    ))
+    .bind(todo_id)
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(links)
+}
+
+pub async fn create_link(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    payload: CreateTodoLinkRequest,
+) -> Result<TodoLinkResponse, AppError> {
+    let (kind, source_id, target_id) = resolve_link(&payload.kind, todo_id, payload.todo_id)?;
+    todo_service::get_todo(state, user_id, todo_id).await?;
+    todo_service::get_todo(state, user_id, payload.todo_id)
+        .await
+        .map_err(|err| match err {
+            AppError::NotFound => AppError::BadRequest("linked todo not found".to_string()),
+            other => other,
+        })?;
+
+    let mut tx = state.db.begin().await?;
+    if kind.is_directed() {
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(LINK_LOCK_KEY)
+            .execute(&mut *tx)
+            .await?;
+
+        // The new edge closes a cycle if its source is already reachable
+        // from its target through links of the same kind.
+        let creates_cycle = sqlx::query_scalar::<_, bool>(
+            "WITH RECURSIVE reachable (id) AS (
+                SELECT $1::uuid
+                UNION
+                SELECT links.target_id FROM todo_links links
+                JOIN reachable ON links.source_id = reachable.id
+                WHERE links.kind = $3
+            )
+            SELECT EXISTS(SELECT 1 FROM reachable WHERE id = $2)",
+        )
+        .bind(target_id)
+        .bind(source_id)
+        .bind(kind.as_str())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if creates_cycle {
+            return Err(AppError::BadRequest(format!(
+                "link would create a `{}` cycle",
+                kind.as_str()
+            )));
+        }
+    }
+
+    let link_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO todo_links (id, source_id, target_id, kind, created_by) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(link_id)
+    .bind(source_id)
+    .bind(target_id)
+    .bind(kind.as_str())
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(map_link_violation)?;
+    tx.commit().await?;
+
+    let link = sqlx::query_as::<_, TodoLinkResponse>(&format!(
+        "SELECT {LINK_COLUMNS} FROM todo_links links
+        JOIN todos other ON other.id = $3
+        WHERE links.id = $1"
+    ))
+    .bind(link_id)
+    .bind(user_id)
+    .bind(payload.todo_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    broadcast_link_change(state, user_id, &[source_id, target_id]).await;
+    Ok(link)
+}
+
+pub async fn delete_link(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    link_id: Uuid,
+) -> Result<(), AppError> {
+    todo_service::get_todo(state, user_id, todo_id).await?;
+
+    let deleted = sqlx::query_as::<_, (Uuid, Uuid)>(
+        "DELETE FROM todo_links WHERE id = $1 AND (source_id = $2 OR target_id = $2) RETURNING source_id, target_id",
+    )
+    .bind(link_id)
+    .bind(todo_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    broadcast_link_change(state, user_id, &[deleted.0, deleted.1]).await;
+    Ok(())
+}
+
+/// Enforces `TODO_GUARD_BLOCKED_START` before `todo_id` moves to `in_progress`.
+pub async fn ensure_not_blocked<'e>(
+    executor: impl PgExecutor<'e>,
+    todo_id: Uuid,
+) -> Result<(), AppError> {
+    let open_blockers = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM todo_links links JOIN todos blocker ON blocker.id = links.source_id
+        WHERE links.target_id = $1 AND links.kind = 'blocks' AND NOT blocker.completed AND blocker.deleted_at IS NULL",
+    )
+    .bind(todo_id)
+    .fetch_one(executor)
+    .await?;
+
+    if open_blockers > 0 {
+        return Err(AppError::BadRequest(format!(
+            "todo is blocked by {open_blockers} open todo(s)"
+        )));
+    }
+    Ok(())
+}
+
+/// Both ends change: `blocked_by` is part of the todo response.
+async fn broadcast_link_change(state: &AppState, user_id: Uuid, todo_ids: &[Uuid]) {
+    for todo_id in todo_ids {
+        if let Ok(todo) = todo_service::get_todo(state, user_id, *todo_id).await {
+            todo_service::broadcast_todo_event(state, user_id, "todo_updated", Some(&todo), None)
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_link_normalizes_direction() {
+        let a = Uuid::from_u128(1);
+        let b = Uuid::from_u128(2);
+
+        assert_eq!(
+            resolve_link("blocks", a, b).expect("link"),
+            (LinkKind::Blocks, a, b)
+        );
+        assert_eq!(
+            resolve_link(" Blocked_By ", a, b).expect("link"),
+            (LinkKind::Blocks, b, a)
+        );
+        assert_eq!(
+            resolve_link("relates_to", b, a).expect("link"),
+            (LinkKind::RelatesTo, a, b)
+        );
+        assert_eq!(
+            resolve_link("duplicate_of", b, a).expect("link"),
+            (LinkKind::DuplicateOf, b, a)
+        );
+    }
+
+    #[test]
+    fn resolve_link_rejects_self_links_and_unknown_kinds() {
+        let a = Uuid::from_u128(1);
+
+        assert!(resolve_link("blocks", a, a).is_err());
+        assert!(matches!(
+            resolve_link("parent_of", a, Uuid::from_u128(2)),
+            Err(AppError::BadRequest(msg)) if msg == "invalid link kind"
+        ));
+    }
+}
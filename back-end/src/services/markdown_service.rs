@@ -0,0 +1,231 @@
+use pulldown_cmark::{Event, Options, Parser, TagEnd, html};
+
+use crate::error::AppError;
+
+pub const MAX_DESCRIPTION_CHARS: usize = 20_000;
+pub const MAX_COMMENT_CHARS: usize = 10_000;
+const EXCERPT_CHARS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RenderedDescription {
+    pub markdown: String,
+    pub html: String,
+    pub excerpt: String,
+}
+
+fn markdown_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH
+}
+
+/// Validates a Markdown description and renders the sanitized HTML and
+/// plain-text excerpt that are stored alongside it.
+pub fn render_description(source: &str) -> Result<RenderedDescription, AppError> {
+    render_markdown(source, "description", MAX_DESCRIPTION_CHARS)
+}
+
+/// Renders a comment body; unlike descriptions, a comment cannot be blank.
+pub fn render_comment(source: &str) -> Result<RenderedDescription, AppError> {
+    let rendered = render_markdown(source, "body", MAX_COMMENT_CHARS)?;
+    if rendered.markdown.is_empty() {
+        return Err(AppError::BadRequest("body is required".to_string()));
+    }
+    Ok(rendered)
+}
+
+fn render_markdown(
+    source: &str,
+    field: &str,
+    max_chars: usize,
+) -> Result<RenderedDescription, AppError> {
+    if source.chars().count() > max_chars {
+        return Err(AppError::BadRequest(format!(
+            "{field} must be at most {max_chars} characters"
+        )));
+    }
+
+    let markdown = source.trim_end();
+    if markdown.trim().is_empty() {
+        return Ok(RenderedDescription::default());
+    }
+
+    Ok(RenderedDescription {
+        markdown: markdown.to_string(),
+        html: render_html(markdown),
+        excerpt: render_excerpt(markdown),
+    })
+}
+
+fn render_html(markdown: &str) -> String {
+    let mut unsafe_html = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(
+        &mut unsafe_html,
+        Parser::new_ext(markdown, markdown_options()),
+    );
+
+    ammonia::Builder::default()
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+fn render_excerpt(markdown: &str) -> String {
+    let mut text = String::new();
+    // Raw `<script>`/`<style>` content is dropped from the HTML, so skip it here too.
+    let mut in_raw_code = false;
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        match event {
+            Event::Html(raw) | Event::InlineHtml(raw) => {
+                let raw = raw.trim_start().to_ascii_lowercase();
+                if raw.starts_with("<script") || raw.starts_with("<style") {
+                    in_raw_code = true;
+                }
+                if raw.contains("</script") || raw.contains("</style") {
+                    in_raw_code = false;
+                }
+            }
+            _ if in_raw_code => {}
+            Event::Text(value) | Event::Code(value) => text.push_str(&value),
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item)
+            | Event::End(TagEnd::CodeBlock | TagEnd::TableCell) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() <= EXCERPT_CHARS {
+        return collapsed;
+    }
+
+    let truncated: String = collapsed.chars().take(EXCERPT_CHARS).collect();
+    let cut = truncated.rfind(' ').unwrap_or(truncated.len());
+    format!("{}…", truncated[..cut].trim_end())
+}
+
+/// Lowercased emails written as `@user@example.com` in the text of `markdown`,
+/// in order of first appearance. Mentions inside code are ignored.
+pub fn extract_mentions(markdown: &str) -> Vec<String> {
+    let mut mentions = Vec::new();
+    let mut text = String::new();
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        match event {
+            Event::Text(value) => text.push_str(&value),
+            _ => {
+                collect_mentions(&text, &mut mentions);
+                text.clear();
+            }
+        }
+    }
+    collect_mentions(&text, &mut mentions);
+    mentions
+}
+
+fn collect_mentions(text: &str, mentions: &mut Vec<String>) {
+    let is_email_char = |ch: char| ch.is_ascii_alphanumeric() || "._%+-@".contains(ch);
+    let mut rest = text;
+    while let Some(start) = rest.find('@') {
+        // `@` must start a word, so the middle of `a@b.c` is not a mention.
+        let preceded_by_word = rest[..start].chars().next_back().is_some_and(is_email_char);
+        let candidate = &rest[start + 1..];
+        let end = candidate
+            .find(|ch: char| !is_email_char(ch))
+            .unwrap_or(candidate.len());
+        let email = candidate[..end].trim_end_matches(['.', '-']);
+        if !preceded_by_word && is_email(email) {
+            let email = email.to_lowercase();
+            if !mentions.contains(&email) {
+                mentions.push(email);
+            }
+        }
+        rest = &candidate[end..];
+    }
+}
+
+fn is_email(candidate: &str) -> bool {
+    match candidate.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_markdown_to_html() {
+        let rendered = render_description("# Plan\n\n- **ship** it\n- ~~wait~~").expect("render");
+
+        assert_eq!(
+            rendered.html,
+            "<h1>Plan</h1>\n<ul>\n<li><strong>ship</strong> it</li>\n<li><del>wait</del></li>\n</ul>\n"
+        );
+        assert_eq!(rendered.excerpt, "Plan ship it wait");
+    }
+
+    #[test]
+    fn strips_scripts_and_unsafe_urls() {
+        let rendered = render_description(
+            "<script>alert(1)</script>\n\n[click](javascript:alert(1)) <img src=x onerror=alert(1)>",
+        )
+        .expect("render");
+
+        assert!(!rendered.html.contains("script"));
+        assert!(!rendered.html.contains("javascript:"));
+        assert!(!rendered.html.contains("onerror"));
+        assert_eq!(rendered.excerpt, "click");
+        assert!(
+            rendered
+                .html
+                .contains("rel=\"noopener noreferrer nofollow\"")
+        );
+    }
+
+    #[test]
+    fn blank_description_renders_empty() {
+        assert_eq!(
+            render_description("  \n ").expect("render"),
+            RenderedDescription::default()
+        );
+    }
+
+    #[test]
+    fn excerpt_is_truncated_on_a_word_boundary() {
+        let rendered = render_description(&"lorem ipsum ".repeat(40)).expect("render");
+
+        assert!(rendered.excerpt.ends_with("ipsum…") || rendered.excerpt.ends_with("lorem…"));
+        assert!(rendered.excerpt.chars().count() <= EXCERPT_CHARS + 1);
+    }
+
+    #[test]
+    fn extracts_mentions_outside_code() {
+        let mentions = extract_mentions(
+            "Thanks @Lan@Example.com and @bao.tran@example.com.\n\n`@skip@example.com` mail me at me@example.com, cc @lan@example.com",
+        );
+
+        assert_eq!(mentions, vec!["lan@example.com", "bao.tran@example.com"]);
+        assert!(extract_mentions("@someone and @nobody@localhost").is_empty());
+    }
+
+    #[test]
+    fn comments_cannot_be_blank() {
+        assert!(matches!(
+            render_comment(" \n "),
+            Err(AppError::BadRequest(msg)) if msg == "body is required"
+        ));
+        assert!(render_comment(&"a".repeat(MAX_COMMENT_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_description() {
+        let result = render_description(&"a".repeat(MAX_DESCRIPTION_CHARS + 1));
+
+        assert!(matches!(result, Err(AppError::BadRequest(msg)) if msg.contains("description")));
+    }
+}
@@ -1,6 +1,26 @@
 pub mod ai_service;
+pub mod attachment_service;
+pub mod attachment_storage;
 pub mod auth_service;
+pub mod bulk_service;
+pub mod comment_service;
 pub mod email_service;
+pub mod history_service;
+pub mod impersonation_service;
+pub mod label_service;
+pub mod link_service;
+pub mod markdown_service;
+pub mod password_service;
+pub mod position_service;
+pub mod recurrence_service;
+pub mod reminder_service;
+pub mod saved_filter_service;
+pub mod subtask_service;
+pub mod todo_filter;
+pub mod todo_query;
 pub mod todo_realtime_service;
+pub mod todo_schedule;
 pub mod todo_service;
+pub mod trash_service;
+pub mod undo_service;
 pub mod user_service;
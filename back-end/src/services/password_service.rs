@@ -0,0 +1,533 @@
+use argon2::{
+    Algorithm, Argon2, Params, PasswordHasher, PasswordVerifier, Version,
+    password_hash::{PasswordHash, SaltString},
+};
+use rand::rngs::OsRng;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::AppError,
+    locale::Language,
+    state::{PasswordConfig, PasswordPolicyConfig},
+};
+
+const PASSWORD_MAX_LENGTH: usize = 128;
+const COMMON_SEQUENCES: [&str; 6] = ["password", "qwerty", "abcd", "1234", "admin", "letmein"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordPolicyViolation {
+    TooShort(usize),
+    TooLong(usize),
+    MissingLetterOrDigit,
+    ContainsEmail,
+    TooWeak,
+    Breached,
+}
+
+impl PasswordPolicyViolation {
+    pub fn message(&self, language: Language) -> String {
+        match self {
+            Self::TooShort(min) => language.message(
+                &format!("password must be at least {min} characters"),
+                &format!("mật khẩu phải có ít nhất {min} ký tự"),
+            ),
+            Self::TooLong(max) => language.message(
+                &format!("password must be at most {max} characters"),
+                &format!("mật khẩu không được vượt quá {max} ký tự"),
+            ),
+            Self::MissingLetterOrDigit => language.message(
+                "password must include letters and numbers",
+                "mật khẩu phải bao gồm cả chữ và số",
+            ),
+            Self::ContainsEmail => language.message(
+                "password must not contain your email",
+                "mật khẩu không được chứa email của bạn",
+            ),
+            Self::TooWeak => language.message(
+                "password is too weak; use a longer password with mixed characters",
+                "mật khẩu quá yếu; hãy dùng mật khẩu dài hơn với nhiều loại ký tự",
+            ),
+            Self::Breached => language.message(
+                "password has appeared in a data breach; choose another one",
+                "mật khẩu đã xuất hiện trong một vụ rò rỉ dữ liệu; hãy chọn mật khẩu khác",
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordVerification {
+    Current,
+    NeedsRehash,
+}
+
+pub async fn hash_password(config: &PasswordConfig, password: &str) -> Result<String, AppError> {
+    let config = config.clone();
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || hash_password_sync(&config, &password))
+        .await
+        .map_err(|error| {
+            tracing::error!("password hashing task failed: {error}");
+            AppError::Internal
+        })?
+}
+
+pub async fn verify_password(
+    config: &PasswordConfig,
+    password: &str,
+    hash: &str,
+) -> Result<PasswordVerification, AppError> {
+    let config = config.clone();
+    let password = password.to_string();
+    let hash = hash.to_string();
+    tokio::task::spawn_blocking(move || verify_password_sync(&config, &password, &hash))
+        .await
+        .map_err(|error| {
+            tracing::error!("password verification task failed: {error}");
+            AppError::Internal
+        })?
+}
+
+fn pepper_key_id(pepper: &str) -> [u8; 8] {
+    let digest = Sha256::digest(pepper.as_bytes());
+    let mut key_id = [0u8; 8];
+    key_id.copy_from_slice(&digest[..8]);
+    key_id
+}
+
+fn current_params(config: &PasswordConfig) -> Result<Params, AppError> {
+    let mut builder = argon2::ParamsBuilder::new();
+    builder
+        .m_cost(config.argon2_memory_kib)
+        .t_cost(config.argon2_iterations)
+        .p_cost(config.argon2_parallelism);
+    if let Some(pepper) = config.pepper.as_deref() {
+        let key_id = argon2::KeyId::new(&pepper_key_id(pepper)).map_err(|_| AppError::Internal)?;
+        builder.keyid(key_id);
+    }
+    builder.build().map_err(|error| {
+        tracing::error!("invalid argon2 parameters: {error}");
+        AppError::Internal
+    })
+}
+
+fn hash_password_sync(config: &PasswordConfig, password: &str) -> Result<String, AppError> {
+    let params = current_params(config)?;
+    let argon = match config.pepper.as_deref() {
+        Some(pepper) => Argon2::new_with_secret(
+            pepper.as_bytes(),
+            Algorithm::Argon2id,
+            Version::V0x13,
+            params,
+        )
+        .map_err(|_| AppError::Internal)?,
+        None => Argon2::new(Algorithm::Argon2id, Version::V0x13, params),
+    };
+
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = argon
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|_| AppError::Internal)?
+        .to_string();
+    Ok(hash)
+}
+
+fn verify_password_sync(
+    config: &PasswordConfig,
+    password: &str,
+    hash: &str,
+) -> Result<PasswordVerification, AppError> {
+    let parsed_hash = PasswordHash::new(hash).map_err(|_| AppError::Unauthorized)?;
+    let stored_params = Params::try_from(&parsed_hash).map_err(|_| AppError::Unauthorized)?;
+
+    // Hashes written before a pepper was configured carry no key id and are
+    // verified without a secret; peppered hashes must match the current pepper.
+    let argon = if stored_params.keyid().is_empty() {
+        Argon2::default()
+    } else {
+        let pepper = config
+            .pepper
+            .as_deref()
+            .filter(|pepper| stored_params.keyid() == pepper_key_id(pepper))
+            .ok_or_else(|| {
+                tracing::error!("password hash was created with an unknown pepper");
+                AppError::Unauthorized
+            })?;
+        Argon2::new_with_secret(
+            pepper.as_bytes(),
+            Algorithm::default(),
+            Version::default(),
+            Params::default(),
+        )
+        .map_err(|_| AppError::Internal)?
+    };
+
+    argon
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    if needs_rehash(config, &parsed_hash, &stored_params)? {
+        return Ok(PasswordVerification::NeedsRehash);
+    }
+    Ok(PasswordVerification::Current)
+}
+
+fn needs_rehash(
+    config: &PasswordConfig,
+    parsed_hash: &PasswordHash<'_>,
+    stored_params: &Params,
+) -> Result<bool, AppError> {
+    let current = current_params(config)?;
+    let algorithm_outdated = parsed_hash.algorithm != Algorithm::Argon2id.ident();
+    let version_outdated = parsed_hash.version != Some(Version::V0x13.into());
+
+    Ok(algorithm_outdated
+        || version_outdated
+        || stored_params.m_cost() != current.m_cost()
+        || stored_params.t_cost() != current.t_cost()
+        || stored_params.p_cost() != current.p_cost()
+        || stored_params.keyid() != current.keyid())
+}
+
+pub async fn check_password_policy(
+    policy: &PasswordPolicyConfig,
+    password: &str,
+    email: &str,
+) -> Result<(), PasswordPolicyViolation> {
+    check_password_rules(policy, password, email)?;
+    check_breached_password(policy, password).await
+}
+
+pub async fn check_breached_password(
+    policy: &PasswordPolicyConfig,
+    password: &str,
+) -> Result<(), PasswordPolicyViolation> {
+    if let Some(corpus_dir) = policy.breached_corpus_dir.as_deref() {
+        if is_breached(corpus_dir, password).await {
+            return Err(PasswordPolicyViolation::Breached);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn check_password_rules(
+    policy: &PasswordPolicyConfig,
+    password: &str,
+    email: &str,
+) -> Result<(), PasswordPolicyViolation> {
+    let length = password.chars().count();
+    if length < policy.min_length {
+        return Err(PasswordPolicyViolation::TooShort(policy.min_length));
+    }
+    if length > PASSWORD_MAX_LENGTH {
+        return Err(PasswordPolicyViolation::TooLong(PASSWORD_MAX_LENGTH));
+    }
+
+    let has_letter = password.chars().any(char::is_alphabetic);
+    let has_number = password.chars().any(|c| c.is_ascii_digit());
+    if !has_letter || !has_number {
+        return Err(PasswordPolicyViolation::MissingLetterOrDigit);
+    }
+
+    if policy.reject_email && contains_email(password, email) {
+        return Err(PasswordPolicyViolation::ContainsEmail);
+    }
+
+    if strength_score(password) < policy.min_score {
+        return Err(PasswordPolicyViolation::TooWeak);
+    }
+
+    Ok(())
+}
+
+fn contains_email(password: &str, email: &str) -> bool {
+    let password = password.to_lowercase();
+    let email = email.trim().to_lowercase();
+    let local_part = email.split('@').next().unwrap_or_default();
+
+    (!email.is_empty() && password.contains(&email))
+        || (local_part.chars().count() >= 3 && password.contains(local_part))
+}
+
+/// Scores a password from 0 (very weak) to 4 (strong) based on length,
+/// character variety and obvious patterns.
+pub fn strength_score(password: &str) -> u8 {
+    let length = password.chars().count();
+    let classes = [
+        password.chars().any(|c| c.is_lowercase()),
+        password.chars().any(|c| c.is_uppercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|present| **present)
+    .count();
+
+    let mut score: i32 = 0;
+    if length >= 10 {
+        score += 1;
+    }
+    if length >= 14 {
+        score += 1;
+    }
+    if classes >= 3 {
+        score += 1;
+    }
+    if classes == 4 {
+        score += 1;
+    }
+
+    let lowered = password.to_lowercase();
+    if COMMON_SEQUENCES
+        .iter()
+        .any(|sequence| lowered.contains(sequence))
+    {
+        score -= 1;
+    }
+
+    let mut unique = password.chars().collect::<Vec<_>>();
+    unique.sort_unstable();
+    unique.dedup();
+    if unique.len() * 2 < length {
+        score -= 1;
+    }
+
+    score.clamp(0, 4) as u8
+}
+
+fn breached_range_key(password: &str) -> (String, String) {
+    let digest = hex::encode_upper(Sha1::digest(password.as_bytes()));
+    let (prefix, suffix) = digest.split_at(5);
+    (prefix.to_string(), suffix.to_string())
+}
+
+/// Looks the password up in a local k-anonymity corpus laid out like the
+/// Pwned Passwords range API: one `<PREFIX>.txt` file per 5-character SHA-1
+/// prefix holding `SUFFIX:COUNT` lines. Lookup failures are logged and treated
+/// as "not breached" so a missing corpus never blocks sign-ups.
+async fn is_breached(corpus_dir: &std::path::Path, password: &str) -> bool {
+    let (prefix, suffix) = breached_range_key(password);
+    let range_file = corpus_dir.join(format!("{prefix}.txt"));
+
+    match tokio::fs::read_to_string(&range_file).await {
+        Ok(contents) => range_contains(&contents, &suffix),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => false,
+        Err(error) => {
+            tracing::warn!(
+                path = %range_file.display(),
+                "failed to read breached password range: {error}"
+            );
+            false
+        }
+    }
+}
+
+fn range_contains(contents: &str, suffix: &str) -> bool {
+    contents.lines().any(|line| {
+        let entry = line.split(':').next().unwrap_or_default().trim();
+        entry.eq_ignore_ascii_case(suffix)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_policy() -> PasswordPolicyConfig {
+        PasswordPolicyConfig {
+            min_length: 8,
+            min_score: 2,
+            reject_email: true,
+            breached_corpus_dir: None,
+        }
+    }
+
+    fn test_config(pepper: Option<&str>) -> PasswordConfig {
+        PasswordConfig {
+            argon2_memory_kib: 1024,
+            argon2_iterations: 1,
+            argon2_parallelism: 1,
+            pepper: pepper.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn password_hash_and_verify_round_trip() {
+        let config = test_config(None);
+        let password = "strong-password";
+        let hash = hash_password_sync(&config, password).expect("hash");
+        let verification = verify_password_sync(&config, password, &hash).expect("verify ok");
+        assert_eq!(verification, PasswordVerification::Current);
+
+        let wrong = verify_password_sync(&config, "bad-password", &hash);
+        assert!(matches!(wrong, Err(AppError::Unauthorized)));
+    }
+
+    #[test]
+    fn peppered_hash_requires_the_same_pepper() {
+        let config = test_config(Some("pepper-one"));
+        let hash = hash_password_sync(&config, "strong-password").expect("hash");
+
+        let verification =
+            verify_password_sync(&config, "strong-password", &hash).expect("verify ok");
+        assert_eq!(verification, PasswordVerification::Current);
+
+        let rotated =
+            verify_password_sync(&test_config(Some("pepper-two")), "strong-password", &hash);
+        assert!(matches!(rotated, Err(AppError::Unauthorized)));
+    }
+
+    #[test]
+    fn changed_parameters_request_rehash() {
+        let old_config = test_config(None);
+        let hash = hash_password_sync(&old_config, "strong-password").expect("hash");
+
+        let mut new_config = test_config(None);
+        new_config.argon2_iterations = 2;
+
+        let verification =
+            verify_password_sync(&new_config, "strong-password", &hash).expect("verify ok");
+        assert_eq!(verification, PasswordVerification::NeedsRehash);
+    }
+
+    #[test]
+    fn legacy_hash_is_upgraded_once_a_pepper_is_configured() {
+        let hash = hash_password_sync(&test_config(None), "strong-password").expect("hash");
+
+        let verification =
+            verify_password_sync(&test_config(Some("pepper")), "strong-password", &hash)
+                .expect("verify ok");
+        assert_eq!(verification, PasswordVerification::NeedsRehash);
+    }
+
+    #[test]
+    fn default_argon2_hash_verifies_with_default_config() {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default()
+            .hash_password(b"strong-password", &salt)
+            .expect("hash")
+            .to_string();
+
+        let verification =
+            verify_password_sync(&PasswordConfig::default(), "strong-password", &hash)
+                .expect("verify ok");
+        assert_eq!(verification, PasswordVerification::Current);
+    }
+
+    #[tokio::test]
+    async fn async_helpers_run_off_the_executor() {
+        let config = test_config(Some("pepper"));
+        let hash = hash_password(&config, "strong-password")
+            .await
+            .expect("hash");
+        let verification = verify_password(&config, "strong-password", &hash)
+            .await
+            .expect("verify");
+        assert_eq!(verification, PasswordVerification::Current);
+    }
+
+    #[test]
+    fn strength_score_rewards_length_and_variety() {
+        assert_eq!(strength_score("abcdefgh1"), 0);
+        assert!(strength_score("Tr0ub4dor&3x") >= 3);
+        assert_eq!(strength_score("correct-Horse-battery-9"), 4);
+    }
+
+    #[test]
+    fn strength_score_penalizes_common_patterns() {
+        assert!(strength_score("Password1234!") < strength_score("Zebra-Lamp-8264"));
+        assert_eq!(strength_score("aaaaaaaaaaaa1"), 0);
+    }
+
+    #[test]
+    fn policy_rejects_short_passwords() {
+        let result = check_password_rules(&test_policy(), "Ab1!", "user@example.com");
+
+        assert_eq!(result, Err(PasswordPolicyViolation::TooShort(8)));
+    }
+
+    #[test]
+    fn policy_rejects_passwords_containing_email() {
+        let result = check_password_rules(&test_policy(), "Johnny-Rocket-77", "johnny@example.com");
+
+        assert_eq!(result, Err(PasswordPolicyViolation::ContainsEmail));
+    }
+
+    #[test]
+    fn policy_rejects_weak_passwords() {
+        let result = check_password_rules(&test_policy(), "abcdefg12", "user@example.com");
+
+        assert_eq!(result, Err(PasswordPolicyViolation::TooWeak));
+    }
+
+    #[test]
+    fn policy_accepts_strong_passwords() {
+        let result = check_password_rules(&test_policy(), "Violet-Kayak-2931", "user@example.com");
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn violations_are_localized() {
+        let violation = PasswordPolicyViolation::TooShort(12);
+
+        assert_eq!(
+            violation.message(Language::En),
+            "password must be at least 12 characters"
+        );
+        assert_eq!(
+            violation.message(Language::Vi),
+            "mật khẩu phải có ít nhất 12 ký tự"
+        );
+    }
+
+    #[test]
+    fn breached_range_key_splits_sha1_prefix() {
+        let (prefix, suffix) = breached_range_key("password");
+
+        assert_eq!(prefix, "5BAA6");
+        assert_eq!(suffix, "1E4C9B93F3F0682250B6CF8331B7EE68FD8");
+    }
+
+    #[test]
+    fn range_contains_matches_suffix_case_insensitively() {
+        let contents = "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n1e4c9b93f3f0682250b6cf8331b7ee68fd8:3861493\r\n";
+
+        assert!(range_contains(
+            contents,
+            "1E4C9B93F3F0682250B6CF8331B7EE68FD8"
+        ));
+        assert!(!range_contains(
+            contents,
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"
+        ));
+    }
+
+    #[tokio::test]
+    async fn breached_corpus_lookup_reads_prefix_file() {
+        let corpus_dir = std::env::temp_dir().join(format!("breached-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&corpus_dir).expect("create corpus dir");
+        let (prefix, suffix) = breached_range_key("Violet-Kayak-2931");
+        std::fs::write(
+            corpus_dir.join(format!("{prefix}.txt")),
+            format!("{suffix}:42\n"),
+        )
+        .expect("write range file");
+
+        let mut policy = test_policy();
+        policy.breached_corpus_dir = Some(corpus_dir.clone());
+
+        assert_eq!(
+            check_password_policy(&policy, "Violet-Kayak-2931", "user@example.com").await,
+            Err(PasswordPolicyViolation::Breached)
+        );
+        assert_eq!(
+            check_password_policy(&policy, "Amber-Canoe-5147", "user@example.com").await,
+            Ok(())
+        );
+
+        std::fs::remove_dir_all(&corpus_dir).expect("cleanup corpus dir");
+    }
+}
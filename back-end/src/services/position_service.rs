@@ -0,0 +1,417 @@
+use std::time::Duration;
+
+use sqlx::PgConnection;
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::todo::TodoResponse,
+    services::{history_service, todo_query, todo_service},
+    state::AppState,
+};
+
+/// Distance between neighbours after a rebalance and when appending.
+const POSITION_STEP: f64 = 1.0;
+/// Neighbours closer than this get no midpoint; their column is rebalanced.
+const MIN_POSITION_GAP: f64 = 1e-9;
+/// Writes that leave a gap below this queue the column for the rebalance
+/// job, well before moves run out of room.
+const REBALANCE_GAP: f64 = 1e-6;
+const REBALANCE_BATCH_SIZE: i64 = 50;
+const POSITION_CONSTRAINT: &str = "todos_column_position_excl";
+
+/// A position picked by [`place`], with the todos renumbered to make room
+/// for it; the caller announces them once the transaction commits.
+pub(crate) struct Placed {
+    pub position: f64,
+    pub renumbered: Vec<Uuid>,
+}
+
+/// Where a moved todo goes within its column.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Placement {
+    After(Uuid),
+    Before(Uuid),
+    End,
+}
+
+/// A position strictly between two neighbours (`None` for a column edge), or
+/// `None` when they are too close to split.
+pub(crate) fn position_between(before: Option<f64>, after: Option<f64>) -> Option<f64> {
+    match (before, after) {
+        (None, None) => Some(0.0),
+        (Some(before), None) => Some(before + POSITION_STEP),
+        (None, Some(after)) => Some(after - POSITION_STEP),
+        (Some(before), Some(after)) => {
+            let middle = before + (after - before) / 2.0;
+            (after - before >= MIN_POSITION_GAP && before < middle && middle < after)
+                .then_some(middle)
+        }
+    }
+}
+
+/// Whether an error is two live todos claiming one position in a column.
+pub(crate) fn is_position_conflict(err: &sqlx::Error) -> bool {
+    matches!(err, sqlx::Error::Database(db_err) if db_err.constraint() == Some(POSITION_CONSTRAINT))
+}
+
+pub(crate) fn position_conflict() -> AppError {
+    AppError::Conflict("another todo already has this position in the column".to_string())
+}
+
+/// Serializes writers that place todos in a column until the transaction
+/// ends, so two of them never pick the same free position.
+pub(crate) async fn lock_column(
+    conn: &mut PgConnection,
+    assignee_id: Option<Uuid>,
+    status: &str,
+) -> Result<(), AppError> {
+    let key = format!(
+        "todo-column:{}:{status}",
+        assignee_id.map(|id| id.to_string()).unwrap_or_default()
+    );
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+        .bind(key)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Locks the column and returns the position after its last todo.
+pub(crate) async fn end_position(
+    conn: &mut PgConnection,
+    assignee_id: Option<Uuid>,
+    status: &str,
+) -> Result<f64, AppError> {
+    lock_column(conn, assignee_id, status).await?;
+    let position = sqlx::query_scalar(
+        "SELECT COALESCE(MAX(position) + $3, 0) FROM todos WHERE assignee_id IS NOT DISTINCT FROM $1 AND status = $2 AND deleted_at IS NULL",
+    )
+    .bind(assignee_id)
+    .bind(status)
+    .bind(POSITION_STEP)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(position)
+}
+
+/// Locks the column and picks a free position for `todo_id` next to the
+/// anchor, rebalancing the column first when the neighbours are too close.
+/// A position that leaves a small gap queues the column for the job.
+pub(crate) async fn place(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    todo_id: Uuid,
+    assignee_id: Option<Uuid>,
+    status: &str,
+    placement: Placement,
+) -> Result<Placed, AppError> {
+    let (anchor_id, anchor_before) = match placement {
+        Placement::End => {
+            return Ok(Placed {
+                position: end_position(conn, assignee_id, status).await?,
+                renumbered: Vec::new(),
+            });
+        }
+        Placement::After(anchor_id) => (anchor_id, true),
+        Placement::Before(anchor_id) => (anchor_id, false),
+    };
+    if anchor_id == todo_id {
+        return Err(AppError::BadRequest(
+            "a todo cannot be placed next to itself".to_string(),
+        ));
+    }
+    lock_column(conn, assignee_id, status).await?;
+
+    let mut renumbered = Vec::new();
+    for attempt in 0..2 {
+        let anchor = sqlx::query_scalar::<_, f64>(
+            "SELECT position FROM todos WHERE id = $1 AND assignee_id IS NOT DISTINCT FROM $2 AND status = $3 AND deleted_at IS NULL AND (reporter_id = $4 OR assignee_id = $4)",
+        )
+        .bind(anchor_id)
+        .bind(assignee_id)
+        .bind(status)
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest("the anchor todo is not in the target column".to_string())
+        })?;
+        let neighbour_sql = if anchor_before {
+            "SELECT MIN(position) FROM todos WHERE assignee_id IS NOT DISTINCT FROM $1 AND status = $2 AND deleted_at IS NULL AND id <> $3 AND position > $4"
+        } else {
+            "SELECT MAX(position) FROM todos WHERE assignee_id IS NOT DISTINCT FROM $1 AND status = $2 AND deleted_at IS NULL AND id <> $3 AND position < $4"
+        };
+        let neighbour = sqlx::query_scalar::<_, Option<f64>>(neighbour_sql)
+            .bind(assignee_id)
+            .bind(status)
+            .bind(todo_id)
+            .bind(anchor)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        let position = if anchor_before {
+            position_between(Some(anchor), neighbour)
+        } else {
+            position_between(neighbour, Some(anchor))
+        };
+        match position {
+            Some(position) => {
+                let gap = (position - anchor)
+                    .abs()
+                    .min(neighbour.map_or(f64::INFINITY, |neighbour| (position - neighbour).abs()));
+                if gap < REBALANCE_GAP {
+                    queue_column(conn, assignee_id, status).await?;
+                }
+                return Ok(Placed {
+                    position,
+                    renumbered,
+                });
+            }
+            None if attempt == 0 => {
+                // The caller already holds its own row and the column lock,
+                // while other movers take their row before the column lock.
+                // Waiting for their rows here would deadlock, so a busy
+                // column is a conflict to retry instead.
+                lock_column_rows(conn, assignee_id, status, false).await?;
+                renumbered = rebalance_column(conn, Some(user_id), assignee_id, status).await?;
+            }
+            None => break,
+        }
+    }
+
+    // Only a column squeezed again between the rebalance and the retry ends
+    // up here; the client can simply try again.
+    Err(AppError::Conflict(
+        "no room left next to the anchor todo; try again".to_string(),
+    ))
+}
+
+/// Queues a column for the rebalance job.
+async fn queue_column(
+    conn: &mut PgConnection,
+    assignee_id: Option<Uuid>,
+    status: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO todo_crowded_columns (assignee_id, status) VALUES ($1, $2)
+        ON CONFLICT ((COALESCE(assignee_id, '00000000-0000-0000-0000-000000000000'::uuid)), status) DO NOTHING",
+    )
+    .bind(assignee_id)
+    .bind(status)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Queues a column for the rebalance job if any of its gaps got small, for
+/// writes that take a position from the client. The column is read through
+/// the index behind `todos_column_position_excl`.
+pub(crate) async fn queue_if_crowded(
+    conn: &mut PgConnection,
+    assignee_id: Option<Uuid>,
+    status: &str,
+) -> Result<(), AppError> {
+    let crowded = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+            SELECT 1 FROM (
+                SELECT position - LAG(position) OVER (ORDER BY position) AS gap
+                FROM todos
+                WHERE COALESCE(assignee_id, '00000000-0000-0000-0000-000000000000'::uuid) = COALESCE($1, '00000000-0000-0000-0000-000000000000'::uuid)
+                    AND status = $2 AND deleted_at IS NULL
+            ) gaps
+            WHERE gap < $3
+        )",
+    )
+    .bind(assignee_id)
+    .bind(status)
+    .bind(REBALANCE_GAP)
+    .fetch_one(&mut *conn)
+    .await?;
+    if crowded {
+        queue_column(conn, assignee_id, status).await?;
+    }
+    Ok(())
+}
+
+/// Row-locks the live todos of a column in id order. Without `wait`, a row
+/// held by another transaction fails with a conflict right away.
+async fn lock_column_rows(
+    conn: &mut PgConnection,
+    assignee_id: Option<Uuid>,
+    status: &str,
+    wait: bool,
+) -> Result<(), AppError> {
+    let sql = format!(
+        "SELECT id FROM todos WHERE assignee_id IS NOT DISTINCT FROM $1 AND status = $2 AND deleted_at IS NULL ORDER BY id FOR UPDATE{}",
+        if wait { "" } else { " NOWAIT" }
+    );
+    sqlx::query(&sql)
+        .bind(assignee_id)
+        .bind(status)
+        .execute(&mut *conn)
+        .await
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db_err) if db_err.code().as_deref() == Some("55P03") => {
+                AppError::Conflict(
+                    "the column is being reordered by someone else; try again".to_string(),
+                )
+            }
+            _ => AppError::from(err),
+        })?;
+
+    Ok(())
+}
+
+/// Renumbers a column to evenly spaced positions, keeping its order, records
+/// each moved todo in its history (without an actor for the job) and takes
+/// the column off the queue. The caller holds the column lock. Returns the
+/// renumbered todos.
+async fn rebalance_column(
+    conn: &mut PgConnection,
+    actor_id: Option<Uuid>,
+    assignee_id: Option<Uuid>,
+    status: &str,
+) -> Result<Vec<Uuid>, AppError> {
+    let renumbered = sqlx::query_as::<_, (Uuid, f64, f64)>(
+        "UPDATE todos SET position = (ranked.rank - 1) * $3
+        FROM (
+            SELECT id, position, ROW_NUMBER() OVER (ORDER BY position, id) AS rank
+            FROM todos
+            WHERE assignee_id IS NOT DISTINCT FROM $1 AND status = $2 AND deleted_at IS NULL
+        ) ranked
+        WHERE todos.id = ranked.id AND todos.position <> (ranked.rank - 1) * $3
+        RETURNING todos.id, ranked.position, todos.position",
+    )
+    .bind(assignee_id)
+    .bind(status)
+    .bind(POSITION_STEP)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (todo_id, from, to) in &renumbered {
+        history_service::record(
+            &mut *conn,
+            *todo_id,
+            actor_id,
+            "reordered",
+            history_service::diff(
+                Some(&serde_json::json!({ "position": from })),
+                Some(&serde_json::json!({ "position": to })),
+            ),
+        )
+        .await?;
+    }
+    sqlx::query(
+        "DELETE FROM todo_crowded_columns
+        WHERE COALESCE(assignee_id, '00000000-0000-0000-0000-000000000000'::uuid) = COALESCE($1, '00000000-0000-0000-0000-000000000000'::uuid) AND status = $2",
+    )
+    .bind(assignee_id)
+    .bind(status)
+    .execute(&mut *conn)
+    .await?;
+    Ok(renumbered
+        .into_iter()
+        .map(|(todo_id, _, _)| todo_id)
+        .collect())
+}
+
+/// Sends `todo_updated` for renumbered todos, whose positions and versions
+/// changed. Without an actor (the job) each todo's reporter stands in.
+pub(crate) async fn broadcast_renumbered(
+    state: &AppState,
+    actor_id: Option<Uuid>,
+    todo_ids: &[Uuid],
+) {
+    if todo_ids.is_empty() {
+        return;
+    }
+    let todos = match sqlx::query_as::<_, TodoResponse>(&format!(
+        "{} WHERE todos.id = ANY($1)",
+        todo_query::select_todos()
+    ))
+    .bind(todo_ids)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(todos) => todos,
+        Err(err) => {
+            tracing::warn!(error = %err, "failed to load renumbered todos");
+            return;
+        }
+    };
+    for todo in &todos {
+        let actor_id = actor_id.unwrap_or(todo.reporter_id);
+        todo_service::broadcast_todo_event(state, actor_id, "todo_updated", Some(todo), None).await;
+    }
+}
+
+/// Starts the background loop that spreads out crowded columns.
+pub fn spawn_rebalance_job(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(state.todos.rebalance_interval_seconds));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match run_rebalance_once(&state).await {
+                Ok(0) => {}
+                Ok(columns) => tracing::info!(columns, "todo columns rebalanced"),
+                Err(err) => tracing::error!(error = %err, "todo column rebalance failed"),
+            }
+        }
+    });
+}
+
+/// Rebalances one batch of queued columns, oldest first. Each column gets
+/// its own short transaction.
+pub async fn run_rebalance_once(state: &AppState) -> Result<usize, AppError> {
+    let columns = sqlx::query_as::<_, (Option<Uuid>, String)>(
+        "SELECT assignee_id, status FROM todo_crowded_columns ORDER BY queued_at LIMIT $1",
+    )
+    .bind(REBALANCE_BATCH_SIZE)
+    .fetch_all(&state.db)
+    .await?;
+
+    for (assignee_id, status) in &columns {
+        let mut tx = state.db.begin().await?;
+        // Rows before the column, like the request paths, to avoid deadlocks.
+        lock_column_rows(&mut tx, *assignee_id, status, true).await?;
+        lock_column(&mut tx, *assignee_id, status).await?;
+        let renumbered = rebalance_column(&mut tx, None, *assignee_id, status).await?;
+        tx.commit().await?;
+        broadcast_renumbered(state, None, &renumbered).await;
+    }
+    Ok(columns.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_fall_between_neighbours() {
+        assert_eq!(position_between(None, None), Some(0.0));
+        assert_eq!(position_between(Some(3.0), None), Some(4.0));
+        assert_eq!(position_between(None, Some(0.0)), Some(-1.0));
+        assert_eq!(position_between(Some(1.0), Some(2.0)), Some(1.5));
+    }
+
+    #[test]
+    fn crowded_neighbours_need_a_rebalance() {
+        assert_eq!(position_between(Some(1.0), Some(1.0 + 1e-12)), None);
+        assert_eq!(position_between(Some(1.0), Some(1.0)), None);
+
+        let mut after = 1.0;
+        let mut moves = 0;
+        while let Some(position) = position_between(Some(0.0), Some(after)) {
+            after = position;
+            moves += 1;
+        }
+        assert!(moves >= 29, "only {moves} moves fit before rebalancing");
+    }
+}
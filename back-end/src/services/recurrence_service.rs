@@ -0,0 +1,352 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use rrule::{Frequency, RRule, Unvalidated};
+use serde_json::Value;
+use sqlx::{FromRow, PgConnection, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::todo::{RecurrenceRequest, TodoResponse},
+    services::{
+        history_service, position_service, todo_query, todo_service, undo_service::UndoStep,
+        user_service,
+    },
+    state::AppState,
+};
+
+const MAX_RRULE_CHARS: usize = 500;
+
+#[derive(Debug, FromRow)]
+struct SeriesOccurrence {
+    rrule: String,
+    dtstart: DateTime<Utc>,
+    time_zone: String,
+    due_at: DateTime<Utc>,
+    assignee_id: Option<Uuid>,
+}
+
+fn invalid_rule(detail: impl std::fmt::Display) -> AppError {
+    AppError::BadRequest(format!("invalid recurrence: {detail}"))
+}
+
+/// Returns the rule upper-cased and without an `RRULE:` prefix, as stored.
+fn parse_rule(raw: &str) -> Result<(String, RRule<Unvalidated>), AppError> {
+    let trimmed = raw.trim();
+    let rule = match trimmed.get(..6) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("RRULE:") => &trimmed[6..],
+        _ => trimmed,
+    };
+    if rule.is_empty() {
+        return Err(invalid_rule("rrule is required"));
+    }
+    if rule.chars().count() > MAX_RRULE_CHARS {
+        return Err(invalid_rule(format!(
+            "rrule must be at most {MAX_RRULE_CHARS} characters"
+        )));
+    }
+    // Only a bare RRULE is accepted; the series anchors it to the due date.
+    if rule.contains(['\n', '\r']) || rule.to_ascii_uppercase().contains("DTSTART") {
+        return Err(invalid_rule("only the RRULE value is accepted"));
+    }
+
+    let normalized = rule.to_ascii_uppercase();
+    let rule = normalized
+        .parse::<RRule<Unvalidated>>()
+        .map_err(invalid_rule)?;
+    if matches!(
+        rule.get_freq(),
+        Frequency::Hourly | Frequency::Minutely | Frequency::Secondly
+    ) {
+        return Err(invalid_rule("FREQ must be DAILY or less frequent"));
+    }
+    Ok((normalized, rule))
+}
+
+/// Validates `raw` against the first occurrence and returns the rule that is
+/// stored on the series.
+pub fn normalize_rule(
+    raw: &str,
+    dtstart: DateTime<Utc>,
+    time_zone: Tz,
+) -> Result<String, AppError> {
+    let dtstart = dtstart.with_timezone(&rrule::Tz::from(time_zone));
+    let (normalized, rule) = parse_rule(raw)?;
+    rule.validate(dtstart).map_err(invalid_rule)?;
+    Ok(normalized)
+}
+
+/// The first occurrence strictly after `after`. Occurrences are computed in
+/// the series time zone so a 09:00 chore stays at 09:00 across DST changes.
+pub fn next_occurrence(
+    rule: &str,
+    dtstart: DateTime<Utc>,
+    time_zone: Tz,
+    after: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    let time_zone = rrule::Tz::from(time_zone);
+    let set = parse_rule(rule)?
+        .1
+        .build(dtstart.with_timezone(&time_zone))
+        .map_err(invalid_rule)?
+        .after(after.with_timezone(&time_zone));
+
+    Ok(set
+        .all(2)
+        .dates
+        .into_iter()
+        .map(|date| date.with_timezone(&Utc))
+        .find(|date| *date > after))
+}
+
+/// Starts a series for a todo being created inside `tx`.
+pub async fn create_series(
+    tx: &mut Transaction<'_, Postgres>,
+    owner_id: Uuid,
+    rule: &str,
+    dtstart: DateTime<Utc>,
+    time_zone: Tz,
+) -> Result<Uuid, AppError> {
+    let series_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO todo_series (id, owner_id, rrule, dtstart, time_zone) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(series_id)
+    .bind(owner_id)
+    .bind(rule)
+    .bind(dtstart)
+    .bind(time_zone.name())
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(series_id)
+}
+
+/// Sets or replaces the recurrence of a todo. The rule is re-anchored on the
+/// todo's due date, which becomes the series' first occurrence.
+pub async fn set_recurrence(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    payload: RecurrenceRequest,
+) -> Result<TodoResponse, AppError> {
+    let todo = todo_service::get_todo(state, user_id, todo_id).await?;
+    let due_at = todo
+        .due_at
+        .ok_or_else(|| invalid_rule("the todo needs a due date"))?;
+    let time_zone = user_service::time_zone(state, user_id).await?;
+    let rule = normalize_rule(&payload.rrule, due_at, time_zone)?;
+
+    let mut tx = state.db.begin().await?;
+    let updated = match todo.series_id {
+        Some(series_id) => {
+            sqlx::query(
+                "UPDATE todo_series SET rrule = $1, dtstart = $2, time_zone = $3, active = TRUE, updated_at = NOW() WHERE id = $4",
+            )
+            .bind(&rule)
+            .bind(due_at)
+            .bind(time_zone.name())
+            .bind(series_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+        }
+        None => {
+            let series_id = create_series(&mut tx, user_id, &rule, due_at, time_zone).await?;
+            sqlx::query("UPDATE todos SET series_id = $1, updated_at = NOW() WHERE id = $2")
+                .bind(series_id)
+                .bind(todo_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+        }
+    };
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+    tx.commit().await?;
+
+    let todo = todo_service::get_todo(state, user_id, todo_id).await?;
+    todo_service::broadcast_todo_event(state, user_id, "todo_updated", Some(&todo), None).await;
+    Ok(todo)
+}
+
+/// Stops a series. Existing occurrences are kept; no new ones are created.
+pub async fn stop_recurrence(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<TodoResponse, AppError> {
+    let todo = todo_service::get_todo(state, user_id, todo_id).await?;
+    let series_id = todo.series_id.ok_or(AppError::NotFound)?;
+
+    let result = sqlx::query(
+        "UPDATE todo_series SET active = FALSE, updated_at = NOW() WHERE id = $1 AND active",
+    )
+    .bind(series_id)
+    .execute(&state.db)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    let todo = todo_service::get_todo(state, user_id, todo_id).await?;
+    todo_service::broadcast_todo_event(state, user_id, "todo_updated", Some(&todo), None).await;
+    Ok(todo)
+}
+
+/// Creates the occurrence that follows a completed one, copying its title,
+/// description, people, priority and estimate, and records it in the history
+/// as created by `actor_id`. It runs in the completing transaction, so a
+/// series never ends because that write committed without it. The next due
+/// date depends only on the series and the completed occurrence, and
+/// `todos_series_due_key` rejects duplicates, so repeated completions create
+/// it at most once. The caller announces it after commit and pushes the
+/// returned undo step, which removes it again while it is untouched.
+pub(crate) async fn spawn_next_occurrence(
+    conn: &mut PgConnection,
+    actor_id: Uuid,
+    todo_id: Uuid,
+) -> Result<Option<(TodoResponse, UndoStep)>, AppError> {
+    let occurrence = sqlx::query_as::<_, SeriesOccurrence>(
+        "SELECT series.rrule, series.dtstart, series.time_zone, todos.due_at, todos.assignee_id
+        FROM todos JOIN todo_series series ON series.id = todos.series_id
+        WHERE todos.id = $1 AND todos.status = 'done' AND series.active AND todos.due_at IS NOT NULL",
+    )
+    .bind(todo_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(occurrence) = occurrence else {
+        return Ok(None);
+    };
+
+    let time_zone = occurrence.time_zone.parse::<Tz>().unwrap_or(Tz::UTC);
+    let Some(next_due_at) = next_occurrence(
+        &occurrence.rrule,
+        occurrence.dtstart,
+        time_zone,
+        occurrence.due_at,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let position = position_service::end_position(conn, occurrence.assignee_id, "todo").await?;
+    let next = sqlx::query_as::<_, TodoResponse>(&format!(
+        "WITH written AS (
+            INSERT INTO todos (id, reporter_id, assignee_id, title, description, description_html, description_excerpt, completed, status, position, start_at, due_at, series_id, parent_id, priority, estimate)
+            SELECT $1, source.reporter_id, source.assignee_id, source.title, source.description, source.description_html, source.description_excerpt, FALSE, 'todo', $4,
+                source.start_at + ($2 - source.due_at), $2, source.series_id, source.parent_id, source.priority, source.estimate
+            FROM todos source WHERE source.id = $3
+            ON CONFLICT (series_id, due_at) WHERE series_id IS NOT NULL DO NOTHING
+            RETURNING *
+        ) {}",
+        todo_query::select_todos_from("written todos")
+    ))
+    .bind(Uuid::new_v4())
+    .bind(next_due_at)
+    .bind(todo_id)
+    .bind(position)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(next) = next else {
+        return Ok(None);
+    };
+    let created = history_service::snapshot(&mut *conn, next.id).await?;
+    history_service::record(
+        &mut *conn,
+        next.id,
+        Some(actor_id),
+        "created",
+        history_service::diff(None, Some(&created)),
+    )
+    .await?;
+    let step = UndoStep {
+        todo_id: next.id,
+        before: Value::Null,
+        after: created,
+    };
+    Ok(Some((next, step)))
+}
+
+/// Sends `todo_created` for occurrences spawned by a committed write.
+pub(crate) async fn broadcast_spawned(state: &AppState, actor_id: Uuid, spawned: &[TodoResponse]) {
+    for todo in spawned {
+        todo_service::broadcast_todo_event(state, actor_id, "todo_created", Some(todo), None).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("timestamp")
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn weekly_rule_keeps_local_time_across_dst() {
+        // Mondays 09:00 in New York: EDT (UTC-4) until 2024-11-03, then EST.
+        let dtstart = utc("2024-10-28T13:00:00Z");
+        let rule = normalize_rule(
+            "RRULE:freq=weekly;byday=MO",
+            dtstart,
+            chrono_tz::America::New_York,
+        )
+        .expect("rule");
+        assert_eq!(rule, "FREQ=WEEKLY;BYDAY=MO");
+
+        let next =
+            next_occurrence(&rule, dtstart, chrono_tz::America::New_York, dtstart).expect("next");
+
+        assert_eq!(next, Some(utc("2024-11-04T14:00:00Z")));
+    }
+
+    #[test]
+    fn monthly_rule_skips_short_months() {
+        let dtstart = utc("2024-01-31T09:00:00Z");
+
+        let next =
+            next_occurrence("FREQ=MONTHLY;BYMONTHDAY=31", dtstart, Tz::UTC, dtstart).expect("next");
+
+        assert_eq!(next, Some(utc("2024-03-31T09:00:00Z")));
+    }
+
+    #[test]
+    fn finished_series_has_no_next_occurrence() {
+        let dtstart = utc("2024-05-01T09:00:00Z");
+
+        let next = next_occurrence(
+            "FREQ=DAILY;COUNT=2",
+            dtstart,
+            Tz::UTC,
+            utc("2024-05-02T09:00:00Z"),
+        )
+        .expect("next");
+
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn rejects_sub_daily_and_malformed_rules() {
+        let dtstart = utc("2024-05-01T09:00:00Z");
+
+        for raw in [
+            "FREQ=HOURLY",
+            "FREQ=FORTNIGHTLY",
+            "",
+            "DTSTART:20240501T090000Z\nRRULE:FREQ=DAILY",
+        ] {
+            assert!(
+                matches!(
+                    normalize_rule(raw, dtstart, Tz::UTC),
+                    Err(AppError::BadRequest(_))
+                ),
+                "{raw:?} should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use sqlx::FromRow;
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::todo::{
+        SnoozeReminderRequest, SnoozeReminderResponse, TodoRealtimeEvent, TodoReminder,
+        TodoResponse,
+    },
+    services::{email_service, todo_query, todo_service},
+    state::AppState,
+};
+
+const BATCH_SIZE: i64 = 100;
+const MIN_SNOOZE_MINUTES: i64 = 5;
+const MAX_SNOOZE_MINUTES: i64 = 7 * 24 * 60;
+/// Emails tried per reminder before it is given up.
+const MAX_DELIVERY_ATTEMPTS: i32 = 5;
+
+/// A reminder this replica has claimed and must deliver.
+#[derive(Debug, FromRow)]
+struct ClaimedReminder {
+    id: Uuid,
+    todo_id: Uuid,
+    user_id: Uuid,
+    email: String,
+    time_zone: String,
+    due_at: DateTime<Utc>,
+    lead_minutes: Option<i32>,
+    /// Failed deliveries so far; a retry skips the realtime event.
+    attempts: i32,
+}
+
+fn validate_snooze_minutes(minutes: i64) -> Result<Duration, AppError> {
+    if !(MIN_SNOOZE_MINUTES..=MAX_SNOOZE_MINUTES).contains(&minutes) {
+        return Err(AppError::BadRequest(format!(
+            "minutes must be between {MIN_SNOOZE_MINUTES} and {MAX_SNOOZE_MINUTES}"
+        )));
+    }
+    Ok(Duration::minutes(minutes))
+}
+
+/// Wait before retrying a reminder whose email failed `attempts` times:
+/// one minute, doubling each time.
+fn retry_delay(attempts: i32) -> Duration {
+    Duration::minutes(1 << (attempts - 1).clamp(0, 10))
+}
+
+fn format_due(due_at: DateTime<Utc>, time_zone: &str) -> String {
+    let time_zone = time_zone.parse::<Tz>().unwrap_or(Tz::UTC);
+    due_at
+        .with_timezone(&time_zone)
+        .format("%H:%M %d/%m/%Y (%Z)")
+        .to_string()
+}
+
+/// Starts the background loop that delivers due reminders. Every replica may
+/// run it: reminders are claimed in the database before they are sent.
+pub fn spawn_scheduler(state: AppState) {
+    if !state.reminders.enabled {
+        tracing::info!("reminder scheduler disabled");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(StdDuration::from_secs(
+            state.reminders.poll_interval_seconds,
+        ));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match run_once(&state, Utc::now()).await {
+                Ok(0) => {}
+                Ok(delivered) => tracing::info!(delivered, "reminders delivered"),
+                Err(err) => tracing::error!(error = %err, "reminder run failed"),
+            }
+        }
+    });
+}
+
+pub async fn run_once(state: &AppState, now: DateTime<Utc>) -> Result<usize, AppError> {
+    let mut claimed = claim_lead_reminders(state, now).await?;
+    claimed.extend(claim_expired_snoozes(state, now).await?);
+    claimed.extend(claim_retries(state, now).await?);
+
+    let delivered = claimed.len();
+    for reminder in claimed {
+        deliver(state, reminder).await;
+    }
+    Ok(delivered)
+}
+
+/// Claims, per todo and recipient, the closest lead time that has been
+/// reached. A reminder already sent for the same or a closer lead time
+/// suppresses the farther ones, so a todo created an hour before it is due
+/// does not also get its one-day reminder.
+async fn claim_lead_reminders(
+    state: &AppState,
+    now: DateTime<Utc>,
+) -> Result<Vec<ClaimedReminder>, AppError> {
+    let claimed = sqlx::query_as::<_, ClaimedReminder>(
+        "WITH candidates AS (
+            SELECT DISTINCT ON (todos.id, recipient.id) todos.id AS todo_id, recipient.id AS user_id, todos.due_at, leads.lead_minutes
+            FROM todos
+            JOIN users recipient ON recipient.id = COALESCE(todos.assignee_id, todos.reporter_id)
+            CROSS JOIN LATERAL unnest(recipient.reminder_lead_minutes) AS leads(lead_minutes)
+            WHERE NOT todos.completed
+                AND todos.deleted_at IS NULL
+                AND todos.due_at > $1
+                AND todos.due_at <= $1 + INTERVAL '7 days'
+                AND todos.due_at <= $1 + make_interval(mins => leads.lead_minutes)
+                AND NOT EXISTS (SELECT 1 FROM todo_reminder_snoozes snoozes WHERE snoozes.todo_id = todos.id AND snoozes.user_id = recipient.id)
+                AND NOT EXISTS (SELECT 1 FROM todo_reminders sent WHERE sent.kind = 'lead' AND sent.todo_id = todos.id AND sent.user_id = recipient.id AND sent.due_at = todos.due_at AND sent.lead_minutes <= leads.lead_minutes)
+            ORDER BY todos.id, recipient.id, leads.lead_minutes
+            LIMIT $2
+        ), claimed AS (
+            INSERT INTO todo_reminders (id, todo_id, user_id, kind, due_at, lead_minutes)
+            SELECT gen_random_uuid(), todo_id, user_id, 'lead', due_at, lead_minutes FROM candidates
+            ON CONFLICT (todo_id, user_id, due_at, lead_minutes) WHERE kind = 'lead' DO NOTHING
+            RETURNING id, todo_id, user_id, due_at, lead_minutes, attempts
+        )
+        SELECT claimed.id, claimed.todo_id, claimed.user_id, users.email, users.time_zone, claimed.due_at, claimed.lead_minutes, claimed.attempts
+        FROM claimed JOIN users ON users.id = claimed.user_id",
+    )
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(claimed)
+}
+
+/// Deleting the expired snooze is the claim: only one replica gets the row.
+async fn claim_expired_snoozes(
+    state: &AppState,
+    now: DateTime<Utc>,
+) -> Result<Vec<ClaimedReminder>, AppError> {
+    let claimed = sqlx::query_as::<_, ClaimedReminder>(
+        "WITH expired AS (
+            DELETE FROM todo_reminder_snoozes
+            WHERE (todo_id, user_id) IN (
+                SELECT todo_id, user_id FROM todo_reminder_snoozes
+                WHERE snoozed_until <= $1
+                ORDER BY snoozed_until
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING todo_id, user_id
+        ), claimed AS (
+            INSERT INTO todo_reminders (id, todo_id, user_id, kind, due_at)
+            SELECT gen_random_uuid(), expired.todo_id, expired.user_id, 'snooze', todos.due_at
+            FROM expired JOIN todos ON todos.id = expired.todo_id
+            WHERE NOT todos.completed AND todos.deleted_at IS NULL AND todos.due_at IS NOT NULL
+            RETURNING id, todo_id, user_id, due_at, lead_minutes, attempts
+        )
+        SELECT claimed.id, claimed.todo_id, claimed.user_id, users.email, users.time_zone, claimed.due_at, claimed.lead_minutes, claimed.attempts
+        FROM claimed JOIN users ON users.id = claimed.user_id",
+    )
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(claimed)
+}
+
+/// Clearing `retry_at` is the claim on a failed reminder that is due again.
+async fn claim_retries(
+    state: &AppState,
+    now: DateTime<Utc>,
+) -> Result<Vec<ClaimedReminder>, AppError> {
+    let claimed = sqlx::query_as::<_, ClaimedReminder>(
+        "WITH due AS (
+            SELECT id FROM todo_reminders
+            WHERE retry_at <= $1
+            ORDER BY retry_at
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        ), claimed AS (
+            UPDATE todo_reminders SET retry_at = NULL
+            FROM due WHERE todo_reminders.id = due.id
+            RETURNING todo_reminders.id, todo_reminders.todo_id, todo_reminders.user_id, todo_reminders.due_at, todo_reminders.lead_minutes, todo_reminders.attempts
+        )
+        SELECT claimed.id, claimed.todo_id, claimed.user_id, users.email, users.time_zone, claimed.due_at, claimed.lead_minutes, claimed.attempts
+        FROM claimed JOIN users ON users.id = claimed.user_id",
+    )
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(claimed)
+}
+
+/// Keeps the claim of a reminder whose email failed and schedules another
+/// try, unless it has run out of attempts.
+async fn schedule_retry(state: &AppState, reminder: &ClaimedReminder) {
+    let attempts = reminder.attempts + 1;
+    let retry_at = (attempts < MAX_DELIVERY_ATTEMPTS).then(|| Utc::now() + retry_delay(attempts));
+    if retry_at.is_none() {
+        tracing::warn!(reminder_id = %reminder.id, attempts, "reminder given up");
+    }
+    if let Err(err) =
+        sqlx::query("UPDATE todo_reminders SET attempts = $2, retry_at = $3 WHERE id = $1")
+            .bind(reminder.id)
+            .bind(attempts)
+            .bind(retry_at)
+            .execute(&state.db)
+            .await
+    {
+        tracing::error!(reminder_id = %reminder.id, error = %err, "failed to schedule reminder retry");
+    }
+}
+
+async fn deliver(state: &AppState, reminder: ClaimedReminder) {
+    let todo = sqlx::query_as::<_, TodoResponse>(&format!(
+        "{} WHERE todos.id = $1",
+        todo_query::select_todos()
+    ))
+    .bind(reminder.todo_id)
+    .fetch_optional(&state.db)
+    .await;
+    let todo = match todo {
+        // A retry is dropped once the todo no longer needs a reminder.
+        Ok(Some(todo))
+            if reminder.attempts > 0 && (todo.completed || todo.deleted_at.is_some()) =>
+        {
+            return;
+        }
+        Ok(Some(todo)) => todo,
+        Ok(None) => return,
+        Err(err) => {
+            tracing::error!(reminder_id = %reminder.id, error = %err, "failed to load reminder todo");
+            return;
+        }
+    };
+
+    let event = TodoRealtimeEvent {
+        event: "todo_reminder".to_string(),
+        todo_id: Some(todo.id),
+        reminder: Some(TodoReminder {
+            due_at: reminder.due_at,
+            lead_minutes: reminder.lead_minutes,
+            snoozed: reminder.lead_minutes.is_none(),
+        }),
+        label: None,
+        comment: None,
+        attachment: None,
+        todos: None,
+        todo: Some(todo),
+    };
+    if reminder.attempts == 0 {
+        if let Ok(message) = serde_json::to_string(&event) {
+            state
+                .todo_realtime_hub
+                .broadcast_todo_change(reminder.user_id, &[], message)
+                .await;
+        }
+    }
+
+    let title = event
+        .todo
+        .as_ref()
+        .map(|todo| todo.title.as_str())
+        .unwrap_or_default();
+    let due = format_due(reminder.due_at, &reminder.time_zone);
+    if let Err(err) =
+        email_service::send_reminder_email(&state.email, &reminder.email, title, &due).await
+    {
+        tracing::warn!(
+            reminder_id = %reminder.id,
+            user_id = %reminder.user_id,
+            error = %err,
+            "reminder email failed"
+        );
+        schedule_retry(state, &reminder).await;
+        return;
+    }
+
+    if let Err(err) = sqlx::query("UPDATE todo_reminders SET delivered_at = NOW() WHERE id = $1")
+        .bind(reminder.id)
+        .execute(&state.db)
+        .await
+    {
+        tracing::error!(reminder_id = %reminder.id, error = %err, "failed to record reminder delivery");
+    }
+}
+
+pub async fn snooze(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    payload: SnoozeReminderRequest,
+) -> Result<SnoozeReminderResponse, AppError> {
+    let duration = validate_snooze_minutes(payload.minutes)?;
+    let todo = todo_service::get_todo(state, user_id, todo_id).await?;
+    if todo.due_at.is_none() {
+        return Err(AppError::BadRequest("todo has no due date".to_string()));
+    }
+    // Reminders go to the assignee, or the reporter when unassigned.
+    if todo.assignee_id.unwrap_or(todo.reporter_id) != user_id {
+        return Err(AppError::Forbidden);
+    }
+
+    let snoozed_until = sqlx::query_scalar::<_, DateTime<Utc>>(
+        "INSERT INTO todo_reminder_snoozes (todo_id, user_id, snoozed_until) VALUES ($1, $2, $3) ON CONFLICT (todo_id, user_id) DO UPDATE SET snoozed_until = EXCLUDED.snoozed_until RETURNING snoozed_until",
+    )
+    .bind(todo_id)
+    .bind(user_id)
+    .bind(Utc::now() + duration)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(SnoozeReminderResponse {
+        todo_id,
+        snoozed_until,
+    })
+}
+
+pub async fn cancel_snooze(state: &AppState, user_id: Uuid, todo_id: Uuid) -> Result<(), AppError> {
+    let result =
+        sqlx::query("DELETE FROM todo_reminder_snoozes WHERE todo_id = $1 AND user_id = $2")
+            .bind(todo_id)
+            .bind(user_id)
+            .execute(&state.db)
+            .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snooze_minutes_are_bounded() {
+        assert_eq!(
+            validate_snooze_minutes(30).expect("duration"),
+            Duration::minutes(30)
+        );
+        assert!(validate_snooze_minutes(4).is_err());
+        assert!(validate_snooze_minutes(MAX_SNOOZE_MINUTES + 1).is_err());
+    }
+
+    #[test]
+    fn failed_reminders_back_off() {
+        assert_eq!(retry_delay(1), Duration::minutes(1));
+        assert_eq!(retry_delay(2), Duration::minutes(2));
+        assert_eq!(retry_delay(4), Duration::minutes(8));
+    }
+
+    #[test]
+    fn due_time_is_shown_in_the_recipient_time_zone() {
+        let due_at = DateTime::parse_from_rfc3339("2024-05-01T17:30:00Z")
+            .expect("timestamp")
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            format_due(due_at, "Asia/Ho_Chi_Minh"),
+            "00:30 02/05/2024 (+07)"
+        );
+        assert_eq!(format_due(due_at, "not/a-zone"), "17:30 01/05/2024 (UTC)");
+    }
+}
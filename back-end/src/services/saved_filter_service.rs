@@ -0,0 +1,164 @@
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        filter::{SavedFilterRequest, SavedFilterResponse},
+        todo::{ListTodosQuery, TodoPage},
+    },
+    services::{todo_filter::TodoFilter, todo_service},
+    state::AppState,
+};
+
+const MAX_NAME_LENGTH: usize = 100;
+
+fn normalize_request(payload: SavedFilterRequest) -> Result<(String, String), AppError> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("name is required".to_string()));
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "name must be at most {MAX_NAME_LENGTH} characters"
+        )));
+    }
+
+    let query = payload.query.trim().to_string();
+    if query.is_empty() {
+        return Err(AppError::BadRequest("query is required".to_string()));
+    }
+    TodoFilter::parse(&query)?;
+
+    Ok((name, query))
+}
+
+fn map_name_conflict(err: sqlx::Error) -> AppError {
+    if let sqlx::Error::Database(db_err) = &err {
+        if db_err.constraint() == Some("saved_filters_user_name_key") {
+            return AppError::BadRequest("a filter with this name already exists".to_string());
+        }
+    }
+    AppError::from(err)
+}
+
+pub async fn list_filters(
+    state: &AppState,
+    user_id: Uuid,
+) -> Result<Vec<SavedFilterResponse>, AppError> {
+    let filters = sqlx::query_as::<_, SavedFilterResponse>(
+        "SELECT id, name, query, created_at, updated_at FROM saved_filters WHERE user_id = $1 ORDER BY name",
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(filters)
+}
+
+pub async fn create_filter(
+    state: &AppState,
+    user_id: Uuid,
+    payload: SavedFilterRequest,
+) -> Result<SavedFilterResponse, AppError> {
+    let (name, query) = normalize_request(payload)?;
+
+    let filter = sqlx::query_as::<_, SavedFilterResponse>(
+        "INSERT INTO saved_filters (id, user_id, name, query) VALUES ($1, $2, $3, $4) RETURNING id, name, query, created_at, updated_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(name)
+    .bind(query)
+    .fetch_one(&state.db)
+    .await
+    .map_err(map_name_conflict)?;
+
+    Ok(filter)
+}
+
+pub async fn update_filter(
+    state: &AppState,
+    user_id: Uuid,
+    filter_id: Uuid,
+    payload: SavedFilterRequest,
+) -> Result<SavedFilterResponse, AppError> {
+    let (name, query) = normalize_request(payload)?;
+
+    let filter = sqlx::query_as::<_, SavedFilterResponse>(
+        "UPDATE saved_filters SET name = $1, query = $2, updated_at = NOW() WHERE id = $3 AND user_id = $4 RETURNING id, name, query, created_at, updated_at",
+    )
+    .bind(name)
+    .bind(query)
+    .bind(filter_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(map_name_conflict)?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(filter)
+}
+
+pub async fn delete_filter(
+    state: &AppState,
+    user_id: Uuid,
+    filter_id: Uuid,
+) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM saved_filters WHERE id = $1 AND user_id = $2")
+        .bind(filter_id)
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(())
+}
+
+pub async fn run_filter(
+    state: &AppState,
+    user_id: Uuid,
+    filter_id: Uuid,
+    query: ListTodosQuery,
+) -> Result<TodoPage, AppError> {
+    let saved = sqlx::query_scalar::<_, String>(
+        "SELECT query FROM saved_filters WHERE id = $1 AND user_id = $2",
+    )
+    .bind(filter_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let filter = TodoFilter::parse(&saved)?;
+    todo_service::list_todos(state, user_id, query, Some(&filter)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_request_trims_and_validates_query() {
+        let (name, query) = normalize_request(SavedFilterRequest {
+            name: "  My work ".to_string(),
+            query: " assignee:me -completed:true ".to_string(),
+        })
+        .expect("request");
+
+        assert_eq!(name, "My work");
+        assert_eq!(query, "assignee:me -completed:true");
+    }
+
+    #[test]
+    fn normalize_request_rejects_malformed_query() {
+        let result = normalize_request(SavedFilterRequest {
+            name: "Broken".to_string(),
+            query: "status:todo owner:me".to_string(),
+        });
+
+        assert!(matches!(result, Err(AppError::BadRequest(msg)) if msg.contains("position 13")));
+    }
+}
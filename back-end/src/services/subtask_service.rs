@@ -0,0 +1,143 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::todo::TodoResponse,
+    services::{todo_query, todo_service},
+    state::AppState,
+};
+
+/// Levels of nesting, counting the top-level todo: a todo, its subtasks and
+/// their subtasks.
+pub const MAX_SUBTASK_DEPTH: i32 = 3;
+
+/// `parent_depth` is the number of levels from the root down to the new
+/// parent, and `subtree_height` the number of levels in the subtree being
+/// placed under it (1 for a todo without subtasks).
+fn check_depth(parent_depth: i32, subtree_height: i32) -> Result<(), AppError> {
+    if parent_depth + subtree_height > MAX_SUBTASK_DEPTH {
+        return Err(AppError::BadRequest(format!(
+            "subtasks can be nested at most {MAX_SUBTASK_DEPTH} levels deep"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that `parent_id` is visible to the user and that placing `todo_id`
+/// (or a new todo when `None`) under it keeps the tree acyclic and within
+/// the depth limit.
+pub async fn ensure_parent(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Option<Uuid>,
+    parent_id: Uuid,
+) -> Result<(), AppError> {
+    todo_service::get_todo(state, user_id, parent_id)
+        .await
+        .map_err(|err| match err {
+            AppError::NotFound => AppError::BadRequest("parent todo not found".to_string()),
+            other => other,
+        })?;
+
+    let (parent_depth, creates_cycle) = sqlx::query_as::<_, (i32, bool)>(
+        "WITH RECURSIVE ancestors (id, parent_id, depth) AS (
+            SELECT id, parent_id, 1 FROM todos WHERE id = $1
+            UNION ALL
+            SELECT todos.id, todos.parent_id, ancestors.depth + 1
+            FROM todos JOIN ancestors ON todos.id = ancestors.parent_id
+            WHERE ancestors.depth <= $3
+        )
+        SELECT MAX(depth), COALESCE(BOOL_OR(id = $2), FALSE) FROM ancestors",
+    )
+    .bind(parent_id)
+    .bind(todo_id)
+    .bind(MAX_SUBTASK_DEPTH)
+    .fetch_one(&state.db)
+    .await?;
+
+    if creates_cycle {
+        return Err(AppError::BadRequest(
+            "a todo cannot be moved under its own subtask".to_string(),
+        ));
+    }
+
+    let subtree_height = match todo_id {
+        Some(todo_id) => {
+            sqlx::query_scalar::<_, i32>(
+                "WITH RECURSIVE descendants (id, depth) AS (
+                    SELECT id, 1 FROM todos WHERE id = $1
+                    UNION ALL
+                    SELECT todos.id, descendants.depth + 1
+                    FROM todos JOIN descendants ON todos.parent_id = descendants.id
+                    WHERE descendants.depth <= $2
+                )
+                SELECT MAX(depth) FROM descendants",
+            )
+            .bind(todo_id)
+            .bind(MAX_SUBTASK_DEPTH)
+            .fetch_one(&state.db)
+            .await?
+        }
+        None => 1,
+    };
+
+    check_depth(parent_depth, subtree_height)
+}
+
+/// Enforces `TODO_REQUIRE_SUBTASKS_DONE` before `todo_id` moves to `done`.
+pub async fn ensure_subtasks_done<'e>(
+    executor: impl PgExecutor<'e>,
+    todo_id: Uuid,
+) -> Result<(), AppError> {
+    let open = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM todos WHERE parent_id = $1 AND NOT completed AND deleted_at IS NULL",
+    )
+    .bind(todo_id)
+    .fetch_one(executor)
+    .await?;
+
+    if open > 0 {
+        return Err(AppError::BadRequest(format!(
+            "todo has {open} open subtask(s)"
+        )));
+    }
+    Ok(())
+}
+
+/// Direct subtasks of a todo the user can see, in board order.
+pub async fn list_subtasks(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<Vec<TodoResponse>, AppError> {
+    todo_service::get_todo(state, user_id, todo_id).await?;
+
+    let subtasks = sqlx::query_as::<_, TodoResponse>(&format!(
+        "{} WHERE todos.parent_id = $1 AND todos.deleted_at IS NULL AND (todos.reporter_id = $2 OR todos.assignee_id = $2) ORDER BY todos.status_rank, todos.position, todos.id",
+        todo_query::select_todos()
+    ))
+    .bind(todo_id)
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(subtasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth_limit_counts_parent_levels_and_moved_subtree() {
+        assert!(check_depth(1, 1).is_ok());
+        assert!(check_depth(2, 1).is_ok());
+        assert!(check_depth(1, 2).is_ok());
+        assert!(matches!(
+            check_depth(3, 1),
+            Err(AppError::BadRequest(msg)) if msg.contains("3 levels")
+        ));
+        assert!(check_depth(2, 2).is_err());
+    }
+}
@@ -0,0 +1,644 @@
+//! Parser and SQL compiler for the todo filter language, e.g.
+//! `status:in_progress,fixing priority:urgent,high assignee:me -completed:true updated:>7d due:today "login bug"`.
+//!
+//! Terms are separated by whitespace and combined with AND. A leading `-`
+//! negates a term, values may be double-quoted, and bare words become
+//! full-text matches against the todo search vector.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    services::{
+        todo_query::{self, TODO_PRIORITIES, TODO_STATUSES},
+        todo_schedule::{self, DueWindow},
+    },
+};
+
+const MAX_FILTER_LENGTH: usize = 1000;
+const MAX_FILTER_TERMS: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoFilter {
+    terms: Vec<FilterTerm>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FilterTerm {
+    negated: bool,
+    predicate: Predicate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Predicate {
+    Status(Vec<String>),
+    Priority(Vec<String>),
+    /// `estimate:none`; negate it to find estimated todos.
+    Unestimated,
+    Assignee(UserRef),
+    Reporter(UserRef),
+    Completed(bool),
+    Created(DateFilter),
+    Updated(DateFilter),
+    Due(DueFilter),
+    /// Any of the names; an empty list means "no labels".
+    Label(Vec<String>),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DueFilter {
+    Window(DueWindow),
+    Missing,
+    Date(DateFilter),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum UserRef {
+    Me,
+    Nobody,
+    Id(Uuid),
+    Email(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateValue {
+    /// `7d`, `12h`, `2w`: an instant that long before now.
+    Ago(Duration),
+    /// `2024-05-01`: a whole day in the user's time zone.
+    Day(NaiveDate),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DateFilter {
+    comparison: Comparison,
+    value: DateValue,
+}
+
+/// Values the compiled SQL depends on besides the filter itself.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterContext {
+    pub user_id: Uuid,
+    pub now: DateTime<Utc>,
+    pub time_zone: Tz,
+}
+
+#[derive(Debug)]
+struct Token {
+    /// 1-based character position of the token in the input.
+    position: usize,
+    raw: String,
+    key: Option<String>,
+    value: String,
+}
+
+fn error_at(token: &Token, message: impl std::fmt::Display) -> AppError {
+    AppError::BadRequest(format!(
+        "{message} at position {} (`{}`)",
+        token.position, token.raw
+    ))
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, AppError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+
+    while let Some(&(index, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut raw = String::new();
+        let mut key = None;
+        let mut value = String::new();
+        let mut quoted = false;
+        while let Some(&(_, ch)) = chars.peek() {
+            if ch.is_whitespace() && !quoted {
+                break;
+            }
+            chars.next();
+            raw.push(ch);
+            match ch {
+                '"' => quoted = !quoted,
+                ':' if !quoted && key.is_none() => key = Some(std::mem::take(&mut value)),
+                _ => value.push(ch),
+            }
+        }
+
+        if quoted {
+            return Err(AppError::BadRequest(format!(
+                "unterminated quote at position {} (`{raw}`)",
+                index + 1
+            )));
+        }
+        tokens.push(Token {
+            position: index + 1,
+            raw,
+            key,
+            value,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Parses a comma-separated list of `allowed` values for the `key` filter.
+fn parse_choices(token: &Token, key: &str, allowed: &[&str]) -> Result<Vec<String>, AppError> {
+    let values = token
+        .value
+        .split(',')
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            if allowed.contains(&value.as_str()) {
+                Ok(value)
+            } else {
+                Err(error_at(token, format!("unknown {key} `{value}`")))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if values.is_empty() {
+        return Err(error_at(token, format!("missing value for `{key}`")));
+    }
+    Ok(values)
+}
+
+fn parse_estimate(token: &Token) -> Result<Predicate, AppError> {
+    if token.value.trim().eq_ignore_ascii_case("none") {
+        return Ok(Predicate::Unestimated);
+    }
+    Err(error_at(
+        token,
+        "expected `none`; use `-estimate:none` for estimated todos",
+    ))
+}
+
+fn parse_user(token: &Token, allow_nobody: bool) -> Result<UserRef, AppError> {
+    let value = token.value.trim();
+    match value.to_lowercase().as_str() {
+        "me" => Ok(UserRef::Me),
+        "none" if allow_nobody => Ok(UserRef::Nobody),
+        lowered if lowered.contains('@') => Ok(UserRef::Email(lowered.to_string())),
+        _ => Uuid::parse_str(value)
+            .map(UserRef::Id)
+            .map_err(|_| error_at(token, "expected `me`, an email or a user id")),
+    }
+}
+
+fn parse_bool(token: &Token) -> Result<bool, AppError> {
+    match token.value.trim().to_lowercase().as_str() {
+        "true" | "yes" => Ok(true),
+        "false" | "no" => Ok(false),
+        _ => Err(error_at(token, "expected `true` or `false`")),
+    }
+}
+
+fn parse_date(token: &Token) -> Result<DateFilter, AppError> {
+    let value = token.value.trim();
+    let (comparison, rest) = if let Some(rest) = value.strip_prefix(">=") {
+        (Some(Comparison::Gte), rest)
+    } else if let Some(rest) = value.strip_prefix("<=") {
+        (Some(Comparison::Lte), rest)
+    } else if let Some(rest) = value.strip_prefix('>') {
+        (Some(Comparison::Gt), rest)
+    } else if let Some(rest) = value.strip_prefix('<') {
+        (Some(Comparison::Lt), rest)
+    } else if let Some(rest) = value.strip_prefix('=') {
+        (Some(Comparison::Eq), rest)
+    } else {
+        (None, value)
+    };
+
+    let date_value = parse_date_value(rest).ok_or_else(|| {
+        error_at(
+            token,
+            "expected a date like `2024-05-01` or an age like `7d`, `12h`, `2w`",
+        )
+    })?;
+
+    // A bare age reads as "within the last ...".
+    let comparison = match (comparison, date_value) {
+        (Some(comparison), _) => comparison,
+        (None, DateValue::Ago(_)) => Comparison::Gte,
+        (None, DateValue::Day(_)) => Comparison::Eq,
+    };
+    if comparison == Comparison::Eq && matches!(date_value, DateValue::Ago(_)) {
+        return Err(error_at(token, "ages need a comparison such as `>7d`"));
+    }
+
+    Ok(DateFilter {
+        comparison,
+        value: date_value,
+    })
+}
+
+fn parse_due(token: &Token) -> Result<DueFilter, AppError> {
+    if let Some(window) = DueWindow::parse(&token.value) {
+        return Ok(DueFilter::Window(window));
+    }
+    if token.value.trim().eq_ignore_ascii_case("none") {
+        return Ok(DueFilter::Missing);
+    }
+
+    let filter = parse_date(token)?;
+    if matches!(filter.value, DateValue::Ago(_)) {
+        return Err(error_at(
+            token,
+            "expected `overdue`, `today`, `this_week`, `none` or a date like `2024-05-01`",
+        ));
+    }
+    Ok(DueFilter::Date(filter))
+}
+
+fn parse_labels(token: &Token) -> Vec<String> {
+    if token.value.trim().eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    todo_query::parse_label_names(&token.value)
+}
+
+fn parse_date_value(raw: &str) -> Option<DateValue> {
+    if let Ok(day) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        return Some(DateValue::Day(day));
+    }
+
+    let unit = raw.chars().last()?;
+    let amount: i64 = raw[..raw.len() - unit.len_utf8()].parse().ok()?;
+    if !(0..=3650).contains(&amount) {
+        return None;
+    }
+    let duration = match unit {
+        'h' => Duration::hours(amount),
+        'd' => Duration::days(amount),
+        'w' => Duration::weeks(amount),
+        _ => return None,
+    };
+    Some(DateValue::Ago(duration))
+}
+
+fn parse_term(token: &Token) -> Result<FilterTerm, AppError> {
+    let (negated, key) = match token.key.as_deref() {
+        Some(key) => match key.strip_prefix('-') {
+            Some(key) => (true, Some(key.to_lowercase())),
+            None => (false, Some(key.to_lowercase())),
+        },
+        None => (false, None),
+    };
+
+    let Some(key) = key else {
+        let (negated, text) = match token.value.strip_prefix('-') {
+            Some(text) => (true, text.trim()),
+            None => (false, token.value.trim()),
+        };
+        if text.is_empty() {
+            return Err(error_at(token, "expected a term"));
+        }
+        return Ok(FilterTerm {
+            negated,
+            predicate: Predicate::Text(text.to_string()),
+        });
+    };
+
+    if token.value.trim().is_empty() {
+        return Err(error_at(token, format!("missing value for `{key}`")));
+    }
+
+    let predicate = match key.as_str() {
+        "status" => Predicate::Status(parse_choices(token, "status", &TODO_STATUSES)?),
+        "priority" => Predicate::Priority(parse_choices(token, "priority", &TODO_PRIORITIES)?),
+        "estimate" => parse_estimate(token)?,
+        "assignee" => Predicate::Assignee(parse_user(token, true)?),
+        "reporter" => Predicate::Reporter(parse_user(token, false)?),
+        "completed" => Predicate::Completed(parse_bool(token)?),
+        "created" => Predicate::Created(parse_date(token)?),
+        "updated" => Predicate::Updated(parse_date(token)?),
+        "due" => Predicate::Due(parse_due(token)?),
+        "label" => Predicate::Label(parse_labels(token)),
+        other => return Err(error_at(token, format!("unknown filter key `{other}`"))),
+    };
+
+    Ok(FilterTerm { negated, predicate })
+}
+
+impl TodoFilter {
+    pub fn parse(input: &str) -> Result<Self, AppError> {
+        if input.chars().count() > MAX_FILTER_LENGTH {
+            return Err(AppError::BadRequest(format!(
+                "filter must be at most {MAX_FILTER_LENGTH} characters"
+            )));
+        }
+
+        let tokens = tokenize(input)?;
+        if tokens.len() > MAX_FILTER_TERMS {
+            return Err(error_at(
+                &tokens[MAX_FILTER_TERMS],
+                format!("filter has more than {MAX_FILTER_TERMS} terms"),
+            ));
+        }
+
+        let terms = tokens
+            .iter()
+            .map(parse_term)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { terms })
+    }
+
+    /// Appends one `AND (...)` condition per term. Negated terms treat NULL
+    /// comparisons (e.g. no assignee) as "not matching" so they are kept.
+    pub fn push_conditions(
+        &self,
+        builder: &mut QueryBuilder<'_, Postgres>,
+        context: FilterContext,
+    ) {
+        for term in &self.terms {
+            builder.push(if term.negated {
+                " AND NOT COALESCE(("
+            } else {
+                " AND (("
+            });
+            push_predicate(builder, &term.predicate, context);
+            builder.push(if term.negated { "), FALSE)" } else { "))" });
+        }
+    }
+}
+
+fn push_predicate(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    predicate: &Predicate,
+    context: FilterContext,
+) {
+    match predicate {
+        Predicate::Status(statuses) => {
+            builder
+                .push("todos.status = ANY(")
+                .push_bind(statuses.clone())
+                .push(")");
+        }
+        Predicate::Priority(priorities) => {
+            builder
+                .push("todos.priority = ANY(")
+                .push_bind(priorities.clone())
+                .push(")");
+        }
+        Predicate::Unestimated => {
+            builder.push("todos.estimate IS NULL");
+        }
+        Predicate::Assignee(user) => push_user(builder, "assignee", user, context),
+        Predicate::Reporter(user) => push_user(builder, "reporter", user, context),
+        Predicate::Completed(completed) => {
+            builder.push("todos.completed = ").push_bind(*completed);
+        }
+        Predicate::Created(filter) => push_date(builder, "todos.created_at", filter, context),
+        Predicate::Updated(filter) => push_date(builder, "todos.updated_at", filter, context),
+        Predicate::Due(DueFilter::Window(window)) => {
+            todo_query::push_due_window(builder, *window, context.now, context.time_zone);
+        }
+        Predicate::Due(DueFilter::Missing) => {
+            builder.push("todos.due_at IS NULL");
+        }
+        Predicate::Due(DueFilter::Date(filter)) => {
+            push_date(builder, "todos.due_at", filter, context);
+        }
+        Predicate::Label(names) => todo_query::push_labels(builder, names, false),
+        Predicate::Text(text) => {
+            builder
+                .push("todos.search_vector @@ plainto_tsquery('todo_search', ")
+                .push_bind(text.clone())
+                .push(")");
+        }
+    }
+}
+
+fn push_user(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    alias: &str,
+    user: &UserRef,
+    context: FilterContext,
+) {
+    match user {
+        UserRef::Me => {
+            builder
+                .push(format!("todos.{alias}_id = "))
+                .push_bind(context.user_id);
+        }
+        UserRef::Nobody => {
+            builder.push(format!("todos.{alias}_id IS NULL"));
+        }
+        UserRef::Id(id) => {
+            builder.push(format!("todos.{alias}_id = ")).push_bind(*id);
+        }
+        UserRef::Email(email) => {
+            builder
+                .push(format!("{alias}.email = "))
+                .push_bind(email.clone());
+        }
+    }
+}
+
+fn push_date(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    column: &str,
+    filter: &DateFilter,
+    context: FilterContext,
+) {
+    let (start, end) = match filter.value {
+        DateValue::Ago(duration) => {
+            let instant = context.now - duration;
+            (instant, instant)
+        }
+        DateValue::Day(day) => (
+            todo_schedule::local_day_start(context.time_zone, day),
+            todo_schedule::local_day_start(context.time_zone, day + Duration::days(1)),
+        ),
+    };
+
+    match filter.comparison {
+        Comparison::Eq => {
+            builder
+                .push(format!("{column} >= "))
+                .push_bind(start)
+                .push(format!(" AND {column} < "))
+                .push_bind(end);
+        }
+        Comparison::Gt => {
+            builder.push(format!("{column} >= ")).push_bind(end);
+        }
+        Comparison::Gte => {
+            builder.push(format!("{column} >= ")).push_bind(start);
+        }
+        Comparison::Lt => {
+            builder.push(format!("{column} < ")).push_bind(start);
+        }
+        Comparison::Lte => {
+            builder.push(format!("{column} < ")).push_bind(end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(input: &str) -> String {
+        let filter = TodoFilter::parse(input).expect("filter");
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT 1 FROM todos WHERE TRUE");
+        filter.push_conditions(
+            &mut builder,
+            FilterContext {
+                user_id: Uuid::nil(),
+                now: Utc::now(),
+                time_zone: Tz::UTC,
+            },
+        );
+        builder.sql().to_string()
+    }
+
+    fn bad_request(input: &str) -> String {
+        match TodoFilter::parse(input) {
+            Err(AppError::BadRequest(message)) => message,
+            other => panic!("expected bad request, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_typed_terms() {
+        let filter =
+            TodoFilter::parse("status:in_progress,Fixing assignee:me -completed:true updated:>7d")
+                .expect("filter");
+
+        assert_eq!(
+            filter.terms,
+            vec![
+                FilterTerm {
+                    negated: false,
+                    predicate: Predicate::Status(vec![
+                        "in_progress".to_string(),
+                        "fixing".to_string()
+                    ]),
+                },
+                FilterTerm {
+                    negated: false,
+                    predicate: Predicate::Assignee(UserRef::Me),
+                },
+                FilterTerm {
+                    negated: true,
+                    predicate: Predicate::Completed(true),
+                },
+                FilterTerm {
+                    negated: false,
+                    predicate: Predicate::Updated(DateFilter {
+                        comparison: Comparison::Gt,
+                        value: DateValue::Ago(Duration::days(7)),
+                    }),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_values_keep_spaces() {
+        let filter =
+            TodoFilter::parse("\"login bug\" reporter:\"Lead@Example.com\"").expect("filter");
+
+        assert_eq!(
+            filter.terms[0].predicate,
+            Predicate::Text("login bug".to_string())
+        );
+        assert_eq!(
+            filter.terms[1].predicate,
+            Predicate::Reporter(UserRef::Email("lead@example.com".to_string()))
+        );
+    }
+
+    #[test]
+    fn compiles_to_parameterized_sql() {
+        assert_eq!(
+            compile("status:todo -assignee:none created:2024-05-01"),
+            "SELECT 1 FROM todos WHERE TRUE AND ((todos.status = ANY($1))) AND NOT COALESCE((todos.assignee_id IS NULL), FALSE) AND ((todos.created_at >= $2 AND todos.created_at < $3))"
+        );
+    }
+
+    #[test]
+    fn compiles_due_windows() {
+        assert_eq!(
+            compile("due:overdue -due:none"),
+            "SELECT 1 FROM todos WHERE TRUE AND ((todos.due_at < $1 AND NOT todos.completed)) AND NOT COALESCE((todos.due_at IS NULL), FALSE)"
+        );
+        assert!(compile("due:this_week").contains("todos.due_at >= $1 AND todos.due_at < $2"));
+        assert!(bad_request("due:>3d").contains("expected `overdue`"));
+    }
+
+    #[test]
+    fn user_values_are_never_inlined() {
+        let sql = compile("\"'; DROP TABLE todos; --\"");
+
+        assert!(sql.ends_with("todos.search_vector @@ plainto_tsquery('todo_search', $1)))"));
+        assert!(!sql.contains("DROP"));
+    }
+
+    #[test]
+    fn errors_point_at_offending_token() {
+        assert_eq!(
+            bad_request("status:todo prio:high"),
+            "unknown filter key `prio` at position 13 (`prio:high`)"
+        );
+        assert_eq!(
+            bad_request("status:todo,archived"),
+            "unknown status `archived` at position 1 (`status:todo,archived`)"
+        );
+        assert!(bad_request("updated:>soon").contains("position 1"));
+        assert!(bad_request("assignee:").contains("missing value for `assignee`"));
+        assert!(bad_request("title:\"open").starts_with("unterminated quote at position 1"));
+    }
+
+    #[test]
+    fn label_terms_match_any_name_and_combine_with_and() {
+        let filter = TodoFilter::parse("label:Bug,urgent -label:wontfix").expect("filter");
+
+        assert_eq!(
+            filter.terms[0].predicate,
+            Predicate::Label(vec!["bug".to_string(), "urgent".to_string()])
+        );
+        assert!(filter.terms[1].negated);
+        assert_eq!(
+            TodoFilter::parse("label:none").expect("filter").terms[0].predicate,
+            Predicate::Label(Vec::new())
+        );
+        assert!(compile("label:bug label:urgent").contains(
+            "AND ((EXISTS (SELECT 1 FROM todo_labels JOIN labels ON labels.id = todo_labels.label_id WHERE todo_labels.todo_id = todos.id AND lower(labels.name) = ANY($2))))"
+        ));
+    }
+
+    #[test]
+    fn priority_and_estimate_terms() {
+        assert_eq!(
+            compile("priority:Urgent,high -estimate:none"),
+            "SELECT 1 FROM todos WHERE TRUE AND ((todos.priority = ANY($1))) AND NOT COALESCE((todos.estimate IS NULL), FALSE)"
+        );
+        assert_eq!(
+            bad_request("priority:asap"),
+            "unknown priority `asap` at position 1 (`priority:asap`)"
+        );
+        assert!(bad_request("estimate:3").contains("expected `none`"));
+    }
+
+    #[test]
+    fn rejects_ambiguous_dates_and_oversized_filters() {
+        assert!(bad_request("updated:=7d").contains("ages need a comparison"));
+        assert!(bad_request(&"x ".repeat(40)).contains("more than 32 terms"));
+    }
+}
@@ -0,0 +1,541 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{error::AppError, models::todo::TodoResponse, services::todo_schedule::DueWindow};
+
+pub const TODO_COLUMNS: &str = "todos.id, reporter.email AS reporter, todos.reporter_id, reporter.email AS reporter_email, todos.assignee_id, assignee.email AS assignee_email, todos.title, todos.description, todos.description_html, todos.description_excerpt, todos.completed, todos.status, todos.position, todos.priority, todos.estimate, todos.start_at, todos.due_at, todos.series_id, CASE WHEN series.active THEN series.rrule END AS recurrence, todos.parent_id, subtask_rollup.subtask_count, subtask_rollup.subtasks_done, COALESCE(blockers.blocked_by, '[]'::json) AS blocked_by, COALESCE(todo_label_set.labels, '[]'::json) AS labels, todos.created_at, todos.updated_at, todos.deleted_at, todos.version";
+
+pub const TODO_JOINS: &str = "JOIN users reporter ON reporter.id = todos.reporter_id LEFT JOIN users assignee ON assignee.id = todos.assignee_id LEFT JOIN todo_series series ON series.id = todos.series_id LEFT JOIN LATERAL (SELECT COUNT(*) AS subtask_count, COUNT(*) FILTER (WHERE subtasks.completed) AS subtasks_done FROM todos subtasks WHERE subtasks.parent_id = todos.id AND subtasks.deleted_at IS NULL) subtask_rollup ON TRUE LEFT JOIN LATERAL (SELECT json_agg(json_build_object('id', blocker.id, 'title', CASE WHEN shared.visible THEN blocker.title END, 'status', CASE WHEN shared.visible THEN blocker.status END) ORDER BY blocks.created_at) AS blocked_by FROM todo_links blocks JOIN todos blocker ON blocker.id = blocks.source_id CROSS JOIN LATERAL (SELECT todos.reporter_id IN (blocker.reporter_id, blocker.assignee_id) AND (todos.assignee_id IS NULL OR todos.assignee_id IN (blocker.reporter_id, blocker.assignee_id)) AS visible) shared WHERE blocks.target_id = todos.id AND blocks.kind = 'blocks' AND NOT blocker.completed AND blocker.deleted_at IS NULL) blockers ON TRUE LEFT JOIN LATERAL (SELECT json_agg(json_build_object('id', labels.id, 'name', labels.name, 'color', labels.color) ORDER BY lower(labels.name), labels.id) AS labels FROM todo_labels JOIN labels ON labels.id = todo_labels.label_id WHERE todo_labels.todo_id = todos.id) todo_label_set ON TRUE";
+
+pub const TODO_STATUSES: [&str; 7] = [
+    "todo",
+    "planned",
+    "in_progress",
+    "fixing",
+    "waiting_test",
+    "done",
+    "failed",
+];
+
+/// Most urgent first; `priority_rank` follows this order.
+pub const TODO_PRIORITIES: [&str; 4] = ["urgent", "high", "medium", "low"];
+
+pub const DEFAULT_PRIORITY: &str = "medium";
+
+const DEFAULT_PAGE_SIZE: u32 = 100;
+const MAX_PAGE_SIZE: u32 = 500;
+const DEFAULT_SEARCH_LIMIT: u32 = 20;
+const MAX_SEARCH_LIMIT: u32 = 100;
+
+// `ts_headline` wraps matches in these control characters; `render_highlight`
+// escapes the rest of the snippet and turns them into `<mark>` tags.
+const HIGHLIGHT_START: char = '\u{2}';
+const HIGHLIGHT_STOP: char = '\u{3}';
+
+pub fn select_todos() -> String {
+    select_todos_from("todos")
+}
+
+/// Selects todo responses from `source`, which must expose the `todos`
+/// columns under the alias `todos` (e.g. `written todos` for a CTE).
+pub fn select_todos_from(source: &str) -> String {
+    format!("SELECT {TODO_COLUMNS} FROM {source} {TODO_JOINS}")
+}
+
+pub fn headline_options() -> String {
+    format!(
+        "StartSel={HIGHLIGHT_START}, StopSel={HIGHLIGHT_STOP}, MaxWords=30, MinWords=10, HighlightAll=false"
+    )
+}
+
+/// Turns a raw `ts_headline` snippet into HTML-safe text with `<mark>`
+/// around each match.
+pub fn render_highlight(raw: &str) -> String {
+    let mut rendered = String::with_capacity(raw.len() + 16);
+    let mut open = false;
+    for ch in raw.chars() {
+        match ch {
+            HIGHLIGHT_START if !open => {
+                rendered.push_str("<mark>");
+                open = true;
+            }
+            HIGHLIGHT_STOP if open => {
+                rendered.push_str("</mark>");
+                open = false;
+            }
+            HIGHLIGHT_START | HIGHLIGHT_STOP => {}
+            '&' => rendered.push_str("&amp;"),
+            '<' => rendered.push_str("&lt;"),
+            '>' => rendered.push_str("&gt;"),
+            '"' => rendered.push_str("&quot;"),
+            '\'' => rendered.push_str("&#39;"),
+            other => rendered.push(other),
+        }
+    }
+    if open {
+        rendered.push_str("</mark>");
+    }
+    rendered
+}
+
+/// Whitelisted orderings for todo listings. Every sort ends with `todos.id`
+/// so keyset pagination has a unique, stable key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoSort {
+    Board,
+    CreatedAsc,
+    CreatedDesc,
+    UpdatedAsc,
+    UpdatedDesc,
+    DueAsc,
+    DueDesc,
+    PriorityAsc,
+    PriorityDesc,
+}
+
+impl TodoSort {
+    pub fn parse(raw: Option<&str>) -> Result<Self, AppError> {
+        match raw.map(str::trim).unwrap_or("board") {
+            "" | "board" => Ok(Self::Board),
+            "created_at" => Ok(Self::CreatedAsc),
+            "-created_at" => Ok(Self::CreatedDesc),
+            "updated_at" => Ok(Self::UpdatedAsc),
+            "-updated_at" => Ok(Self::UpdatedDesc),
+            "due_at" => Ok(Self::DueAsc),
+            "-due_at" => Ok(Self::DueDesc),
+            "priority" => Ok(Self::PriorityAsc),
+            "-priority" => Ok(Self::PriorityDesc),
+            other => Err(AppError::BadRequest(format!("unsupported sort: {other}"))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Board => "board",
+            Self::CreatedAsc => "created_at",
+            Self::CreatedDesc => "-created_at",
+            Self::UpdatedAsc => "updated_at",
+            Self::UpdatedDesc => "-updated_at",
+            Self::DueAsc => "due_at",
+            Self::DueDesc => "-due_at",
+            Self::PriorityAsc => "priority",
+            Self::PriorityDesc => "-priority",
+        }
+    }
+
+    /// Sort key expressions (before the `todos.id` tie-breaker) with the SQL
+    /// type used to cast cursor values back.
+    fn key_columns(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Board => &[
+                ("todos.status_rank", "smallint"),
+                ("todos.position", "double precision"),
+            ],
+            Self::CreatedAsc | Self::CreatedDesc => &[("todos.created_at", "timestamptz")],
+            Self::UpdatedAsc | Self::UpdatedDesc => &[("todos.updated_at", "timestamptz")],
+            // Missing due dates sort last in both directions.
+            Self::DueAsc => &[(
+                "COALESCE(todos.due_at, 'infinity'::timestamptz)",
+                "timestamptz",
+            )],
+            Self::DueDesc => &[(
+                "COALESCE(todos.due_at, '-infinity'::timestamptz)",
+                "timestamptz",
+            )],
+            // Todos of the same priority keep their board order.
+            Self::PriorityAsc | Self::PriorityDesc => &[
+                ("todos.priority_rank", "smallint"),
+                ("todos.status_rank", "smallint"),
+                ("todos.position", "double precision"),
+            ],
+        }
+    }
+
+    fn descending(self) -> bool {
+        matches!(
+            self,
+            Self::CreatedDesc | Self::UpdatedDesc | Self::DueDesc | Self::PriorityDesc
+        )
+    }
+
+    fn cursor_values(self, todo: &TodoResponse) -> Vec<String> {
+        match self {
+            Self::Board => vec![
+                status_rank(&todo.status).to_string(),
+                todo.position.to_string(),
+            ],
+            Self::CreatedAsc | Self::CreatedDesc => vec![todo.created_at.to_rfc3339()],
+            Self::UpdatedAsc | Self::UpdatedDesc => vec![todo.updated_at.to_rfc3339()],
+            Self::DueAsc | Self::DueDesc => vec![
+                todo.due_at
+                    .map(|due_at| due_at.to_rfc3339())
+                    .unwrap_or_else(|| {
+                        if self == Self::DueAsc {
+                            "infinity"
+                        } else {
+                            "-infinity"
+                        }
+                        .to_string()
+                    }),
+            ],
+            Self::PriorityAsc | Self::PriorityDesc => vec![
+                priority_rank(&todo.priority).to_string(),
+                status_rank(&todo.status).to_string(),
+                todo.position.to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoCursor {
+    sort: String,
+    values: Vec<String>,
+    id: Uuid,
+}
+
+pub fn status_rank(status: &str) -> i16 {
+    TODO_STATUSES
+        .iter()
+        .position(|candidate| *candidate == status)
+        .map(|index| index as i16 + 1)
+        .unwrap_or(TODO_STATUSES.len() as i16 + 1)
+}
+
+pub fn priority_rank(priority: &str) -> i16 {
+    TODO_PRIORITIES
+        .iter()
+        .position(|candidate| *candidate == priority)
+        .map(|index| index as i16 + 1)
+        .unwrap_or(TODO_PRIORITIES.len() as i16 + 1)
+}
+
+pub fn resolve_page_size(limit: Option<u32>) -> Result<usize, AppError> {
+    match limit {
+        None => Ok(DEFAULT_PAGE_SIZE as usize),
+        Some(limit) if (1..=MAX_PAGE_SIZE).contains(&limit) => Ok(limit as usize),
+        Some(_) => Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {MAX_PAGE_SIZE}"
+        ))),
+    }
+}
+
+pub fn resolve_search_limit(limit: Option<u32>) -> Result<usize, AppError> {
+    match limit {
+        None => Ok(DEFAULT_SEARCH_LIMIT as usize),
+        Some(limit) if (1..=MAX_SEARCH_LIMIT).contains(&limit) => Ok(limit as usize),
+        Some(_) => Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {MAX_SEARCH_LIMIT}"
+        ))),
+    }
+}
+
+pub fn encode_cursor(sort: TodoSort, todo: &TodoResponse) -> String {
+    let cursor = TodoCursor {
+        sort: sort.as_str().to_string(),
+        values: sort.cursor_values(todo),
+        id: todo.id,
+    };
+    let json = serde_json::to_vec(&cursor).unwrap_or_default();
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+pub fn decode_cursor(sort: TodoSort, raw: &str) -> Result<TodoCursor, AppError> {
+    let invalid = || AppError::BadRequest("invalid cursor".to_string());
+    let bytes = URL_SAFE_NO_PAD.decode(raw.trim()).map_err(|_| invalid())?;
+    let cursor: TodoCursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+
+    if cursor.sort != sort.as_str() || cursor.values.len() != sort.key_columns().len() {
+        return Err(AppError::BadRequest(
+            "cursor does not match the requested sort".to_string(),
+        ));
+    }
+
+    Ok(cursor)
+}
+
+pub fn push_visibility(builder: &mut QueryBuilder<'_, Postgres>, user_id: Uuid) {
+    builder
+        .push(" WHERE todos.deleted_at IS NULL AND (todos.reporter_id = ")
+        .push_bind(user_id)
+        .push(" OR todos.assignee_id = ")
+        .push_bind(user_id)
+        .push(")");
+}
+
+pub fn push_due_window(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    window: DueWindow,
+    now: DateTime<Utc>,
+    time_zone: Tz,
+) {
+    let (start, end) = window.bounds(now, time_zone);
+    if let Some(start) = start {
+        builder
+            .push("todos.due_at >= ")
+            .push_bind(start)
+            .push(" AND ");
+    }
+    builder.push("todos.due_at < ").push_bind(end);
+    if window == DueWindow::Overdue {
+        builder.push(" AND NOT todos.completed");
+    }
+}
+
+/// Splits a comma-separated list of label names into lowercase, unique names.
+pub fn parse_label_names(raw: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for name in raw.split(',').map(|name| name.trim().to_lowercase()) {
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Matches todos carrying any (or all) of the lowercase label `names`. Names
+/// are compared across the catalogs of everyone on the todo; an empty list
+/// matches todos without labels.
+pub fn push_labels(builder: &mut QueryBuilder<'_, Postgres>, names: &[String], match_all: bool) {
+    if names.is_empty() {
+        builder.push("NOT EXISTS (SELECT 1 FROM todo_labels WHERE todo_labels.todo_id = todos.id)");
+        return;
+    }
+
+    let matching = "FROM todo_labels JOIN labels ON labels.id = todo_labels.label_id WHERE todo_labels.todo_id = todos.id AND lower(labels.name) = ANY(";
+    if match_all {
+        builder
+            .push(format!(
+                "(SELECT COUNT(DISTINCT lower(labels.name)) {matching}"
+            ))
+            .push_bind(names.to_vec())
+            .push(")) = ")
+            .push_bind(names.len() as i64);
+    } else {
+        builder
+            .push(format!("EXISTS (SELECT 1 {matching}"))
+            .push_bind(names.to_vec())
+            .push("))");
+    }
+}
+
+pub fn push_keyset(builder: &mut QueryBuilder<'_, Postgres>, sort: TodoSort, cursor: TodoCursor) {
+    let columns = sort.key_columns();
+    builder.push(" AND (");
+    for (expression, _) in columns {
+        builder.push(*expression).push(", ");
+    }
+    builder.push("todos.id) ");
+    builder.push(if sort.descending() { "< (" } else { "> (" });
+    for ((_, sql_type), value) in columns.iter().zip(cursor.values) {
+        builder.push("CAST(");
+        builder.push_bind(value);
+        builder.push(format!(" AS {sql_type}), "));
+    }
+    builder.push_bind(cursor.id).push(")");
+}
+
+pub fn push_order_by(builder: &mut QueryBuilder<'_, Postgres>, sort: TodoSort) {
+    let direction = if sort.descending() { "DESC" } else { "ASC" };
+    builder.push(" ORDER BY ");
+    for (expression, _) in sort.key_columns() {
+        builder.push(format!("{expression} {direction}, "));
+    }
+    builder.push(format!("todos.id {direction}"));
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn todo(status: &str, position: f64) -> TodoResponse {
+        TodoResponse {
+            id: Uuid::new_v4(),
+            reporter: "owner@example.com".to_string(),
+            reporter_id: Uuid::new_v4(),
+            reporter_email: "owner@example.com".to_string(),
+            assignee_id: None,
+            assignee_email: None,
+            title: "Write tests".to_string(),
+            description: String::new(),
+            description_html: String::new(),
+            description_excerpt: String::new(),
+            completed: false,
+            status: status.to_string(),
+            position,
+            priority: DEFAULT_PRIORITY.to_string(),
+            estimate: None,
+            start_at: None,
+            due_at: None,
+            series_id: None,
+            recurrence: None,
+            parent_id: None,
+            subtask_count: 0,
+            subtasks_done: 0,
+            blocked_by: sqlx::types::Json(Vec::new()),
+            labels: sqlx::types::Json(Vec::new()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn label_names_are_lowercased_and_deduplicated() {
+        assert_eq!(
+            parse_label_names(" Bug, urgent ,bug,,"),
+            vec!["bug".to_string(), "urgent".to_string()]
+        );
+        assert!(parse_label_names(" , ").is_empty());
+    }
+
+    #[test]
+    fn label_conditions_match_any_or_all() {
+        let names = vec!["bug".to_string(), "urgent".to_string()];
+
+        let mut any = QueryBuilder::<Postgres>::new("");
+        push_labels(&mut any, &names, false);
+        assert!(any.sql().starts_with("EXISTS (SELECT 1 FROM todo_labels"));
+
+        let mut all = QueryBuilder::<Postgres>::new("");
+        push_labels(&mut all, &names, true);
+        assert!(all.sql().ends_with("= ANY($1)) = $2"));
+
+        let mut none = QueryBuilder::<Postgres>::new("");
+        push_labels(&mut none, &[], false);
+        assert!(none.sql().starts_with("NOT EXISTS"));
+    }
+
+    #[test]
+    fn sort_parse_accepts_whitelisted_values_only() {
+        assert_eq!(TodoSort::parse(None).expect("sort"), TodoSort::Board);
+        assert_eq!(
+            TodoSort::parse(Some("-updated_at")).expect("sort"),
+            TodoSort::UpdatedDesc
+        );
+        assert!(matches!(
+            TodoSort::parse(Some("title; DROP TABLE todos")),
+            Err(AppError::BadRequest(msg)) if msg.contains("unsupported sort")
+        ));
+    }
+
+    #[test]
+    fn render_highlight_escapes_text_and_marks_matches() {
+        let raw = format!(
+            "<b>{HIGHLIGHT_START}Hoàn{HIGHLIGHT_STOP} {HIGHLIGHT_START}thành{HIGHLIGHT_STOP} & co"
+        );
+
+        assert_eq!(
+            render_highlight(&raw),
+            "&lt;b&gt;<mark>Hoàn</mark> <mark>thành</mark> &amp; co"
+        );
+    }
+
+    #[test]
+    fn render_highlight_balances_stray_markers() {
+        let raw = format!("{HIGHLIGHT_STOP}a {HIGHLIGHT_START}b {HIGHLIGHT_START}c");
+
+        assert_eq!(render_highlight(&raw), "a <mark>b c</mark>");
+    }
+
+    #[test]
+    fn status_rank_follows_board_columns() {
+        assert_eq!(status_rank("todo"), 1);
+        assert_eq!(status_rank("failed"), 7);
+        assert_eq!(status_rank("unknown"), 8);
+    }
+
+    #[test]
+    fn cursor_round_trips_for_matching_sort() {
+        let last = todo("in_progress", 4.0);
+        let encoded = encode_cursor(TodoSort::Board, &last);
+        let decoded = decode_cursor(TodoSort::Board, &encoded).expect("cursor");
+
+        assert_eq!(decoded.id, last.id);
+        assert_eq!(decoded.values, vec!["3".to_string(), "4".to_string()]);
+    }
+
+    #[test]
+    fn cursor_rejects_other_sort_and_garbage() {
+        let encoded = encode_cursor(TodoSort::CreatedAsc, &todo("todo", 0.0));
+
+        assert!(decode_cursor(TodoSort::UpdatedDesc, &encoded).is_err());
+        assert!(decode_cursor(TodoSort::Board, "not-a-cursor").is_err());
+    }
+
+    #[test]
+    fn due_sort_puts_missing_due_dates_last() {
+        let last = todo("todo", 0.0);
+        let cursor = decode_cursor(TodoSort::DueDesc, &encode_cursor(TodoSort::DueDesc, &last))
+            .expect("cursor");
+
+        assert_eq!(cursor.values, vec!["-infinity".to_string()]);
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT 1 FROM todos WHERE TRUE");
+        push_order_by(&mut builder, TodoSort::DueAsc);
+
+        assert_eq!(
+            builder.sql(),
+            "SELECT 1 FROM todos WHERE TRUE ORDER BY COALESCE(todos.due_at, 'infinity'::timestamptz) ASC, todos.id ASC"
+        );
+    }
+
+    #[test]
+    fn priority_sort_falls_back_to_board_order() {
+        let mut last = todo("in_progress", 2.5);
+        last.priority = "high".to_string();
+        let cursor = decode_cursor(
+            TodoSort::PriorityAsc,
+            &encode_cursor(TodoSort::PriorityAsc, &last),
+        )
+        .expect("cursor");
+
+        assert_eq!(cursor.values, vec!["2", "3", "2.5"]);
+        assert_eq!(priority_rank("urgent"), 1);
+        assert_eq!(priority_rank("unknown"), 5);
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT 1 FROM todos WHERE TRUE");
+        push_order_by(&mut builder, TodoSort::PriorityAsc);
+
+        assert_eq!(
+            builder.sql(),
+            "SELECT 1 FROM todos WHERE TRUE ORDER BY todos.priority_rank ASC, todos.status_rank ASC, todos.position ASC, todos.id ASC"
+        );
+    }
+
+    #[test]
+    fn page_size_is_bounded() {
+        assert_eq!(resolve_page_size(None).expect("limit"), 100);
+        assert_eq!(resolve_page_size(Some(500)).expect("limit"), 500);
+        assert!(resolve_page_size(Some(0)).is_err());
+        assert!(resolve_page_size(Some(501)).is_err());
+        assert_eq!(resolve_search_limit(None).expect("limit"), 20);
+        assert!(resolve_search_limit(Some(101)).is_err());
+    }
+
+    #[test]
+    fn keyset_and_order_follow_sort_direction() {
+        let last = todo("todo", 0.0);
+        let cursor = decode_cursor(
+            TodoSort::UpdatedDesc,
+            &encode_cursor(TodoSort::UpdatedDesc, &last),
+        )
+        .expect("cursor");
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT 1 FROM todos WHERE TRUE");
+        push_keyset(&mut builder, TodoSort::UpdatedDesc, cursor);
+        push_order_by(&mut builder, TodoSort::UpdatedDesc);
+
+        assert_eq!(
+            builder.sql(),
+            "SELECT 1 FROM todos WHERE TRUE AND (todos.updated_at, todos.id) < (CAST($1 AS timestamptz), $2) ORDER BY todos.updated_at DESC, todos.id DESC"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::error::AppError;
+
+/// Due-date windows evaluated in the user's time zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueWindow {
+    /// Not completed and due before now.
+    Overdue,
+    /// Due between local midnight today and local midnight tomorrow.
+    Today,
+    /// Due between local midnight on Monday and the following Monday.
+    ThisWeek,
+}
+
+impl DueWindow {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "overdue" => Some(Self::Overdue),
+            "today" => Some(Self::Today),
+            "this_week" | "week" => Some(Self::ThisWeek),
+            _ => None,
+        }
+    }
+
+    /// Half-open `[start, end)` range of `due_at` values in the window.
+    pub fn bounds(
+        self,
+        now: DateTime<Utc>,
+        time_zone: Tz,
+    ) -> (Option<DateTime<Utc>>, DateTime<Utc>) {
+        let today = now.with_timezone(&time_zone).date_naive();
+        match self {
+            Self::Overdue => (None, now),
+            Self::Today => (
+                Some(local_day_start(time_zone, today)),
+                local_day_start(time_zone, today + Duration::days(1)),
+            ),
+            Self::ThisWeek => {
+                let monday =
+                    today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+                (
+                    Some(local_day_start(time_zone, monday)),
+                    local_day_start(time_zone, monday + Duration::days(7)),
+                )
+            }
+        }
+    }
+}
+
+pub fn parse_time_zone(raw: &str) -> Result<Tz, AppError> {
+    raw.trim()
+        .parse::<Tz>()
+        .map_err(|_| AppError::BadRequest(format!("unknown time zone: {}", raw.trim())))
+}
+
+/// The first instant of `date` in `time_zone`. When a DST jump skips local
+/// midnight the day starts at the first valid local time after it.
+pub fn local_day_start(time_zone: Tz, date: NaiveDate) -> DateTime<Utc> {
+    let mut local = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    for _ in 0..4 {
+        match time_zone.from_local_datetime(&local) {
+            LocalResult::Single(start) | LocalResult::Ambiguous(start, _) => {
+                return start.with_timezone(&Utc);
+            }
+            LocalResult::None => local += Duration::minutes(30),
+        }
+    }
+    local.and_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("timestamp")
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn today_follows_the_user_time_zone() {
+        // 2024-05-01 20:00 UTC is already 2024-05-02 03:00 in Ho Chi Minh City.
+        let now = utc("2024-05-01T20:00:00Z");
+
+        let (start, end) = DueWindow::Today.bounds(now, chrono_tz::Asia::Ho_Chi_Minh);
+
+        assert_eq!(start, Some(utc("2024-05-01T17:00:00Z")));
+        assert_eq!(end, utc("2024-05-02T17:00:00Z"));
+    }
+
+    #[test]
+    fn this_week_starts_on_local_monday() {
+        // Wednesday 2024-05-08 in New York.
+        let now = utc("2024-05-08T15:00:00Z");
+
+        let (start, end) = DueWindow::ThisWeek.bounds(now, chrono_tz::America::New_York);
+
+        assert_eq!(start, Some(utc("2024-05-06T04:00:00Z")));
+        assert_eq!(end, utc("2024-05-13T04:00:00Z"));
+    }
+
+    #[test]
+    fn overdue_ends_now() {
+        let now = utc("2024-05-08T15:00:00Z");
+
+        assert_eq!(DueWindow::Overdue.bounds(now, Tz::UTC), (None, now));
+    }
+
+    #[test]
+    fn day_start_skips_missing_midnight() {
+        // Santiago skipped 00:00-01:00 on 2024-09-08.
+        let start = local_day_start(
+            chrono_tz::America::Santiago,
+            NaiveDate::from_ymd_opt(2024, 9, 8).expect("date"),
+        );
+
+        assert_eq!(start, utc("2024-09-08T04:00:00Z"));
+    }
+
+    #[test]
+    fn parse_time_zone_accepts_iana_names_only() {
+        assert_eq!(
+            parse_time_zone(" Asia/Ho_Chi_Minh ").expect("tz"),
+            chrono_tz::Asia::Ho_Chi_Minh
+        );
+        assert!(parse_time_zone("GMT+7 please").is_err());
+        assert_eq!(DueWindow::parse("This_Week"), Some(DueWindow::ThisWeek));
+        assert_eq!(DueWindow::parse("tomorrow"), None);
+    }
+}
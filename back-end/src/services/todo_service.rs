@@ -1,23 +1,30 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgExecutor, Postgres, QueryBuilder};
 use uuid::Uuid;
 
 use crate::{
     error::AppError,
     models::todo::{
-        CreateTodoRequest, ReorderTodosRequest, TodoRealtimeEvent, TodoResponse, UpdateTodoRequest,
+        CreateTodoRequest, ListTodosQuery, MoveTodoRequest, PatchTodoRequest, ReorderTodosRequest,
+        SearchTodosQuery, TodoColumnSummary, TodoPage, TodoRealtimeEvent, TodoResponse,
+        TodoSearchResult,
+    },
+    services::{
+        history_service, link_service,
+        markdown_service::{self, RenderedDescription},
+        position_service::{self, Placement},
+        recurrence_service, subtask_service,
+        todo_filter::{FilterContext, TodoFilter},
+        todo_query::{self, DEFAULT_PRIORITY, TODO_PRIORITIES, TODO_STATUSES, TodoSort},
+        todo_schedule::DueWindow,
+        undo_service::{self, UndoStep},
+        user_service,
     },
     state::AppState,
 };
 
-const TODO_STATUSES: [&str; 7] = [
-    "todo",
-    "planned",
-    "in_progress",
-    "fixing",
-    "waiting_test",
-    "done",
-    "failed",
-];
-
 fn normalize_title(title: &str) -> Result<String, AppError> {
     let trimmed = title.trim();
     if trimmed.is_empty() {
@@ -27,7 +34,7 @@ fn normalize_title(title: &str) -> Result<String, AppError> {
     Ok(trimmed.to_string())
 }
 
-fn normalize_status(status: &str) -> Result<String, AppError> {
+pub(crate) fn normalize_status(status: &str) -> Result<String, AppError> {
     let trimmed = status.trim().to_lowercase();
     if TODO_STATUSES.contains(&trimmed.as_str()) {
         return Ok(trimmed);
@@ -36,17 +43,74 @@ fn normalize_status(status: &str) -> Result<String, AppError> {
     Err(AppError::BadRequest("invalid status".to_string()))
 }
 
-fn ensure_update_payload(payload: &UpdateTodoRequest) -> Result<Option<String>, AppError> {
+fn normalize_priority(priority: &str) -> Result<String, AppError> {
+    let trimmed = priority.trim().to_lowercase();
+    if TODO_PRIORITIES.contains(&trimmed.as_str()) {
+        return Ok(trimmed);
+    }
+
+    Err(AppError::BadRequest("invalid priority".to_string()))
+}
+
+fn ensure_estimate(estimate: f64) -> Result<f64, AppError> {
+    if estimate.is_finite() && (0.0..=1000.0).contains(&estimate) {
+        return Ok(estimate);
+    }
+
+    Err(AppError::BadRequest(
+        "estimate must be between 0 and 1000".to_string(),
+    ))
+}
+
+fn parse_list_filter(
+    raw: Option<&str>,
+    normalize: fn(&str) -> Result<String, AppError>,
+) -> Result<Vec<String>, AppError> {
+    let Some(raw) = raw else {
+        return Ok(Vec::new());
+    };
+
+    raw.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(normalize)
+        .collect()
+}
+
+fn parse_status_filter(raw: Option<&str>) -> Result<Vec<String>, AppError> {
+    parse_list_filter(raw, normalize_status)
+}
+
+fn parse_priority_filter(raw: Option<&str>) -> Result<Vec<String>, AppError> {
+    parse_list_filter(raw, normalize_priority)
+}
+
+/// Rejects `null` for a field that cannot be cleared.
+fn required<T>(field: &str, value: Option<Option<T>>) -> Result<Option<T>, AppError> {
+    match value {
+        Some(None) => Err(AppError::BadRequest(format!("{field} cannot be null"))),
+        Some(Some(value)) => Ok(Some(value)),
+        None => Ok(None),
+    }
+}
+
+fn ensure_update_payload(payload: &PatchTodoRequest) -> Result<Option<String>, AppError> {
     if payload.title.is_none()
+        && payload.description.is_none()
         && payload.completed.is_none()
         && payload.status.is_none()
         && payload.position.is_none()
         && payload.assignee_id.is_none()
+        && payload.priority.is_none()
+        && payload.estimate.is_none()
+        && payload.start_at.is_none()
+        && payload.due_at.is_none()
+        && payload.parent_id.is_none()
     {
         return Err(AppError::BadRequest("nothing to update".to_string()));
     }
 
-    let normalized_title = match &payload.title {
+    let normalized_title = match required("title", payload.title.as_ref().map(Option::as_ref))? {
         Some(title) => Some(normalize_title(title)?),
         None => None,
     };
@@ -54,7 +118,41 @@ fn ensure_update_payload(payload: &UpdateTodoRequest) -> Result<Option<String>,
     Ok(normalized_title)
 }
 
-async fn ensure_user_exists(state: &AppState, user_id: Uuid) -> Result<(), AppError> {
+fn ensure_schedule(
+    start_at: Option<DateTime<Utc>>,
+    due_at: Option<DateTime<Utc>>,
+) -> Result<(), AppError> {
+    if let (Some(start_at), Some(due_at)) = (start_at, due_at) {
+        if start_at > due_at {
+            return Err(schedule_error());
+        }
+    }
+    Ok(())
+}
+
+fn schedule_error() -> AppError {
+    AppError::BadRequest("start_at must not be after due_at".to_string())
+}
+
+fn map_write_violation(err: sqlx::Error) -> AppError {
+    if let sqlx::Error::Database(db_err) = &err {
+        if db_err.constraint() == Some("todos_start_before_due_check") {
+            return schedule_error();
+        }
+    }
+    if position_service::is_position_conflict(&err) {
+        return position_service::position_conflict();
+    }
+    AppError::from(err)
+}
+
+pub(crate) fn snapshot_uuid(snapshot: &serde_json::Value, field: &str) -> Option<Uuid> {
+    snapshot[field]
+        .as_str()
+        .and_then(|value| value.parse::<Uuid>().ok())
+}
+
+pub(crate) async fn ensure_user_exists(state: &AppState, user_id: Uuid) -> Result<(), AppError> {
     let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
         .bind(user_id)
         .fetch_one(&state.db)
@@ -67,7 +165,7 @@ async fn ensure_user_exists(state: &AppState, user_id: Uuid) -> Result<(), AppEr
     Ok(())
 }
 
-async fn broadcast_todo_event(
+pub(crate) async fn broadcast_todo_event(
     state: &AppState,
     actor_id: Uuid,
     event: &str,
@@ -86,6 +184,11 @@ async fn broadcast_todo_event(
         event: event.to_string(),
         todo: todo.cloned(),
         todo_id,
+        reminder: None,
+        label: None,
+        comment: None,
+        attachment: None,
+        todos: None,
     };
 
     if let Ok(message) = serde_json::to_string(&payload) {
@@ -96,15 +199,241 @@ async fn broadcast_todo_event(
     }
 }
 
-pub async fn list_todos(state: &AppState, user_id: Uuid) -> Result<Vec<TodoResponse>, AppError> {
-    let todos = sqlx::query_as::<_, TodoResponse>(
-        "SELECT todos.id, reporter.email AS reporter, todos.reporter_id, reporter.email AS reporter_email, todos.assignee_id, assignee.email AS assignee_email, todos.title, todos.completed, todos.status, todos.position, todos.created_at, todos.updated_at FROM todos JOIN users reporter ON reporter.id = todos.reporter_id LEFT JOIN users assignee ON assignee.id = todos.assignee_id WHERE todos.reporter_id = $1 OR todos.assignee_id = $1 ORDER BY CASE todos.status WHEN 'todo' THEN 1 WHEN 'planned' THEN 2 WHEN 'in_progress' THEN 3 WHEN 'fixing' THEN 4 WHEN 'waiting_test' THEN 5 WHEN 'done' THEN 6 WHEN 'failed' THEN 7 ELSE 8 END, todos.position ASC, todos.updated_at DESC",
-    )
-    .bind(user_id)
-    .fetch_all(&state.db)
-    .await?;
+/// Parsed `GET /todos` filters, shared by the listing and the column summary.
+struct ListConditions {
+    statuses: Vec<String>,
+    priorities: Vec<String>,
+    due: Option<DueWindow>,
+    labels: Option<Vec<String>>,
+    match_all_labels: bool,
+    filter: Option<TodoFilter>,
+    context: FilterContext,
+}
+
+impl ListConditions {
+    async fn parse(
+        state: &AppState,
+        user_id: Uuid,
+        query: &ListTodosQuery,
+    ) -> Result<Self, AppError> {
+        let filter = query.filter.as_deref().map(TodoFilter::parse).transpose()?;
+        let context = FilterContext {
+            user_id,
+            now: Utc::now(),
+            time_zone: user_service::time_zone(state, user_id).await?,
+        };
+        let statuses = parse_status_filter(query.status.as_deref())?;
+        let priorities = parse_priority_filter(query.priority.as_deref())?;
+        let due = query
+            .due
+            .as_deref()
+            .map(|raw| {
+                DueWindow::parse(raw).ok_or_else(|| {
+                    AppError::BadRequest("due must be one of overdue, today, this_week".to_string())
+                })
+            })
+            .transpose()?;
+        let labels = query
+            .label
+            .as_deref()
+            .map(todo_query::parse_label_names)
+            .filter(|names| !names.is_empty());
+        let match_all_labels = match query.label_match.as_deref().map(str::trim) {
+            None | Some("any") => false,
+            Some("all") => true,
+            Some(_) => {
+                return Err(AppError::BadRequest(
+                    "label_match must be any or all".to_string(),
+                ));
+            }
+        };
+
+        Ok(Self {
+            statuses,
+            priorities,
+            due,
+            labels,
+            match_all_labels,
+            filter,
+            context,
+        })
+    }
+
+    /// Appends the visibility check and every filter to a `SELECT ... FROM todos`.
+    fn push(
+        &self,
+        builder: &mut QueryBuilder<'_, Postgres>,
+        query: &ListTodosQuery,
+        saved_filter: Option<&TodoFilter>,
+    ) {
+        todo_query::push_visibility(builder, self.context.user_id);
+        if !self.statuses.is_empty() {
+            builder
+                .push(" AND todos.status = ANY(")
+                .push_bind(self.statuses.clone())
+                .push(")");
+        }
+        if !self.priorities.is_empty() {
+            builder
+                .push(" AND todos.priority = ANY(")
+                .push_bind(self.priorities.clone())
+                .push(")");
+        }
+        if let Some(assignee_id) = query.assignee_id {
+            builder
+                .push(" AND todos.assignee_id = ")
+                .push_bind(assignee_id);
+        }
+        if let Some(reporter_id) = query.reporter_id {
+            builder
+                .push(" AND todos.reporter_id = ")
+                .push_bind(reporter_id);
+        }
+        if let Some(completed) = query.completed {
+            builder.push(" AND todos.completed = ").push_bind(completed);
+        }
+        if let Some(created_after) = query.created_after {
+            builder
+                .push(" AND todos.created_at >= ")
+                .push_bind(created_after);
+        }
+        if let Some(created_before) = query.created_before {
+            builder
+                .push(" AND todos.created_at < ")
+                .push_bind(created_before);
+        }
+        if let Some(updated_after) = query.updated_after {
+            builder
+                .push(" AND todos.updated_at >= ")
+                .push_bind(updated_after);
+        }
+        if let Some(updated_before) = query.updated_before {
+            builder
+                .push(" AND todos.updated_at < ")
+                .push_bind(updated_before);
+        }
+        if let Some(window) = self.due {
+            builder.push(" AND (");
+            todo_query::push_due_window(builder, window, self.context.now, self.context.time_zone);
+            builder.push(")");
+        }
+        if let Some(labels) = &self.labels {
+            builder.push(" AND (");
+            todo_query::push_labels(builder, labels, self.match_all_labels);
+            builder.push(")");
+        }
+        for filter in saved_filter.into_iter().chain(self.filter.as_ref()) {
+            filter.push_conditions(builder, self.context);
+        }
+    }
+}
+
+pub async fn list_todos(
+    state: &AppState,
+    user_id: Uuid,
+    query: ListTodosQuery,
+    saved_filter: Option<&TodoFilter>,
+) -> Result<TodoPage, AppError> {
+    let sort = TodoSort::parse(query.sort.as_deref())?;
+    let conditions = ListConditions::parse(state, user_id, &query).await?;
+    let limit = todo_query::resolve_page_size(query.limit)?;
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(|raw| todo_query::decode_cursor(sort, raw))
+        .transpose()?;
+
+    let mut builder = QueryBuilder::<Postgres>::new(todo_query::select_todos());
+    conditions.push(&mut builder, &query, saved_filter);
+    if let Some(cursor) = cursor {
+        todo_query::push_keyset(&mut builder, sort, cursor);
+    }
+    todo_query::push_order_by(&mut builder, sort);
+    builder.push(" LIMIT ").push_bind(limit as i64 + 1);
+
+    let mut items = builder
+        .build_query_as::<TodoResponse>()
+        .fetch_all(&state.db)
+        .await?;
+
+    let next_cursor = if items.len() > limit {
+        items.truncate(limit);
+        items
+            .last()
+            .map(|todo| todo_query::encode_cursor(sort, todo))
+    } else {
+        None
+    };
+
+    Ok(TodoPage { items, next_cursor })
+}
+
+/// Todo count and estimate total per status column for the todos that
+/// `list_todos` would return. Every column is present, in board order.
+pub async fn summarize_todos(
+    state: &AppState,
+    user_id: Uuid,
+    query: ListTodosQuery,
+) -> Result<Vec<TodoColumnSummary>, AppError> {
+    let conditions = ListConditions::parse(state, user_id, &query).await?;
+
+    let mut builder = QueryBuilder::<Postgres>::new(
+        "SELECT todos.status, COUNT(*) AS todo_count, COUNT(todos.estimate) AS estimated_count, COALESCE(SUM(todos.estimate), 0) AS estimate_total FROM todos JOIN users reporter ON reporter.id = todos.reporter_id LEFT JOIN users assignee ON assignee.id = todos.assignee_id",
+    );
+    conditions.push(&mut builder, &query, None);
+    builder.push(" GROUP BY todos.status");
+
+    let rows = builder
+        .build_query_as::<TodoColumnSummary>()
+        .fetch_all(&state.db)
+        .await?;
+
+    Ok(TODO_STATUSES
+        .iter()
+        .map(|status| {
+            rows.iter()
+                .find(|row| row.status == *status)
+                .cloned()
+                .unwrap_or_else(|| TodoColumnSummary::empty(status))
+        })
+        .collect())
+}
+
+pub async fn search_todos(
+    state: &AppState,
+    user_id: Uuid,
+    query: SearchTodosQuery,
+) -> Result<Vec<TodoSearchResult>, AppError> {
+    let text = query.q.trim();
+    if text.is_empty() {
+        return Err(AppError::BadRequest("q is required".to_string()));
+    }
+    let limit = todo_query::resolve_search_limit(query.limit)?;
 
-    Ok(todos)
+    let mut builder = QueryBuilder::<Postgres>::new("SELECT ");
+    builder
+        .push(todo_query::TODO_COLUMNS)
+        .push(", ts_rank(todos.search_vector, search.query) AS rank, ts_headline('todo_search', todos.title || E'\\n' || todos.description_excerpt, search.query, ")
+        .push_bind(todo_query::headline_options())
+        .push(") AS highlight FROM todos ")
+        .push(todo_query::TODO_JOINS)
+        .push(" CROSS JOIN websearch_to_tsquery('todo_search', ")
+        .push_bind(text.to_string())
+        .push(") AS search(query)");
+    todo_query::push_visibility(&mut builder, user_id);
+    builder
+        .push(" AND todos.search_vector @@ search.query ORDER BY rank DESC, todos.updated_at DESC, todos.id LIMIT ")
+        .push_bind(limit as i64);
+
+    let mut results = builder
+        .build_query_as::<TodoSearchResult>()
+        .fetch_all(&state.db)
+        .await?;
+    for result in &mut results {
+        result.highlight = todo_query::render_highlight(&result.highlight);
+    }
+
+    Ok(results)
 }
 
 pub async fn create_todo(
@@ -113,10 +442,20 @@ pub async fn create_todo(
     payload: CreateTodoRequest,
 ) -> Result<TodoResponse, AppError> {
     let title = normalize_title(&payload.title)?;
+    let description = match payload.description.as_deref() {
+        Some(description) => markdown_service::render_description(description)?,
+        None => RenderedDescription::default(),
+    };
     let status = match payload.status {
         Some(status) => normalize_status(&status)?,
         None => "todo".to_string(),
     };
+    let priority = match payload.priority {
+        Some(priority) => normalize_priority(&priority)?,
+        None => DEFAULT_PRIORITY.to_string(),
+    };
+    let estimate = payload.estimate.map(ensure_estimate).transpose()?;
+    ensure_schedule(payload.start_at, payload.due_at)?;
 
     let assignee_id = match payload.assignee_id {
         Some(assignee_id) => {
@@ -125,29 +464,65 @@ pub async fn create_todo(
         }
         None => Some(user_id),
     };
-
-    let position: i32 = sqlx::query_scalar(
-        "SELECT COALESCE(MAX(position), -1) + 1 FROM todos WHERE assignee_id = $1 AND status = $2",
-    )
-    .bind(assignee_id)
-    .bind(&status)
-    .fetch_one(&state.db)
-    .await?;
+    if let Some(parent_id) = payload.parent_id {
+        subtask_service::ensure_parent(state, user_id, None, parent_id).await?;
+    }
 
     let completed = matches!(status.as_str(), "done" | "failed");
 
-    let todo = sqlx::query_as::<_, TodoResponse>(
-        "INSERT INTO todos (id, reporter_id, assignee_id, title, completed, status, position) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id, (SELECT email FROM users WHERE id = reporter_id) AS reporter, reporter_id, (SELECT email FROM users WHERE id = reporter_id) AS reporter_email, assignee_id, (SELECT email FROM users WHERE id = assignee_id) AS assignee_email, title, completed, status, position, created_at, updated_at",
-    )
+    let recurrence = match payload.recurrence.as_deref() {
+        Some(raw) => {
+            let due_at = payload.due_at.ok_or_else(|| {
+                AppError::BadRequest("invalid recurrence: the todo needs a due date".to_string())
+            })?;
+            let time_zone = user_service::time_zone(state, user_id).await?;
+            let rule = recurrence_service::normalize_rule(raw, due_at, time_zone)?;
+            Some((rule, due_at, time_zone))
+        }
+        None => None,
+    };
+
+    let mut tx = state.db.begin().await?;
+    let position = position_service::end_position(&mut tx, assignee_id, &status).await?;
+    let series_id = match recurrence {
+        Some((rule, due_at, time_zone)) => Some(
+            recurrence_service::create_series(&mut tx, user_id, &rule, due_at, time_zone).await?,
+        ),
+        None => None,
+    };
+
+    let todo = sqlx::query_as::<_, TodoResponse>(&format!(
+        "WITH written AS (INSERT INTO todos (id, reporter_id, assignee_id, title, description, description_html, description_excerpt, completed, status, position, start_at, due_at, series_id, parent_id, priority, estimate) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16) RETURNING *) {}",
+        todo_query::select_todos_from("written todos")
+    ))
     .bind(Uuid::new_v4())
     .bind(user_id)
     .bind(assignee_id)
     .bind(title)
+    .bind(description.markdown)
+    .bind(description.html)
+    .bind(description.excerpt)
     .bind(completed)
     .bind(&status)
     .bind(position)
-    .fetch_one(&state.db)
+    .bind(payload.start_at)
+    .bind(payload.due_at)
+    .bind(series_id)
+    .bind(payload.parent_id)
+    .bind(priority)
+    .bind(estimate)
+    .fetch_one(&mut *tx)
+    .await?;
+    let created = history_service::snapshot(&mut *tx, todo.id).await?;
+    history_service::record(
+        &mut *tx,
+        todo.id,
+        Some(user_id),
+        "created",
+        history_service::diff(None, Some(&created)),
+    )
     .await?;
+    tx.commit().await?;
 
     broadcast_todo_event(state, user_id, "todo_created", Some(&todo), None).await;
     Ok(todo)
@@ -158,44 +533,104 @@ pub async fn get_todo(
     user_id: Uuid,
     todo_id: Uuid,
 ) -> Result<TodoResponse, AppError> {
-    let todo = sqlx::query_as::<_, TodoResponse>(
-        "SELECT todos.id, reporter.email AS reporter, todos.reporter_id, reporter.email AS reporter_email, todos.assignee_id, assignee.email AS assignee_email, todos.title, todos.completed, todos.status, todos.position, todos.created_at, todos.updated_at FROM todos JOIN users reporter ON reporter.id = todos.reporter_id LEFT JOIN users assignee ON assignee.id = todos.assignee_id WHERE todos.id = $1 AND EXISTS (SELECT 1 FROM todos visible_todos WHERE visible_todos.id = todos.id AND (visible_todos.reporter_id = $2 OR visible_todos.assignee_id = $2))",
-    )
+    find_todo(&state.db, user_id, todo_id).await
+}
+
+pub(crate) async fn find_todo<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<TodoResponse, AppError> {
+    let todo = sqlx::query_as::<_, TodoResponse>(&format!(
+        "{} WHERE todos.id = $1 AND todos.deleted_at IS NULL AND (todos.reporter_id = $2 OR todos.assignee_id = $2)",
+        todo_query::select_todos()
+    ))
     .bind(todo_id)
     .bind(user_id)
-    .fetch_optional(&state.db)
+    .fetch_optional(executor)
     .await?
     .ok_or(AppError::NotFound)?;
 
     Ok(todo)
 }
 
-pub async fn update_todo(
+/// Enforces `If-Match`: when versions are given, the todo (already locked by
+/// the caller) must currently have one of them.
+pub(crate) async fn ensure_version<'e>(
+    executor: impl PgExecutor<'e>,
+    todo_id: Uuid,
+    expected: Option<&[i64]>,
+) -> Result<(), AppError> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let version: i64 = sqlx::query_scalar("SELECT version FROM todos WHERE id = $1")
+        .bind(todo_id)
+        .fetch_one(executor)
+        .await?;
+    if !expected.contains(&version) {
+        return Err(AppError::PreconditionFailed);
+    }
+    Ok(())
+}
+
+/// Whether a write moves a todo into `status` from another one.
+fn enters_status(before: &serde_json::Value, status: &str) -> bool {
+    before["status"].as_str() != Some(status)
+}
+
+/// Whether a write moves a todo of a recurring series into `done` (it was
+/// not done before), which makes its next occurrence due.
+fn completes_series(before: &serde_json::Value, status: &str, series_id: Option<Uuid>) -> bool {
+    status == "done" && enters_status(before, status) && series_id.is_some()
+}
+
+/// The outcome of [`apply_update`], published by the caller after commit.
+pub(crate) struct AppliedUpdate {
+    pub todo: TodoResponse,
+    /// The todo's own step, then the one of its next occurrence, if any.
+    pub steps: Vec<UndoStep>,
+    /// The next occurrence created because the update completed a recurring
+    /// todo.
+    pub next_occurrence: Option<TodoResponse>,
+}
+
+/// Validates and writes an update in the caller's transaction, recording
+/// history but leaving undo, realtime and recurrence to the caller.
+pub(crate) async fn apply_update(
     state: &AppState,
+    conn: &mut PgConnection,
     user_id: Uuid,
     todo_id: Uuid,
-    payload: UpdateTodoRequest,
-) -> Result<TodoResponse, AppError> {
+    payload: PatchTodoRequest,
+    if_match: Option<&[i64]>,
+) -> Result<AppliedUpdate, AppError> {
     let title = ensure_update_payload(&payload)?;
-    if let Some(assignee_id) = payload.assignee_id {
+    let mut completed = required("completed", payload.completed)?;
+    let status = required("status", payload.status)?;
+    let requested_position = required("position", payload.position)?;
+    let priority = required("priority", payload.priority)?;
+    // A cleared description is stored as an empty one.
+    let description = payload
+        .description
+        .map(|description| {
+            markdown_service::render_description(description.as_deref().unwrap_or_default())
+        })
+        .transpose()?;
+    if let Some(Some(assignee_id)) = payload.assignee_id {
         ensure_user_exists(state, assignee_id).await?;
     }
-    let current_assignee_id: Option<Uuid> = sqlx::query_scalar(
-        "SELECT assignee_id FROM todos WHERE id = $1 AND (reporter_id = $2 OR assignee_id = $2)",
-    )
-    .bind(todo_id)
-    .bind(user_id)
-    .fetch_optional(&state.db)
-    .await?
-    .ok_or(AppError::NotFound)?;
-    let mut status = match payload.status {
+    let mut status = match status {
         Some(status) => Some(normalize_status(&status)?),
         None => None,
     };
-    let mut completed = payload.completed;
+    let priority = priority.as_deref().map(normalize_priority).transpose()?;
+    let estimate = payload
+        .estimate
+        .map(|estimate| estimate.map(ensure_estimate).transpose())
+        .transpose()?;
 
     let assignee_id = payload.assignee_id;
-    let position_owner_id = assignee_id.or(current_assignee_id).unwrap_or(user_id);
 
     if completed.is_some() && status.is_none() {
         status = completed.map(|value| {
@@ -207,61 +642,211 @@ pub async fn update_todo(
         });
     }
 
+    if let Some(Some(parent_id)) = payload.parent_id {
+        subtask_service::ensure_parent(state, user_id, Some(todo_id), parent_id).await?;
+    }
+
+    let before = history_service::lock_snapshot(&mut *conn, user_id, todo_id).await?;
+    ensure_version(&mut *conn, todo_id, if_match).await?;
     if let Some(status_value) = status.as_deref() {
         if completed.is_none() {
             completed = Some(matches!(status_value, "done" | "failed"));
         }
+        ensure_status_allowed(state, conn, todo_id, &before, status_value).await?;
     }
 
-    let position = if let (Some(status_value), None) = (status.as_deref(), payload.position) {
-        Some(
-            sqlx::query_scalar(
-                "SELECT COALESCE(MAX(position), -1) + 1 FROM todos WHERE assignee_id = $1 AND status = $2",
+    // A new status, or a new assignee (whose column it joins), puts the todo
+    // at the end of the column unless the request places it.
+    let current_assignee_id = snapshot_uuid(&before, "assignee_id");
+    let reassigned = assignee_id.is_some_and(|assignee_id| assignee_id != current_assignee_id);
+    let position = match requested_position {
+        None if status.is_some() || reassigned => {
+            let column_status = status
+                .clone()
+                .or_else(|| before["status"].as_str().map(str::to_string))
+                .unwrap_or_default();
+            Some(
+                position_service::end_position(
+                    conn,
+                    assignee_id.unwrap_or(current_assignee_id),
+                    &column_status,
+                )
+                .await?,
             )
-            .bind(position_owner_id)
-            .bind(status_value)
-            .fetch_one(&state.db)
-            .await?,
-        )
-    } else {
-        payload.position
+        }
+        requested_position => requested_position,
     };
 
-    let todo = sqlx::query_as::<_, TodoResponse>(
-        "UPDATE todos SET title = COALESCE($1, title), completed = COALESCE($2, completed), status = COALESCE($3, status), position = COALESCE($4, position), assignee_id = COALESCE($5, assignee_id), updated_at = NOW() WHERE id = $6 AND (reporter_id = $7 OR assignee_id = $7) RETURNING id, (SELECT email FROM users WHERE id = reporter_id) AS reporter, reporter_id, (SELECT email FROM users WHERE id = reporter_id) AS reporter_email, assignee_id, (SELECT email FROM users WHERE id = assignee_id) AS assignee_email, title, completed, status, position, created_at, updated_at",
-    )
+    let (description, description_html, description_excerpt) = match description {
+        Some(rendered) => (
+            Some(rendered.markdown),
+            Some(rendered.html),
+            Some(rendered.excerpt),
+        ),
+        None => (None, None, None),
+    };
+
+    let todo = sqlx::query_as::<_, TodoResponse>(&format!(
+        "WITH written AS (UPDATE todos SET title = COALESCE($1, title), completed = COALESCE($2, completed), status = COALESCE($3, status), position = COALESCE($4, position), assignee_id = CASE WHEN $20 THEN $5 ELSE assignee_id END, description = COALESCE($8, description), description_html = COALESCE($9, description_html), description_excerpt = COALESCE($10, description_excerpt), start_at = CASE WHEN $11 THEN $12 ELSE start_at END, due_at = CASE WHEN $13 THEN $14 ELSE due_at END, parent_id = CASE WHEN $15 THEN $16 ELSE parent_id END, priority = COALESCE($17, priority), estimate = CASE WHEN $18 THEN $19 ELSE estimate END, updated_at = NOW() WHERE id = $6 AND deleted_at IS NULL AND (reporter_id = $7 OR assignee_id = $7) RETURNING *) {}",
+        todo_query::select_todos_from("written todos")
+    ))
     .bind(title.as_deref())
     .bind(completed)
     .bind(status.as_deref())
     .bind(position)
-    .bind(assignee_id)
+    .bind(assignee_id.flatten())
     .bind(todo_id)
     .bind(user_id)
-    .fetch_optional(&state.db)
-    .await?
+    .bind(description)
+    .bind(description_html)
+    .bind(description_excerpt)
+    .bind(payload.start_at.is_some())
+    .bind(payload.start_at.flatten())
+    .bind(payload.due_at.is_some())
+    .bind(payload.due_at.flatten())
+    .bind(payload.parent_id.is_some())
+    .bind(payload.parent_id.flatten())
+    .bind(priority)
+    .bind(estimate.is_some())
+    .bind(estimate.flatten())
+    .bind(assignee_id.is_some())
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(map_write_violation)?
     .ok_or(AppError::NotFound)?;
+    if requested_position.is_some() {
+        position_service::queue_if_crowded(conn, todo.assignee_id, &todo.status).await?;
+    }
+    let after = history_service::snapshot(&mut *conn, todo_id).await?;
+    history_service::record(
+        &mut *conn,
+        todo_id,
+        Some(user_id),
+        "updated",
+        history_service::diff(Some(&before), Some(&after)),
+    )
+    .await?;
 
+    let completed_series = completes_series(&before, &todo.status, todo.series_id);
+    let mut steps = vec![UndoStep {
+        todo_id,
+        before,
+        after,
+    }];
+    let mut next_occurrence = None;
+    if completed_series {
+        if let Some((next, step)) =
+            recurrence_service::spawn_next_occurrence(conn, user_id, todo_id).await?
+        {
+            next_occurrence = Some(next);
+            steps.push(step);
+        }
+    }
+    Ok(AppliedUpdate {
+        todo,
+        steps,
+        next_occurrence,
+    })
+}
+
+pub async fn update_todo(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    payload: PatchTodoRequest,
+    if_match: Option<&[i64]>,
+) -> Result<TodoResponse, AppError> {
+    let mut tx = state.db.begin().await?;
+    let applied = apply_update(state, &mut tx, user_id, todo_id, payload, if_match).await?;
+    undo_service::push(&mut tx, user_id, "update", applied.steps).await?;
+    tx.commit().await?;
+
+    let todo = applied.todo;
     broadcast_todo_event(state, user_id, "todo_updated", Some(&todo), None).await;
+    recurrence_service::broadcast_spawned(state, user_id, applied.next_occurrence.as_slice()).await;
     Ok(todo)
 }
 
-pub async fn delete_todo(state: &AppState, user_id: Uuid, todo_id: Uuid) -> Result<(), AppError> {
-    let todo = get_todo(state, user_id, todo_id).await?;
-    let result =
-        sqlx::query("DELETE FROM todos WHERE id = $1 AND (reporter_id = $2 OR assignee_id = $2)")
-            .bind(todo_id)
-            .bind(user_id)
-            .execute(&state.db)
-            .await?;
-
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound);
+/// Moves a todo and its subtasks to the trash in the caller's transaction.
+/// They share one `deleted_at` (the transaction time), which is how a restore
+/// finds them again. Returns the todo as it was and the undo steps.
+pub(crate) async fn apply_delete(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    todo_id: Uuid,
+    if_match: Option<&[i64]>,
+) -> Result<(TodoResponse, Vec<UndoStep>), AppError> {
+    history_service::lock_snapshot(&mut *conn, user_id, todo_id).await?;
+    ensure_version(&mut *conn, todo_id, if_match).await?;
+    let todo = find_todo(&mut *conn, user_id, todo_id).await?;
+    // Subtasks that are already in the trash keep their own timestamp.
+    let trashed = history_service::lock_subtree_snapshots(&mut *conn, todo_id)
+        .await?
+        .into_iter()
+        .filter(|(_, snapshot)| snapshot["deleted_at"].is_null())
+        .collect::<Vec<_>>();
+    let ids = trashed.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    sqlx::query("UPDATE todos SET deleted_at = NOW() WHERE id = ANY($1)")
+        .bind(&ids)
+        .execute(&mut *conn)
+        .await?;
+
+    let mut steps = Vec::with_capacity(trashed.len());
+    for (trashed_id, before) in trashed {
+        let after = history_service::snapshot(&mut *conn, trashed_id).await?;
+        history_service::record(
+            &mut *conn,
+            trashed_id,
+            Some(user_id),
+            "deleted",
+            history_service::diff(Some(&before), Some(&after)),
+        )
+        .await?;
+        steps.push(UndoStep {
+            todo_id: trashed_id,
+            before,
+            after,
+        });
     }
+    Ok((todo, steps))
+}
+
+pub async fn delete_todo(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    if_match: Option<&[i64]>,
+) -> Result<(), AppError> {
+    let mut tx = state.db.begin().await?;
+    let (todo, steps) = apply_delete(&mut tx, user_id, todo_id, if_match).await?;
+    undo_service::push(&mut tx, user_id, "delete", steps).await?;
+    tx.commit().await?;
 
     broadcast_todo_event(state, user_id, "todo_deleted", Some(&todo), Some(todo_id)).await;
     Ok(())
 }
 
+/// Rejects moving a todo into `status` while the configured guards forbid it.
+/// Writes that keep the todo's current status are not moves.
+async fn ensure_status_allowed(
+    state: &AppState,
+    conn: &mut PgConnection,
+    todo_id: Uuid,
+    before: &serde_json::Value,
+    status: &str,
+) -> Result<(), AppError> {
+    let entering = enters_status(before, status);
+    if entering && status == "done" && state.todos.require_subtasks_done {
+        subtask_service::ensure_subtasks_done(&mut *conn, todo_id).await?;
+    }
+    if entering && status == "in_progress" && state.todos.guard_blocked_start {
+        link_service::ensure_not_blocked(&mut *conn, todo_id).await?;
+    }
+    Ok(())
+}
+
+/// Moves several todos in one statement. Positions are checked once all
+/// items are written, so items may trade places.
 pub async fn reorder_todos(
     state: &AppState,
     user_id: Uuid,
@@ -271,36 +856,235 @@ pub async fn reorder_todos(
         return Err(AppError::BadRequest("items is required".to_string()));
     }
 
+    let mut ids = Vec::with_capacity(payload.items.len());
+    let mut statuses = Vec::with_capacity(payload.items.len());
+    let mut positions = Vec::with_capacity(payload.items.len());
+    for item in &payload.items {
+        if ids.contains(&item.id) {
+            return Err(AppError::BadRequest(
+                "each todo can be listed only once".to_string(),
+            ));
+        }
+        ids.push(item.id);
+        statuses.push(normalize_status(&item.status)?);
+        positions.push(item.position);
+    }
+
     let mut tx = state.db.begin().await?;
+    let befores = history_service::lock_snapshots(&mut *tx, user_id, &ids)
+        .await?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+    if befores.len() != ids.len() {
+        return Err(AppError::NotFound);
+    }
+    let series_ids = sqlx::query_as::<_, (Uuid, Uuid)>(
+        "SELECT id, series_id FROM todos WHERE id = ANY($1) AND series_id IS NOT NULL",
+    )
+    .bind(&ids)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .collect::<HashMap<_, _>>();
+    let completed_series = ids
+        .iter()
+        .zip(&statuses)
+        .filter(|(todo_id, status)| {
+            completes_series(
+                &befores[*todo_id],
+                status,
+                series_ids.get(*todo_id).copied(),
+            )
+        })
+        .map(|(todo_id, _)| *todo_id)
+        .collect::<Vec<_>>();
 
-    for item in payload.items {
-        let status = normalize_status(&item.status)?;
-        let completed = matches!(status.as_str(), "done" | "failed");
-        let result = sqlx::query(
-            "UPDATE todos SET status = $1, position = $2, completed = $3, updated_at = NOW() WHERE id = $4 AND (reporter_id = $5 OR assignee_id = $5)",
+    let mut columns = BTreeSet::new();
+    // Completed occurrences spawn their next one at the end of `todo`.
+    for todo_id in &completed_series {
+        columns.insert((snapshot_uuid(&befores[todo_id], "assignee_id"), "todo"));
+    }
+    for (item, status) in payload.items.iter().zip(&statuses) {
+        ensure_version(
+            &mut *tx,
+            item.id,
+            item.version.as_ref().map(std::slice::from_ref),
         )
-        .bind(status)
-        .bind(item.position)
-        .bind(completed)
-        .bind(item.id)
-        .bind(user_id)
-        .execute(&mut *tx)
         .await?;
+        ensure_status_allowed(state, &mut tx, item.id, &befores[&item.id], status).await?;
+        columns.insert((
+            snapshot_uuid(&befores[&item.id], "assignee_id"),
+            status.as_str(),
+        ));
+    }
+    for (assignee_id, status) in &columns {
+        position_service::lock_column(&mut tx, *assignee_id, status).await?;
+    }
 
-        if result.rows_affected() == 0 {
-            return Err(AppError::NotFound);
+    sqlx::query(
+        "UPDATE todos SET status = moved.status, position = moved.position, completed = moved.status IN ('done', 'failed'), updated_at = NOW() FROM UNNEST($1::uuid[], $2::text[], $3::float8[]) AS moved(id, status, position) WHERE todos.id = moved.id",
+    )
+    .bind(&ids)
+    .bind(&statuses)
+    .bind(&positions)
+    .execute(&mut *tx)
+    .await
+    .map_err(map_write_violation)?;
+    for (assignee_id, status) in &columns {
+        position_service::queue_if_crowded(&mut tx, *assignee_id, status).await?;
+    }
+
+    let mut spawned = Vec::new();
+    let mut spawned_steps = Vec::new();
+    for todo_id in completed_series {
+        if let Some((next, step)) =
+            recurrence_service::spawn_next_occurrence(&mut tx, user_id, todo_id).await?
+        {
+            spawned.push(next);
+            spawned_steps.push(step);
         }
     }
 
+    let mut steps = Vec::with_capacity(ids.len() + spawned_steps.len());
+    for (todo_id, after) in history_service::snapshots(&mut *tx, &ids).await? {
+        let before = befores[&todo_id].clone();
+        history_service::record(
+            &mut *tx,
+            todo_id,
+            Some(user_id),
+            "reordered",
+            history_service::diff(Some(&before), Some(&after)),
+        )
+        .await?;
+        steps.push(UndoStep {
+            todo_id,
+            before,
+            after,
+        });
+    }
+
+    steps.extend(spawned_steps);
+    undo_service::push(&mut tx, user_id, "reorder", steps).await?;
     tx.commit().await?;
     broadcast_todo_event(state, user_id, "todo_reordered", None, None).await;
+    recurrence_service::broadcast_spawned(state, user_id, &spawned).await;
     Ok(())
 }
 
+/// Moves one todo next to a neighbour, writing only its own row. The column
+/// is rebalanced first in the rare case the neighbours are too close.
+pub async fn move_todo(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+    payload: MoveTodoRequest,
+    if_match: Option<&[i64]>,
+) -> Result<TodoResponse, AppError> {
+    let placement = match (payload.after_id, payload.before_id) {
+        (Some(after_id), None) => Placement::After(after_id),
+        (None, Some(before_id)) => Placement::Before(before_id),
+        (None, None) => Placement::End,
+        (Some(_), Some(_)) => {
+            return Err(AppError::BadRequest(
+                "give at most one of after_id and before_id".to_string(),
+            ));
+        }
+    };
+    let status = payload
+        .status
+        .as_deref()
+        .map(normalize_status)
+        .transpose()?;
+
+    let mut tx = state.db.begin().await?;
+    let before = history_service::lock_snapshot(&mut *tx, user_id, todo_id).await?;
+    ensure_version(&mut *tx, todo_id, if_match).await?;
+    let status = status
+        .or_else(|| before["status"].as_str().map(str::to_string))
+        .unwrap_or_default();
+    ensure_status_allowed(state, &mut tx, todo_id, &before, &status).await?;
+    let mut placed = position_service::place(
+        &mut tx,
+        user_id,
+        todo_id,
+        snapshot_uuid(&before, "assignee_id"),
+        &status,
+        placement,
+    )
+    .await?;
+
+    sqlx::query(
+        "UPDATE todos SET status = $1, position = $2, completed = $1 IN ('done', 'failed'), updated_at = NOW() WHERE id = $3",
+    )
+    .bind(&status)
+    .bind(placed.position)
+    .bind(todo_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(map_write_violation)?;
+
+    let series_id: Option<Uuid> = sqlx::query_scalar("SELECT series_id FROM todos WHERE id = $1")
+        .bind(todo_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let next_occurrence = if completes_series(&before, &status, series_id) {
+        recurrence_service::spawn_next_occurrence(&mut tx, user_id, todo_id).await?
+    } else {
+        None
+    };
+    let (next_occurrence, spawned_step) = next_occurrence.unzip();
+    let after = history_service::snapshot(&mut *tx, todo_id).await?;
+    history_service::record(
+        &mut *tx,
+        todo_id,
+        Some(user_id),
+        "reordered",
+        history_service::diff(Some(&before), Some(&after)),
+    )
+    .await?;
+    let mut steps = vec![UndoStep {
+        todo_id,
+        before,
+        after,
+    }];
+    steps.extend(spawned_step);
+    undo_service::push(&mut tx, user_id, "reorder", steps).await?;
+    tx.commit().await?;
+
+    let todo = get_todo(state, user_id, todo_id).await?;
+    broadcast_todo_event(state, user_id, "todo_reordered", Some(&todo), None).await;
+    placed
+        .renumbered
+        .retain(|renumbered_id| *renumbered_id != todo_id);
+    position_service::broadcast_renumbered(state, Some(user_id), &placed.renumbered).await;
+    recurrence_service::broadcast_spawned(state, user_id, next_occurrence.as_slice()).await;
+    Ok(todo)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn only_a_transition_to_done_completes_a_series() {
+        let series_id = Some(Uuid::new_v4());
+        let open = serde_json::json!({ "status": "in_progress" });
+        let done = serde_json::json!({ "status": "done" });
+
+        assert!(completes_series(&open, "done", series_id));
+        assert!(!completes_series(&done, "done", series_id));
+        assert!(!completes_series(&open, "done", None));
+        assert!(!completes_series(&open, "failed", series_id));
+    }
+
+    #[test]
+    fn resending_the_current_status_is_not_a_move() {
+        let open = serde_json::json!({ "status": "in_progress" });
+
+        assert!(!enters_status(&open, "in_progress"));
+        assert!(enters_status(&open, "done"));
+    }
+
     #[test]
     fn normalize_title_trims_and_validates() {
         let title = "   Write tests  ";
@@ -334,15 +1118,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_status_filter_normalizes_comma_separated_values() {
+        let statuses = parse_status_filter(Some(" Todo, in_progress ,")).expect("statuses");
+
+        assert_eq!(
+            statuses,
+            vec!["todo".to_string(), "in_progress".to_string()]
+        );
+        assert!(parse_status_filter(None).expect("statuses").is_empty());
+        assert!(parse_status_filter(Some("todo,archived")).is_err());
+    }
+
+    #[test]
+    fn priority_and_estimate_are_validated() {
+        assert_eq!(normalize_priority(" Urgent ").expect("priority"), "urgent");
+        assert!(normalize_priority("asap").is_err());
+        assert_eq!(
+            parse_priority_filter(Some("low,HIGH")).expect("priorities"),
+            vec!["low".to_string(), "high".to_string()]
+        );
+        assert_eq!(ensure_estimate(2.5).expect("estimate"), 2.5);
+        assert!(ensure_estimate(-1.0).is_err());
+        assert!(ensure_estimate(f64::NAN).is_err());
+    }
+
     #[test]
     fn ensure_update_payload_rejects_empty_request() {
-        let payload = UpdateTodoRequest {
-            title: None,
-            completed: None,
-            status: None,
-            position: None,
-            assignee_id: None,
-        };
+        let payload = PatchTodoRequest::default();
 
         let result = ensure_update_payload(&payload);
 
@@ -353,16 +1156,40 @@ mod tests {
 
     #[test]
     fn ensure_update_payload_normalizes_title() {
-        let payload = UpdateTodoRequest {
-            title: Some("   Updated title ".to_string()),
-            completed: Some(true),
-            status: None,
-            position: None,
-            assignee_id: None,
+        let payload = PatchTodoRequest {
+            title: Some(Some("   Updated title ".to_string())),
+            completed: Some(Some(true)),
+            ..PatchTodoRequest::default()
         };
 
         let normalized = ensure_update_payload(&payload).expect("normalized title");
 
         assert_eq!(normalized.as_deref(), Some("Updated title"));
     }
+
+    #[test]
+    fn merge_patch_rejects_null_for_required_fields() {
+        let payload: PatchTodoRequest = serde_json::from_str(r#"{"title":null}"#).expect("payload");
+        let result = ensure_update_payload(&payload);
+        assert!(matches!(result, Err(AppError::BadRequest(msg)) if msg == "title cannot be null"));
+
+        assert!(required::<String>("status", Some(None)).is_err());
+        assert_eq!(
+            required("position", Some(Some(2))).expect("position"),
+            Some(2)
+        );
+        assert_eq!(required::<i32>("position", None).expect("position"), None);
+    }
+
+    #[test]
+    fn merge_patch_distinguishes_null_from_absent() {
+        let payload: PatchTodoRequest =
+            serde_json::from_str(r#"{"assignee_id":null,"due_at":null}"#).expect("payload");
+        assert_eq!(payload.assignee_id, Some(None));
+        assert_eq!(payload.due_at, Some(None));
+        assert_eq!(payload.start_at, None);
+        assert!(ensure_update_payload(&payload).is_ok());
+
+        assert!(serde_json::from_str::<PatchTodoRequest>(r#"{"version":3}"#).is_err());
+    }
 }
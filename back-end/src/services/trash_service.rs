@@ -0,0 +1,205 @@
+use std::time::Duration as StdDuration;
+
+use chrono::{Duration, Utc};
+use sqlx::{Postgres, Transaction};
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::todo::TodoResponse,
+    services::{attachment_service, history_service, position_service, todo_query, todo_service},
+    state::AppState,
+};
+
+const PURGE_BATCH_SIZE: i64 = 100;
+
+/// Trashed todos the user can see, most recently deleted first. Subtasks
+/// deleted together with their parent are listed through the parent only.
+pub async fn list_trash(state: &AppState, user_id: Uuid) -> Result<Vec<TodoResponse>, AppError> {
+    let todos = sqlx::query_as::<_, TodoResponse>(&format!(
+        "{} WHERE todos.deleted_at IS NOT NULL AND (todos.reporter_id = $1 OR todos.assignee_id = $1) AND NOT EXISTS (SELECT 1 FROM todos parent WHERE parent.id = todos.parent_id AND parent.deleted_at = todos.deleted_at) ORDER BY todos.deleted_at DESC, todos.id",
+        todo_query::select_todos()
+    ))
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(todos)
+}
+
+async fn get_trashed_todo(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<TodoResponse, AppError> {
+    let todo = sqlx::query_as::<_, TodoResponse>(&format!(
+        "{} WHERE todos.id = $1 AND todos.deleted_at IS NOT NULL AND (todos.reporter_id = $2 OR todos.assignee_id = $2)",
+        todo_query::select_todos()
+    ))
+    .bind(todo_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(todo)
+}
+
+/// Takes a todo out of the trash together with the subtasks deleted with it.
+/// Each restored todo goes to the end of its status column.
+pub async fn restore_todo(
+    state: &AppState,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<TodoResponse, AppError> {
+    let todo = get_trashed_todo(state, user_id, todo_id).await?;
+    let mut tx = state.db.begin().await?;
+    let subtree = history_service::lock_subtree_snapshots(&mut *tx, todo_id).await?;
+    let deleted_at = subtree
+        .iter()
+        .find(|(id, _)| *id == todo_id)
+        .map(|(_, snapshot)| snapshot["deleted_at"].clone())
+        .filter(|deleted_at| !deleted_at.is_null())
+        .ok_or(AppError::NotFound)?;
+
+    if let Some(parent_id) = todo.parent_id {
+        let parent_trashed: bool =
+            sqlx::query_scalar("SELECT deleted_at IS NOT NULL FROM todos WHERE id = $1")
+                .bind(parent_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        if parent_trashed {
+            return Err(AppError::BadRequest(
+                "restore the parent todo first".to_string(),
+            ));
+        }
+    }
+
+    for (restored_id, before) in subtree {
+        if before["deleted_at"] != deleted_at {
+            continue;
+        }
+        let assignee_id = before["assignee_id"]
+            .as_str()
+            .and_then(|value| value.parse::<Uuid>().ok());
+        let position = position_service::end_position(
+            &mut tx,
+            assignee_id,
+            before["status"].as_str().unwrap_or_default(),
+        )
+        .await?;
+        sqlx::query(
+            "UPDATE todos SET deleted_at = NULL, position = $2, updated_at = NOW() WHERE id = $1",
+        )
+        .bind(restored_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+
+        let after = history_service::snapshot(&mut *tx, restored_id).await?;
+        history_service::record(
+            &mut *tx,
+            restored_id,
+            Some(user_id),
+            "restored",
+            history_service::diff(Some(&before), Some(&after)),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
+    let todo = todo_service::get_todo(state, user_id, todo_id).await?;
+    todo_service::broadcast_todo_event(state, user_id, "todo_restored", Some(&todo), None).await;
+    Ok(todo)
+}
+
+/// Removes a todo and its subtasks for good. The caller holds the row lock.
+async fn purge_subtree(
+    tx: &mut Transaction<'_, Postgres>,
+    todo_id: Uuid,
+    actor_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let removed = history_service::lock_subtree_snapshots(&mut **tx, todo_id).await?;
+    sqlx::query("DELETE FROM todos WHERE id = $1")
+        .bind(todo_id)
+        .execute(&mut **tx)
+        .await?;
+
+    for (removed_id, snapshot) in removed {
+        history_service::record(
+            &mut **tx,
+            removed_id,
+            actor_id,
+            "purged",
+            history_service::diff(Some(&snapshot), None),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn purge_todo(state: &AppState, user_id: Uuid, todo_id: Uuid) -> Result<(), AppError> {
+    let todo = get_trashed_todo(state, user_id, todo_id).await?;
+    let mut tx = state.db.begin().await?;
+    sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM todos WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE",
+    )
+    .bind(todo_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    purge_subtree(&mut tx, todo_id, Some(user_id)).await?;
+    tx.commit().await?;
+
+    // Attachment rows of the todo and its subtasks queued their objects.
+    attachment_service::schedule_cleanup(state);
+    todo_service::broadcast_todo_event(state, user_id, "todo_purged", Some(&todo), Some(todo_id))
+        .await;
+    Ok(())
+}
+
+/// Starts the background loop that purges todos past the retention period.
+pub fn spawn_purge_job(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(StdDuration::from_secs(state.trash.purge_interval_seconds));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match run_purge_once(&state).await {
+                Ok(0) => {}
+                Ok(purged) => tracing::info!(purged, "trashed todos purged"),
+                Err(err) => tracing::error!(error = %err, "trash purge failed"),
+            }
+        }
+    });
+}
+
+/// Purges one batch of expired trash. Subtasks that expire with their parent
+/// are removed through it.
+pub async fn run_purge_once(state: &AppState) -> Result<usize, AppError> {
+    let retention_days = i64::try_from(state.trash.retention_days).unwrap_or(i64::MAX);
+    let cutoff = Duration::try_days(retention_days)
+        .and_then(|retention| Utc::now().checked_sub_signed(retention))
+        .unwrap_or(chrono::DateTime::<Utc>::MIN_UTC);
+
+    let mut tx = state.db.begin().await?;
+    let expired = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM todos WHERE deleted_at < $1 AND NOT EXISTS (SELECT 1 FROM todos parent WHERE parent.id = todos.parent_id AND parent.deleted_at < $1) ORDER BY deleted_at LIMIT $2 FOR UPDATE SKIP LOCKED",
+    )
+    .bind(cutoff)
+    .bind(PURGE_BATCH_SIZE)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for todo_id in &expired {
+        purge_subtree(&mut tx, *todo_id, None).await?;
+    }
+    tx.commit().await?;
+
+    if !expired.is_empty() {
+        attachment_service::schedule_cleanup(state);
+    }
+    Ok(expired.len())
+}
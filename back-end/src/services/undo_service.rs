@@ -0,0 +1,280 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{PgConnection, Postgres, QueryBuilder, types::Json};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::todo::UndoResponse,
+    services::{history_service, markdown_service, position_service, todo_service},
+    state::AppState,
+};
+
+/// Operations kept per user; older ones can no longer be undone.
+const MAX_UNDO_DEPTH: i64 = 20;
+
+/// One todo touched by an operation, as tracked-field snapshots. A `null`
+/// `before` marks a todo the operation created, such as the next occurrence
+/// of a completed recurring todo; undoing removes it.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct UndoStep {
+    pub todo_id: Uuid,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Remembers how to revert an operation, in the transaction that performs
+/// it. Steps that changed nothing are dropped.
+pub(crate) async fn push(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    kind: &str,
+    steps: Vec<UndoStep>,
+) -> Result<(), AppError> {
+    let steps = steps
+        .into_iter()
+        .filter(|step| step.before != step.after)
+        .collect::<Vec<_>>();
+    if steps.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query("INSERT INTO todo_undo_operations (user_id, kind, steps) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(kind)
+        .bind(Json(steps))
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        "DELETE FROM todo_undo_operations WHERE user_id = $1 AND id NOT IN (SELECT id FROM todo_undo_operations WHERE user_id = $1 ORDER BY id DESC LIMIT $2)",
+    )
+    .bind(user_id)
+    .bind(MAX_UNDO_DEPTH)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+fn history_action(kind: &str) -> &'static str {
+    match kind {
+        "reorder" => "reordered",
+        "delete" => "restored",
+        _ => "updated",
+    }
+}
+
+fn changed_since() -> AppError {
+    AppError::Conflict("the todos have changed since; nothing was undone".to_string())
+}
+
+/// Fails when a todo no longer looks the way the operation left it, when a
+/// todo it created has gained subtasks, links, labels, reminders, comments,
+/// watchers or attachments, or when writing it back would put a subtask
+/// under a parent that is gone.
+async fn ensure_unchanged(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    steps: &[UndoStep],
+) -> Result<(), AppError> {
+    for step in steps {
+        let current = history_service::lock_any_snapshot(&mut *conn, user_id, step.todo_id).await?;
+        if current.as_ref() != Some(&step.after) {
+            return Err(changed_since());
+        }
+        if step.before.is_null() {
+            let touched: bool = sqlx::query_scalar(
+                "SELECT EXISTS(SELECT 1 FROM todos WHERE parent_id = $1)
+                    OR EXISTS(SELECT 1 FROM todo_links WHERE source_id = $1 OR target_id = $1)
+                    OR EXISTS(SELECT 1 FROM todo_labels WHERE todo_id = $1)
+                    OR EXISTS(SELECT 1 FROM todo_reminders WHERE todo_id = $1)
+                    OR EXISTS(SELECT 1 FROM todo_comments WHERE todo_id = $1)
+                    OR EXISTS(SELECT 1 FROM todo_watchers WHERE todo_id = $1)
+                    OR EXISTS(SELECT 1 FROM todo_attachments WHERE todo_id = $1)",
+            )
+            .bind(step.todo_id)
+            .fetch_one(&mut *conn)
+            .await?;
+            if touched {
+                return Err(changed_since());
+            }
+        }
+    }
+
+    let live = steps
+        .iter()
+        .filter(|step| !step.before.is_null() && step.before["deleted_at"].is_null())
+        .map(|step| step.todo_id)
+        .collect::<HashSet<_>>();
+    for step in steps.iter().filter(|step| live.contains(&step.todo_id)) {
+        let Some(parent_id) = step.before["parent_id"]
+            .as_str()
+            .and_then(|value| value.parse::<Uuid>().ok())
+        else {
+            continue;
+        };
+        if live.contains(&parent_id) {
+            continue;
+        }
+        let parent_live: Option<bool> =
+            sqlx::query_scalar("SELECT deleted_at IS NULL FROM todos WHERE id = $1")
+                .bind(parent_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+        if parent_live != Some(true) {
+            return Err(changed_since());
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the fields that differ between `after` and `before` back.
+async fn write_back(conn: &mut PgConnection, step: &UndoStep) -> Result<(), AppError> {
+    let fields = history_service::diff(Some(&step.after), Some(&step.before));
+    let mut builder = QueryBuilder::<Postgres>::new("UPDATE todos SET updated_at = NOW()");
+    // Keys are tracked field names, so they are safe to use as columns.
+    for field in fields.keys() {
+        builder.push(format!(", {field} = restored.{field}"));
+    }
+    if fields.contains_key("description") {
+        let rendered = markdown_service::render_description(
+            step.before["description"].as_str().unwrap_or_default(),
+        )?;
+        builder
+            .push(", description_html = ")
+            .push_bind(rendered.html)
+            .push(", description_excerpt = ")
+            .push_bind(rendered.excerpt);
+    }
+    builder
+        .push(" FROM jsonb_populate_record(NULL::todos, ")
+        .push_bind(step.before.clone())
+        .push(") restored WHERE todos.id = ")
+        .push_bind(step.todo_id);
+    builder.build().execute(&mut *conn).await?;
+
+    Ok(())
+}
+
+/// Reverts the user's most recent update, reorder or delete. The operation
+/// is dropped either way, so a conflicting one does not block older ones.
+pub async fn undo_last(state: &AppState, user_id: Uuid) -> Result<UndoResponse, AppError> {
+    let mut tx = state.db.begin().await?;
+    let (operation_id, kind, Json(steps)) = sqlx::query_as::<_, (i64, String, Json<Vec<UndoStep>>)>(
+        "SELECT id, kind, steps FROM todo_undo_operations WHERE user_id = $1 ORDER BY id DESC LIMIT 1 FOR UPDATE",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    sqlx::query("DELETE FROM todo_undo_operations WHERE id = $1")
+        .bind(operation_id)
+        .execute(&mut *tx)
+        .await?;
+
+    if let Err(err) = ensure_unchanged(&mut tx, user_id, &steps).await {
+        tx.commit().await?;
+        return Err(err);
+    }
+    // Steps may trade positions; other todos may have taken one meanwhile,
+    // which only shows once all of them are written.
+    sqlx::query("SET CONSTRAINTS todos_column_position_excl DEFERRED")
+        .execute(&mut *tx)
+        .await?;
+    let mut removed = Vec::new();
+    for step in &steps {
+        if step.before.is_null() {
+            removed.push(todo_service::find_todo(&mut *tx, user_id, step.todo_id).await?);
+            sqlx::query("DELETE FROM todos WHERE id = $1")
+                .bind(step.todo_id)
+                .execute(&mut *tx)
+                .await?;
+            history_service::record(
+                &mut *tx,
+                step.todo_id,
+                Some(user_id),
+                "purged",
+                history_service::diff(Some(&step.after), None),
+            )
+            .await?;
+            continue;
+        }
+        write_back(&mut tx, step).await?;
+        let after = history_service::snapshot(&mut *tx, step.todo_id).await?;
+        history_service::record(
+            &mut *tx,
+            step.todo_id,
+            Some(user_id),
+            history_action(&kind),
+            history_service::diff(Some(&step.after), Some(&after)),
+        )
+        .await?;
+    }
+    if let Err(err) = tx.commit().await {
+        if !position_service::is_position_conflict(&err) {
+            return Err(err.into());
+        }
+        sqlx::query("DELETE FROM todo_undo_operations WHERE id = $1")
+            .bind(operation_id)
+            .execute(&state.db)
+            .await?;
+        return Err(changed_since());
+    }
+
+    let mut todos = Vec::with_capacity(steps.len());
+    for step in steps.iter().filter(|step| !step.before.is_null()) {
+        match todo_service::get_todo(state, user_id, step.todo_id).await {
+            Ok(todo) => todos.push(todo),
+            Err(AppError::NotFound) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    match kind.as_str() {
+        "reorder" => {
+            todo_service::broadcast_todo_event(state, user_id, "todo_reordered", None, None).await
+        }
+        "delete" => {
+            for todo in &todos {
+                todo_service::broadcast_todo_event(
+                    state,
+                    user_id,
+                    "todo_restored",
+                    Some(todo),
+                    None,
+                )
+                .await;
+            }
+        }
+        _ => {
+            for todo in &todos {
+                todo_service::broadcast_todo_event(
+                    state,
+                    user_id,
+                    "todo_updated",
+                    Some(todo),
+                    None,
+                )
+                .await;
+            }
+        }
+    }
+
+    for todo in &removed {
+        todo_service::broadcast_todo_event(
+            state,
+            user_id,
+            "todo_purged",
+            Some(todo),
+            Some(todo.id),
+        )
+        .await;
+    }
+
+    Ok(UndoResponse {
+        operation: kind,
+        todos,
+    })
+}
@@ -1,4 +1,40 @@
-use crate::{error::AppError, models::auth::UserResponse, state::AppState};
+use chrono_tz::Tz;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        auth::UserResponse,
+        user::{UpdateUserPreferencesRequest, UserPreferencesResponse},
+    },
+    services::todo_schedule,
+    state::AppState,
+};
+
+const MAX_REMINDER_LEADS: usize = 5;
+const MAX_REMINDER_LEAD_MINUTES: i32 = 7 * 24 * 60;
+
+/// Deduplicates lead times and orders them from the earliest reminder to the latest.
+fn normalize_reminder_leads(mut leads: Vec<i32>) -> Result<Vec<i32>, AppError> {
+    if leads
+        .iter()
+        .any(|lead| !(1..=MAX_REMINDER_LEAD_MINUTES).contains(lead))
+    {
+        return Err(AppError::BadRequest(format!(
+            "reminder_lead_minutes must be between 1 and {MAX_REMINDER_LEAD_MINUTES}"
+        )));
+    }
+
+    leads.sort_unstable_by(|a, b| b.cmp(a));
+    leads.dedup();
+    if leads.len() > MAX_REMINDER_LEADS {
+        return Err(AppError::BadRequest(format!(
+            "at most {MAX_REMINDER_LEADS} reminder lead times are allowed"
+        )));
+    }
+
+    Ok(leads)
+}
 
 pub async fn list_users(state: &AppState) -> Result<Vec<UserResponse>, AppError> {
     let users =
@@ -8,3 +44,86 @@ pub async fn list_users(state: &AppState) -> Result<Vec<UserResponse>, AppError>
 
     Ok(users)
 }
+
+pub async fn get_preferences(
+    state: &AppState,
+    user_id: Uuid,
+) -> Result<UserPreferencesResponse, AppError> {
+    let preferences = sqlx::query_as::<_, UserPreferencesResponse>(
+        "SELECT time_zone, reminder_lead_minutes FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(preferences)
+}
+
+pub async fn update_preferences(
+    state: &AppState,
+    user_id: Uuid,
+    payload: UpdateUserPreferencesRequest,
+) -> Result<UserPreferencesResponse, AppError> {
+    if payload.time_zone.is_none() && payload.reminder_lead_minutes.is_none() {
+        return Err(AppError::BadRequest("nothing to update".to_string()));
+    }
+    let time_zone = payload
+        .time_zone
+        .as_deref()
+        .map(todo_schedule::parse_time_zone)
+        .transpose()?;
+    let reminder_lead_minutes = payload
+        .reminder_lead_minutes
+        .map(normalize_reminder_leads)
+        .transpose()?;
+
+    let preferences = sqlx::query_as::<_, UserPreferencesResponse>(
+        "UPDATE users SET time_zone = COALESCE($1, time_zone), reminder_lead_minutes = COALESCE($2, reminder_lead_minutes) WHERE id = $3 RETURNING time_zone, reminder_lead_minutes",
+    )
+    .bind(time_zone.map(|time_zone| time_zone.name()))
+    .bind(reminder_lead_minutes)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(preferences)
+}
+
+/// The user's time zone for due-date windows, falling back to UTC.
+pub async fn time_zone(state: &AppState, user_id: Uuid) -> Result<Tz, AppError> {
+    let stored = sqlx::query_scalar::<_, String>("SELECT time_zone FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await?;
+
+    Ok(stored
+        .and_then(|name| name.parse::<Tz>().ok())
+        .unwrap_or(Tz::UTC))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reminder_leads_are_sorted_and_deduplicated() {
+        assert_eq!(
+            normalize_reminder_leads(vec![15, 1440, 60, 15]).expect("leads"),
+            vec![1440, 60, 15]
+        );
+        assert!(
+            normalize_reminder_leads(Vec::new())
+                .expect("leads")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn reminder_leads_are_bounded() {
+        assert!(normalize_reminder_leads(vec![0]).is_err());
+        assert!(normalize_reminder_leads(vec![MAX_REMINDER_LEAD_MINUTES + 1]).is_err());
+        assert!(normalize_reminder_leads(vec![1, 2, 3, 4, 5, 6]).is_err());
+    }
+}
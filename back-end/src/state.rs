@@ -1,21 +1,36 @@
-use std::num::NonZeroU32;
+use std::{num::NonZeroU32, path::PathBuf, sync::Arc};
 
 use axum::http::HeaderValue;
+use axum_extra::extract::cookie::SameSite;
+use ipnet::IpNet;
 use sqlx::{Pool, Postgres};
 
-use crate::services::todo_realtime_service::TodoRealtimeHub;
+use crate::{
+    client_ip,
+    services::{
+        attachment_storage::{AttachmentStorage, LocalStorage, S3Config, S3Storage},
+        todo_realtime_service::TodoRealtimeHub,
+    },
+};
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: Pool<Postgres>,
     pub jwt: JwtConfig,
+    pub password: PasswordConfig,
+    pub password_policy: PasswordPolicyConfig,
     pub email: EmailConfig,
     pub ollama: OllamaConfig,
     pub cors_allowed_origins: Vec<HeaderValue>,
     pub rate_limit_per_second: NonZeroU32,
     pub rate_limit_burst: NonZeroU32,
-    pub refresh_cookie_name: String,
-    pub refresh_cookie_secure: bool,
+    pub trusted_proxies: Vec<IpNet>,
+    pub refresh_cookie: RefreshCookieConfig,
+    pub reminders: ReminderConfig,
+    pub todos: TodoConfig,
+    pub trash: TrashConfig,
+    pub attachments: AttachmentConfig,
+    pub attachment_storage: Arc<dyn AttachmentStorage>,
     pub todo_realtime_hub: TodoRealtimeHub,
 }
 
@@ -24,6 +39,125 @@ pub struct JwtConfig {
     pub secret: String,
     pub access_ttl_minutes: i64,
     pub refresh_ttl_days: i64,
+    pub impersonation_ttl_minutes: i64,
+}
+
+#[derive(Clone)]
+pub struct PasswordConfig {
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub pepper: Option<String>,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            argon2_memory_kib: argon2::Params::DEFAULT_M_COST,
+            argon2_iterations: argon2::Params::DEFAULT_T_COST,
+            argon2_parallelism: argon2::Params::DEFAULT_P_COST,
+            pepper: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PasswordPolicyConfig {
+    pub min_length: usize,
+    pub min_score: u8,
+    pub reject_email: bool,
+    pub breached_corpus_dir: Option<PathBuf>,
+}
+
+impl Default for PasswordPolicyConfig {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            min_score: 2,
+            reject_email: true,
+            breached_corpus_dir: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RefreshCookieConfig {
+    pub name: String,
+    pub secure: bool,
+    pub same_site: SameSite,
+    pub path: String,
+    pub domain: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct ReminderConfig {
+    pub enabled: bool,
+    pub poll_interval_seconds: u64,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_seconds: 60,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TodoConfig {
+    /// Reject moving a todo to `done` while it has open subtasks.
+    pub require_subtasks_done: bool,
+    /// Reject moving a todo to `in_progress` while an open todo blocks it.
+    pub guard_blocked_start: bool,
+    /// How often columns with crowded positions are renumbered.
+    pub rebalance_interval_seconds: u64,
+}
+
+impl Default for TodoConfig {
+    fn default() -> Self {
+        Self {
+            require_subtasks_done: false,
+            guard_blocked_start: false,
+            rebalance_interval_seconds: 3600,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TrashConfig {
+    /// Days a deleted todo stays restorable before it is purged.
+    pub retention_days: u64,
+    pub purge_interval_seconds: u64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 30,
+            purge_interval_seconds: 3600,
+        }
+    }
+}
+
+const DEFAULT_ATTACHMENT_TYPES: &str = "image/png,image/jpeg,image/gif,image/webp,application/pdf,text/plain,text/csv,application/json,application/zip,application/gzip";
+
+#[derive(Clone)]
+pub struct AttachmentConfig {
+    pub max_bytes: usize,
+    /// Exact MIME types or `type/*` wildcards.
+    pub allowed_types: Vec<String>,
+    pub cleanup_interval_seconds: u64,
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: 10 * 1024 * 1024,
+            allowed_types: parse_list(DEFAULT_ATTACHMENT_TYPES),
+            cleanup_interval_seconds: 300,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -68,6 +202,81 @@ impl AppState {
             return Err("REFRESH_TOKEN_TTL_DAYS must be greater than zero".into());
         }
 
+        let impersonation_ttl_minutes: i64 = std::env::var("IMPERSONATION_TTL_MIN")
+            .unwrap_or_else(|_| "15".to_string())
+            .parse()
+            .map_err(|_| "IMPERSONATION_TTL_MIN must be a positive integer")?;
+        if impersonation_ttl_minutes <= 0 {
+            return Err("IMPERSONATION_TTL_MIN must be greater than zero".into());
+        }
+
+        let argon2_memory_kib = parse_non_zero(
+            "ARGON2_MEMORY_KIB",
+            std::env::var("ARGON2_MEMORY_KIB").ok(),
+            argon2::Params::DEFAULT_M_COST,
+        )?
+        .get();
+        let argon2_iterations = parse_non_zero(
+            "ARGON2_ITERATIONS",
+            std::env::var("ARGON2_ITERATIONS").ok(),
+            argon2::Params::DEFAULT_T_COST,
+        )?
+        .get();
+        let argon2_parallelism = parse_non_zero(
+            "ARGON2_PARALLELISM",
+            std::env::var("ARGON2_PARALLELISM").ok(),
+            argon2::Params::DEFAULT_P_COST,
+        )?
+        .get();
+        argon2::Params::new(
+            argon2_memory_kib,
+            argon2_iterations,
+            argon2_parallelism,
+            None,
+        )
+        .map_err(|err| format!("Invalid Argon2 parameters: {err}"))?;
+        let password_pepper = std::env::var("PASSWORD_PEPPER")
+            .ok()
+            .filter(|value| !value.is_empty());
+        if password_pepper
+            .as_deref()
+            .is_some_and(|pepper| pepper.len() < 16)
+        {
+            return Err("PASSWORD_PEPPER must be at least 16 characters".into());
+        }
+
+        let password_min_length = parse_non_zero(
+            "PASSWORD_MIN_LENGTH",
+            std::env::var("PASSWORD_MIN_LENGTH").ok(),
+            8,
+        )?
+        .get() as usize;
+        let password_min_score: u8 = std::env::var("PASSWORD_MIN_SCORE")
+            .unwrap_or_else(|_| "2".to_string())
+            .parse()
+            .map_err(|_| "PASSWORD_MIN_SCORE must be an integer between 0 and 4")?;
+        if password_min_score > 4 {
+            return Err("PASSWORD_MIN_SCORE must be an integer between 0 and 4".into());
+        }
+        let password_reject_email = parse_bool(
+            "PASSWORD_REJECT_EMAIL",
+            std::env::var("PASSWORD_REJECT_EMAIL").ok(),
+            true,
+        )?;
+        let breached_corpus_dir = std::env::var("PASSWORD_BREACHED_CORPUS_DIR")
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from);
+        if let Some(dir) = &breached_corpus_dir {
+            if !dir.is_dir() {
+                return Err(format!(
+                    "PASSWORD_BREACHED_CORPUS_DIR is not a directory: {}",
+                    dir.display()
+                )
+                .into());
+            }
+        }
+
         let smtp_host = require_env("SMTP_HOST")?;
         let smtp_port: u16 = std::env::var("SMTP_PORT")
             .unwrap_or_else(|_| "587".to_string())
@@ -112,6 +321,11 @@ impl AppState {
             40,
         )?;
 
+        let trusted_proxies = client_ip::parse_trusted_proxies(
+            &std::env::var("TRUSTED_PROXIES")
+                .unwrap_or_else(|_| "127.0.0.1/32,::1/128".to_string()),
+        )?;
+
         let refresh_cookie_name =
             std::env::var("REFRESH_COOKIE_NAME").unwrap_or_else(|_| "todo_refresh".to_string());
         let env_mode = std::env::var("APP_ENV").unwrap_or_else(|_| "development".to_string());
@@ -120,6 +334,83 @@ impl AppState {
             std::env::var("REFRESH_COOKIE_SECURE").ok(),
             env_mode == "production",
         )?;
+        let refresh_cookie_same_site = parse_same_site(
+            std::env::var("REFRESH_COOKIE_SAME_SITE").ok(),
+            if env_mode == "production" {
+                SameSite::Strict
+            } else {
+                SameSite::Lax
+            },
+        )?;
+        if refresh_cookie_same_site == SameSite::None && !refresh_cookie_secure {
+            return Err("REFRESH_COOKIE_SAME_SITE=none requires REFRESH_COOKIE_SECURE=true".into());
+        }
+        let refresh_cookie_path =
+            std::env::var("REFRESH_COOKIE_PATH").unwrap_or_else(|_| "/api/auth".to_string());
+        if !refresh_cookie_path.starts_with('/') {
+            return Err("REFRESH_COOKIE_PATH must start with '/'".into());
+        }
+        let refresh_cookie_domain = std::env::var("REFRESH_COOKIE_DOMAIN")
+            .ok()
+            .filter(|value| !value.is_empty());
+
+        let reminders_enabled = parse_bool(
+            "REMINDERS_ENABLED",
+            std::env::var("REMINDERS_ENABLED").ok(),
+            true,
+        )?;
+        let reminder_poll_interval_seconds = parse_u64(
+            "REMINDER_POLL_SECONDS",
+            std::env::var("REMINDER_POLL_SECONDS").ok(),
+            60,
+        )?;
+        let require_subtasks_done = parse_bool(
+            "TODO_REQUIRE_SUBTASKS_DONE",
+            std::env::var("TODO_REQUIRE_SUBTASKS_DONE").ok(),
+            false,
+        )?;
+        let guard_blocked_start = parse_bool(
+            "TODO_GUARD_BLOCKED_START",
+            std::env::var("TODO_GUARD_BLOCKED_START").ok(),
+            false,
+        )?;
+        let todo_rebalance_interval_seconds = parse_u64(
+            "TODO_REBALANCE_SECONDS",
+            std::env::var("TODO_REBALANCE_SECONDS").ok(),
+            3600,
+        )?;
+        let trash_retention_days = parse_u64(
+            "TRASH_RETENTION_DAYS",
+            std::env::var("TRASH_RETENTION_DAYS").ok(),
+            30,
+        )?;
+        let trash_purge_interval_seconds = parse_u64(
+            "TRASH_PURGE_SECONDS",
+            std::env::var("TRASH_PURGE_SECONDS").ok(),
+            3600,
+        )?;
+
+        let attachment_max_bytes = parse_u64(
+            "ATTACHMENT_MAX_BYTES",
+            std::env::var("ATTACHMENT_MAX_BYTES").ok(),
+            10 * 1024 * 1024,
+        )?;
+        if attachment_max_bytes == 0 {
+            return Err("ATTACHMENT_MAX_BYTES must be greater than zero".into());
+        }
+        let attachment_allowed_types = parse_list(
+            &std::env::var("ATTACHMENT_ALLOWED_TYPES")
+                .unwrap_or_else(|_| DEFAULT_ATTACHMENT_TYPES.to_string()),
+        );
+        if attachment_allowed_types.is_empty() {
+            return Err("ATTACHMENT_ALLOWED_TYPES must list at least one type".into());
+        }
+        let attachment_cleanup_interval_seconds = parse_u64(
+            "ATTACHMENT_CLEANUP_SECONDS",
+            std::env::var("ATTACHMENT_CLEANUP_SECONDS").ok(),
+            300,
+        )?;
+        let attachment_storage = parse_attachment_storage()?;
 
         Ok(Self {
             db,
@@ -127,6 +418,19 @@ impl AppState {
                 secret: jwt_secret,
                 access_ttl_minutes,
                 refresh_ttl_days,
+                impersonation_ttl_minutes,
+            },
+            password: PasswordConfig {
+                argon2_memory_kib,
+                argon2_iterations,
+                argon2_parallelism,
+                pepper: password_pepper,
+            },
+            password_policy: PasswordPolicyConfig {
+                min_length: password_min_length,
+                min_score: password_min_score,
+                reject_email: password_reject_email,
+                breached_corpus_dir,
             },
             email: EmailConfig {
                 smtp_host,
@@ -146,13 +450,68 @@ impl AppState {
             cors_allowed_origins,
             rate_limit_per_second,
             rate_limit_burst,
-            refresh_cookie_name,
-            refresh_cookie_secure,
+            trusted_proxies,
+            refresh_cookie: RefreshCookieConfig {
+                name: refresh_cookie_name,
+                secure: refresh_cookie_secure,
+                same_site: refresh_cookie_same_site,
+                path: refresh_cookie_path,
+                domain: refresh_cookie_domain,
+            },
+            reminders: ReminderConfig {
+                enabled: reminders_enabled,
+                poll_interval_seconds: reminder_poll_interval_seconds,
+            },
+            todos: TodoConfig {
+                require_subtasks_done,
+                guard_blocked_start,
+                rebalance_interval_seconds: todo_rebalance_interval_seconds,
+            },
+            trash: TrashConfig {
+                retention_days: trash_retention_days,
+                purge_interval_seconds: trash_purge_interval_seconds,
+            },
+            attachments: AttachmentConfig {
+                max_bytes: attachment_max_bytes as usize,
+                allowed_types: attachment_allowed_types,
+                cleanup_interval_seconds: attachment_cleanup_interval_seconds,
+            },
+            attachment_storage,
             todo_realtime_hub: TodoRealtimeHub::default(),
         })
     }
 }
 
+fn parse_attachment_storage() -> Result<Arc<dyn AttachmentStorage>, Box<dyn std::error::Error>> {
+    let backend = std::env::var("ATTACHMENT_STORAGE").unwrap_or_else(|_| "local".to_string());
+    match backend.as_str() {
+        "local" => {
+            let dir = std::env::var("ATTACHMENT_DIR")
+                .unwrap_or_else(|_| "./data/attachments".to_string());
+            Ok(Arc::new(LocalStorage::new(dir)))
+        }
+        "s3" => {
+            let storage = S3Storage::new(S3Config {
+                endpoint: require_env("S3_ENDPOINT")?,
+                bucket: require_env("S3_BUCKET")?,
+                region: std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                access_key_id: require_env("S3_ACCESS_KEY_ID")?,
+                secret_access_key: require_env("S3_SECRET_ACCESS_KEY")?,
+                path_style: parse_bool("S3_PATH_STYLE", std::env::var("S3_PATH_STYLE").ok(), true)?,
+            })?;
+            Ok(Arc::new(storage))
+        }
+        _ => Err("ATTACHMENT_STORAGE must be local or s3".into()),
+    }
+}
+
+fn parse_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
 fn require_env(name: &str) -> Result<String, Box<dyn std::error::Error>> {
     std::env::var(name).map_err(|_| format!("Missing required env var: {name}").into())
 }
@@ -232,3 +591,20 @@ fn parse_bool(
         _ => Err(format!("{name} must be a boolean").into()),
     }
 }
+
+fn parse_same_site(
+    raw: Option<String>,
+    default: SameSite,
+) -> Result<SameSite, Box<dyn std::error::Error>> {
+    let value = match raw {
+        Some(val) => val,
+        None => return Ok(default),
+    };
+
+    match value.to_lowercase().as_str() {
+        "strict" => Ok(SameSite::Strict),
+        "lax" => Ok(SameSite::Lax),
+        "none" => Ok(SameSite::None),
+        _ => Err("REFRESH_COOKIE_SAME_SITE must be one of strict, lax or none".into()),
+    }
+}
@@ -1,71 +1,21 @@
-use std::num::NonZeroU32;
+mod common;
 
 use todo_api::{
+    client_ip::SessionMetadata,
     error::AppError,
+    locale::Language,
     models::auth::{LoginRequest, RegisterRequest},
-    services::{auth_service, todo_realtime_service::TodoRealtimeHub},
-    state::{AppState, EmailConfig, JwtConfig, OllamaConfig},
+    services::auth_service,
 };
 use uuid::Uuid;
 
 #[tokio::test]
 async fn register_login_refresh_logout_flow() -> Result<(), AppError> {
-    let run_integration = std::env::var("RUN_INTEGRATION_TESTS")
-        .ok()
-        .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
-        .unwrap_or(false);
-
-    if !run_integration {
-        eprintln!("Skipping integration test: set RUN_INTEGRATION_TESTS=1 to enable.");
+    let Some(state) = common::integration_state().await? else {
         return Ok(());
-    }
-
-    let database_url = std::env::var("DATABASE_URL").map_err(|err| {
-        eprintln!("DATABASE_URL not set: {err}");
-        AppError::Internal
-    })?;
-    let pool = sqlx::postgres::PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await?;
-    sqlx::migrate!("./migrations")
-        .run(&pool)
-        .await
-        .map_err(|err| {
-            eprintln!("migration failed: {err}");
-            AppError::Internal
-        })?;
-
-    let state = AppState {
-        db: pool,
-        jwt: JwtConfig {
-            secret: "test-secret-which-is-long-enough-1234567890".into(),
-            access_ttl_minutes: 15,
-            refresh_ttl_days: 7,
-        },
-        email: EmailConfig {
-            smtp_host: "smtp.example.com".into(),
-            smtp_port: 587,
-            smtp_username: "user".into(),
-            smtp_password: "pass".into(),
-            from_email: "no-reply@example.com".into(),
-            from_name: "Todo App".into(),
-            reset_url_base: "http://localhost:5173".into(),
-            reset_ttl_minutes: 30,
-        },
-        ollama: OllamaConfig {
-            base_url: "http://localhost:11434".into(),
-            default_model: "llama3.1".into(),
-            timeout_seconds: 60,
-        },
-        cors_allowed_origins: Vec::new(),
-        rate_limit_per_second: NonZeroU32::new(10).unwrap(),
-        rate_limit_burst: NonZeroU32::new(20).unwrap(),
-        refresh_cookie_name: "todo_refresh".into(),
-        refresh_cookie_secure: false,
-        todo_realtime_hub: TodoRealtimeHub::default(),
     };
 
+    let session = SessionMetadata::default();
     let email = format!("user+{}@example.com", Uuid::new_v4());
     let password = "P@ssword123";
 
@@ -75,6 +25,8 @@ async fn register_login_refresh_logout_flow() -> Result<(), AppError> {
             email: email.clone(),
             password: password.into(),
         },
+        &session,
+        Language::En,
     )
     .await?;
 
@@ -89,6 +41,7 @@ async fn register_login_refresh_logout_flow() -> Result<(), AppError> {
             email: email.clone(),
             password: password.into(),
         },
+        &session,
     )
     .await?;
 
@@ -97,7 +50,7 @@ async fn register_login_refresh_logout_flow() -> Result<(), AppError> {
     assert!(!login_response.access_token.is_empty());
     assert!(!login_refresh_token.is_empty());
 
-    let refreshed = auth_service::refresh(&state, &login_refresh_token).await?;
+    let refreshed = auth_service::refresh(&state, &login_refresh_token, &session).await?;
 
     let (refreshed, refreshed_token) = refreshed;
     assert_eq!(refreshed.user.email, email);
@@ -106,7 +59,7 @@ async fn register_login_refresh_logout_flow() -> Result<(), AppError> {
 
     auth_service::logout(&state, &refreshed_token).await?;
 
-    let reuse_after_logout = auth_service::refresh(&state, &refreshed_token).await;
+    let reuse_after_logout = auth_service::refresh(&state, &refreshed_token, &session).await;
 
     assert!(matches!(reuse_after_logout, Err(AppError::Unauthorized)));
 
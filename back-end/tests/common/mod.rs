@@ -0,0 +1,87 @@
+use std::{num::NonZeroU32, sync::Arc};
+
+use axum_extra::extract::cookie::SameSite;
+use todo_api::{
+    error::AppError,
+    services::{attachment_storage::LocalStorage, todo_realtime_service::TodoRealtimeHub},
+    state::{
+        AppState, AttachmentConfig, EmailConfig, JwtConfig, OllamaConfig, PasswordConfig,
+        PasswordPolicyConfig, RefreshCookieConfig, ReminderConfig, TodoConfig, TrashConfig,
+    },
+};
+
+/// State backed by `DATABASE_URL`, or `None` unless `RUN_INTEGRATION_TESTS`
+/// is set.
+pub async fn integration_state() -> Result<Option<AppState>, AppError> {
+    let run_integration = std::env::var("RUN_INTEGRATION_TESTS")
+        .ok()
+        .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+
+    if !run_integration {
+        eprintln!("Skipping integration test: set RUN_INTEGRATION_TESTS=1 to enable.");
+        return Ok(None);
+    }
+
+    let database_url = std::env::var("DATABASE_URL").map_err(|err| {
+        eprintln!("DATABASE_URL not set: {err}");
+        AppError::Internal
+    })?;
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(10)
+        .connect(&database_url)
+        .await?;
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .map_err(|err| {
+            eprintln!("migration failed: {err}");
+            AppError::Internal
+        })?;
+
+    Ok(Some(AppState {
+        db: pool,
+        jwt: JwtConfig {
+            secret: "test-secret-which-is-long-enough-1234567890".into(),
+            access_ttl_minutes: 15,
+            refresh_ttl_days: 7,
+            impersonation_ttl_minutes: 15,
+        },
+        password: PasswordConfig::default(),
+        password_policy: PasswordPolicyConfig::default(),
+        email: EmailConfig {
+            smtp_host: "smtp.example.com".into(),
+            smtp_port: 587,
+            smtp_username: "user".into(),
+            smtp_password: "pass".into(),
+            from_email: "no-reply@example.com".into(),
+            from_name: "Todo App".into(),
+            reset_url_base: "http://localhost:5173".into(),
+            reset_ttl_minutes: 30,
+        },
+        ollama: OllamaConfig {
+            base_url: "http://localhost:11434".into(),
+            default_model: "llama3.1".into(),
+            timeout_seconds: 60,
+        },
+        cors_allowed_origins: Vec::new(),
+        rate_limit_per_second: NonZeroU32::new(10).unwrap(),
+        rate_limit_burst: NonZeroU32::new(20).unwrap(),
+        trusted_proxies: Vec::new(),
+        refresh_cookie: RefreshCookieConfig {
+            name: "todo_refresh".into(),
+            secure: false,
+            same_site: SameSite::Lax,
+            path: "/api/auth".into(),
+            domain: None,
+        },
+        reminders: ReminderConfig::default(),
+        todos: TodoConfig::default(),
+        trash: TrashConfig::default(),
+        attachments: AttachmentConfig::default(),
+        attachment_storage: Arc::new(LocalStorage::new(
+            std::env::temp_dir().join("todo-attachments-test"),
+        )),
+        todo_realtime_hub: TodoRealtimeHub::default(),
+    }))
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use todo_api::{
+    client_ip::SessionMetadata,
+    error::AppError,
+    locale::Language,
+    models::{
+        auth::RegisterRequest,
+        todo::{CreateTodoRequest, MoveTodoRequest, PatchTodoRequest},
+    },
+    services::{auth_service, position_service, todo_service},
+};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn moves_in_a_crowded_column_rebalance_without_deadlocks() -> Result<(), AppError> {
+    let Some(state) = common::integration_state().await? else {
+        return Ok(());
+    };
+
+    let (registered, _) = auth_service::register(
+        &state,
+        RegisterRequest {
+            email: format!("board+{}@example.com", Uuid::new_v4()),
+            password: "P@ssword123".into(),
+        },
+        &SessionMetadata::default(),
+        Language::En,
+    )
+    .await?;
+    let user_id = registered.user.id;
+
+    let mut ids = Vec::new();
+    for index in 0..8 {
+        let payload: CreateTodoRequest =
+            serde_json::from_value(serde_json::json!({ "title": format!("card {index}") }))
+                .expect("create payload");
+        ids.push(
+            todo_service::create_todo(&state, user_id, payload)
+                .await?
+                .id,
+        );
+    }
+    // Squeeze the column so no todo fits between neighbours any more.
+    sqlx::query(
+        "UPDATE todos SET position = ordered.rank * 1e-12 FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY position) AS rank FROM todos WHERE id = ANY($1)) ordered WHERE todos.id = ordered.id",
+    )
+    .bind(&ids)
+    .execute(&state.db)
+    .await?;
+
+    // Everyone drags a card right after the first one at the same time.
+    let anchor = ids[0];
+    let moves = ids[2..].iter().map(|todo_id| {
+        let state = state.clone();
+        let todo_id = *todo_id;
+        tokio::spawn(async move {
+            let payload = MoveTodoRequest {
+                after_id: Some(anchor),
+                ..MoveTodoRequest::default()
+            };
+            todo_service::move_todo(&state, user_id, todo_id, payload, None).await
+        })
+    });
+    let mut moved = 0;
+    for result in futures_util::future::join_all(moves).await {
+        match result.expect("move task") {
+            Ok(_) => moved += 1,
+            Err(AppError::Conflict(_)) => {}
+            Err(err) => panic!("move failed: {err:?}"),
+        }
+    }
+    assert!(moved > 0, "no move went through");
+
+    let positions = sqlx::query_scalar::<_, f64>(
+        "SELECT position FROM todos WHERE id = ANY($1) ORDER BY position",
+    )
+    .bind(&ids)
+    .fetch_all(&state.db)
+    .await?;
+    assert_eq!(positions.len(), ids.len());
+    assert!(
+        positions.windows(2).all(|pair| pair[1] - pair[0] >= 1e-9),
+        "column was not rebalanced: {positions:?}"
+    );
+    // The second card never moved itself, so only the rebalance recorded it.
+    let rebalanced = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM todo_history WHERE todo_id = $1 AND action = 'reordered' AND actor_id = $2 AND changes ? 'position')",
+    )
+    .bind(ids[1])
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+    assert!(rebalanced, "the rebalance left no history entry");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn crowding_a_column_queues_it_for_the_rebalance_job() -> Result<(), AppError> {
+    let Some(state) = common::integration_state().await? else {
+        return Ok(());
+    };
+
+    let (registered, _) = auth_service::register(
+        &state,
+        RegisterRequest {
+            email: format!("queue+{}@example.com", Uuid::new_v4()),
+            password: "P@ssword123".into(),
+        },
+        &SessionMetadata::default(),
+        Language::En,
+    )
+    .await?;
+    let user_id = registered.user.id;
+
+    let mut todos = Vec::new();
+    for index in 0..2 {
+        let payload: CreateTodoRequest =
+            serde_json::from_value(serde_json::json!({ "title": format!("card {index}") }))
+                .expect("create payload");
+        todos.push(todo_service::create_todo(&state, user_id, payload).await?);
+    }
+    // A client-chosen position right next to the first card.
+    let payload: PatchTodoRequest =
+        serde_json::from_value(serde_json::json!({ "position": todos[0].position + 1e-8 }))
+            .expect("patch payload");
+    todo_service::update_todo(&state, user_id, todos[1].id, payload, None).await?;
+
+    let queued = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM todo_crowded_columns WHERE assignee_id IS NOT DISTINCT FROM $1 AND status = $2)",
+    )
+    .bind(todos[0].assignee_id)
+    .bind(&todos[0].status)
+    .fetch_one(&state.db)
+    .await?;
+    assert!(queued, "the crowded column was not queued");
+
+    position_service::run_rebalance_once(&state).await?;
+    let positions = sqlx::query_scalar::<_, f64>(
+        "SELECT position FROM todos WHERE id = ANY($1) ORDER BY position",
+    )
+    .bind(todos.iter().map(|todo| todo.id).collect::<Vec<_>>())
+    .fetch_all(&state.db)
+    .await?;
+    assert!(
+        positions[1] - positions[0] >= 1.0,
+        "not renumbered: {positions:?}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+import { api } from '../services/api'
+import type { Todo } from '../types/todo'
+
+const page = (todos: Partial<Todo>[], nextCursor?: string) =>
+  new Response(JSON.stringify(todos), {
+    status: 200,
+    headers: nextCursor ? { 'x-next-cursor': nextCursor } : {},
+  })
+
+describe('api.listTodos', () => {
+  afterEach(() => {
+    vi.unstubAllGlobals()
+  })
+
+  it('follows the next cursor until the last page', async () => {
+    const fetchMock = vi
+      .fn()
+      .mockResolvedValueOnce(page([{ id: '1' }, { id: '2' }], 'next-page'))
+      .mockResolvedValueOnce(page([{ id: '3' }]))
+    vi.stubGlobal('fetch', fetchMock)
+
+    const todos = await api.listTodos()
+
+    expect(todos.map((todo) => todo.id)).toEqual(['1', '2', '3'])
+    expect(fetchMock).toHaveBeenCalledTimes(2)
+    expect(fetchMock.mock.calls[0][0]).toContain('/todos?limit=500')
+    expect(fetchMock.mock.calls[1][0]).toContain('cursor=next-page')
+  })
+})
@@ -115,7 +115,7 @@ const refreshSession = async (): Promise<AuthResponse> => {
   return refreshPromise
 }
 
-const request = async <T>(path: string, options?: RequestInit): Promise<T> => {
+const send = async (path: string, options?: RequestInit): Promise<Response> => {
   const accessToken = appState.auth.accessToken.get()
   const response = await fetch(`${API_BASE_URL}${path}`, {
     credentials: 'include',
@@ -137,10 +137,7 @@ const request = async <T>(path: string, options?: RequestInit): Promise<T> => {
           ...options,
         })
         if (retryResponse.ok) {
-          if (retryResponse.status === 204) {
-            return undefined as T
-          }
-          return (await retryResponse.json()) as T
+          return retryResponse
         }
       } catch {
         authActions.logout()
@@ -151,6 +148,10 @@ const request = async <T>(path: string, options?: RequestInit): Promise<T> => {
     throw new Error(normalizeErrorMessage(message, response.status, path))
   }
 
+  return response
+}
+
+const readBody = async <T>(response: Response): Promise<T> => {
   if (response.status === 204) {
     return undefined as T
   }
@@ -158,6 +159,27 @@ const request = async <T>(path: string, options?: RequestInit): Promise<T> => {
   return (await response.json()) as T
 }
 
+const request = async <T>(path: string, options?: RequestInit): Promise<T> =>
+  readBody<T>(await send(path, options))
+
+const TODO_PAGE_SIZE = 500
+
+// The todo list is paged; follow `X-Next-Cursor` until the last page.
+const requestAllTodos = async (): Promise<Todo[]> => {
+  const todos: Todo[] = []
+  let cursor: string | null = null
+  do {
+    const query = new URLSearchParams({ limit: String(TODO_PAGE_SIZE) })
+    if (cursor) {
+      query.set('cursor', cursor)
+    }
+    const response = await send(`/todos?${query.toString()}`)
+    todos.push(...(await readBody<Todo[]>(response)))
+    cursor = response.headers.get('x-next-cursor')
+  } while (cursor)
+  return todos
+}
+
 export const api = {
   register(payload: RegisterRequest) {
     return request<AuthResponse>('/auth/register', {
@@ -194,7 +216,7 @@ export const api = {
     })
   },
   listTodos() {
-    return request<Todo[]>('/todos')
+    return requestAllTodos()
   },
   listUsers() {
     return request<UserResponse[]>('/users')